@@ -9,11 +9,12 @@ pub struct Model {
     pub user_id: Uuid,
     #[sea_orm(primary_key, auto_increment = false)]
     pub idempotency_key: String,
-    pub response_status_code: i16,
-    pub response_headers: Vec<String>,
-    #[sea_orm(column_type = "VarBinary(StringLen::None)")]
-    pub response_body: Vec<u8>,
+    pub response_status_code: Option<i16>,
+    #[sea_orm(column_type = "VarBinary(StringLen::None)", nullable)]
+    pub response_body: Option<Vec<u8>>,
     pub created_at: DateTimeWithTimeZone,
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub response_headers: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -3,6 +3,13 @@
 pub mod prelude;
 
 pub mod idempotency;
+pub mod issue_delivery_queue;
+pub mod list_memberships;
+pub mod lists;
+pub mod newsletter_issue_lists;
+pub mod newsletter_issues;
 pub mod subscription_tokens;
 pub mod subscriptions;
+pub mod suppressions;
+pub mod tracking_events;
 pub mod users;
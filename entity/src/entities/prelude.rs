@@ -1,6 +1,13 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.4
 
 pub use super::idempotency::Entity as Idempotency;
+pub use super::issue_delivery_queue::Entity as IssueDeliveryQueue;
+pub use super::list_memberships::Entity as ListMemberships;
+pub use super::lists::Entity as Lists;
+pub use super::newsletter_issue_lists::Entity as NewsletterIssueLists;
+pub use super::newsletter_issues::Entity as NewsletterIssues;
 pub use super::subscription_tokens::Entity as SubscriptionTokens;
 pub use super::subscriptions::Entity as Subscriptions;
+pub use super::suppressions::Entity as Suppressions;
+pub use super::tracking_events::Entity as TrackingEvents;
 pub use super::users::Entity as Users;
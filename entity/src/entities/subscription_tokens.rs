@@ -8,10 +8,19 @@ pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub subscription_token: String,
     pub subscriber_id: Uuid,
+    pub list_id: Uuid,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::lists::Entity",
+        from = "Column::ListId",
+        to = "super::lists::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Lists,
     #[sea_orm(
         belongs_to = "super::subscriptions::Entity",
         from = "Column::SubscriberId",
@@ -22,6 +31,12 @@ pub enum Relation {
     Subscriptions,
 }
 
+impl Related<super::lists::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Lists.def()
+    }
+}
+
 impl Related<super::subscriptions::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Subscriptions.def()
@@ -14,12 +14,32 @@ pub struct Model {
     pub subscribed_at: DateTimeWithTimeZone,
     #[sea_orm(column_type = "Text")]
     pub status: String,
+    #[sea_orm(column_type = "Text")]
+    pub locale: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::issue_delivery_queue::Entity")]
+    IssueDeliveryQueue,
+    #[sea_orm(has_many = "super::list_memberships::Entity")]
+    ListMemberships,
     #[sea_orm(has_many = "super::subscription_tokens::Entity")]
     SubscriptionTokens,
+    #[sea_orm(has_many = "super::tracking_events::Entity")]
+    TrackingEvents,
+}
+
+impl Related<super::issue_delivery_queue::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::IssueDeliveryQueue.def()
+    }
+}
+
+impl Related<super::list_memberships::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::ListMemberships.def()
+    }
 }
 
 impl Related<super::subscription_tokens::Entity> for Entity {
@@ -28,4 +48,10 @@ impl Related<super::subscription_tokens::Entity> for Entity {
     }
 }
 
+impl Related<super::tracking_events::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TrackingEvents.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -10,9 +10,21 @@ pub struct Model {
     #[sea_orm(unique)]
     pub username: String,
     pub password_hash: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub email: Option<String>,
+    pub is_owner: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::suppressions::Entity")]
+    Suppressions,
+}
+
+impl Related<super::suppressions::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Suppressions.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
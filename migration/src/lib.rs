@@ -5,6 +5,19 @@ mod m20250218_152412_create_subscription_tokens_table;
 mod m20250223_072332_create_users_table;
 mod m20250419_075152_add_seed_user;
 mod m20250420_132533_create_idempotency_table;
+mod m20251019_080000_create_lists_tables;
+mod m20251019_090000_create_newsletter_issues_table;
+mod m20251019_100000_add_email_to_users;
+mod m20251019_110000_add_archive_columns_to_newsletter_issues;
+mod m20251019_120000_create_tracking_events_table;
+mod m20251019_130000_create_suppressions_table;
+mod m20251019_140000_add_manual_entries_to_suppressions;
+mod m20251019_150000_add_locale_to_subscriptions;
+mod m20251019_160000_add_trace_context_to_newsletter_issues;
+mod m20251019_170000_make_idempotency_responses_nullable;
+mod m20251019_180000_add_owner_flag_to_users;
+mod m20251019_190000_create_issue_delivery_queue_table;
+mod m20251019_200000_add_markdown_content_to_newsletter_issues;
 
 pub struct Migrator;
 
@@ -17,6 +30,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20250223_072332_create_users_table::Migration),
             Box::new(m20250419_075152_add_seed_user::Migration),
             Box::new(m20250420_132533_create_idempotency_table::Migration),
+            Box::new(m20251019_080000_create_lists_tables::Migration),
+            Box::new(m20251019_090000_create_newsletter_issues_table::Migration),
+            Box::new(m20251019_100000_add_email_to_users::Migration),
+            Box::new(m20251019_110000_add_archive_columns_to_newsletter_issues::Migration),
+            Box::new(m20251019_120000_create_tracking_events_table::Migration),
+            Box::new(m20251019_130000_create_suppressions_table::Migration),
+            Box::new(m20251019_140000_add_manual_entries_to_suppressions::Migration),
+            Box::new(m20251019_150000_add_locale_to_subscriptions::Migration),
+            Box::new(m20251019_160000_add_trace_context_to_newsletter_issues::Migration),
+            Box::new(m20251019_170000_make_idempotency_responses_nullable::Migration),
+            Box::new(m20251019_180000_add_owner_flag_to_users::Migration),
+            Box::new(m20251019_190000_create_issue_delivery_queue_table::Migration),
+            Box::new(m20251019_200000_add_markdown_content_to_newsletter_issues::Migration),
         ]
     }
 }
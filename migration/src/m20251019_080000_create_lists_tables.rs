@@ -0,0 +1,147 @@
+use sea_orm_migration::{prelude::*, schema::*, sea_orm::prelude::Uuid};
+
+use crate::m20250116_212701_create_subscriptions_table::Subscriptions;
+use crate::m20250218_152412_create_subscription_tokens_table::SubscriptionTokens;
+
+/// The list every existing subscriber is moved into.
+/// Its slug is also the fallback when a form does not name a list.
+const DEFAULT_LIST_ID: &str = "6f1c2a4e-3b5d-4c8e-9a7f-0d2e4b6c8a10";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Lists::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(Lists::Id))
+                    .col(text_uniq(Lists::Slug))
+                    .col(text(Lists::Name))
+                    .col(timestamp_with_time_zone(Lists::CreatedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(ListMemberships::Table)
+                    .if_not_exists()
+                    .col(uuid(ListMemberships::ListId).not_null())
+                    .col(uuid(ListMemberships::SubscriberId).not_null())
+                    .col(text(ListMemberships::Status))
+                    .col(timestamp_with_time_zone(ListMemberships::CreatedAt))
+                    .primary_key(
+                        Index::create()
+                            .name("pk_list_memberships")
+                            .col(ListMemberships::ListId)
+                            .col(ListMemberships::SubscriberId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_list_memberships_list_id")
+                            .from(ListMemberships::Table, ListMemberships::ListId)
+                            .to(Lists::Table, Lists::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_list_memberships_subscriber_id")
+                            .from(ListMemberships::Table, ListMemberships::SubscriberId)
+                            .to(Subscriptions::Table, Subscriptions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Seed the default list and move every existing subscriber into it,
+        // keeping the status they had on the single list.
+        let default_list_id = Uuid::parse_str(DEFAULT_LIST_ID).unwrap();
+        let insert = Query::insert()
+            .into_table(Lists::Table)
+            .columns([Lists::Id, Lists::Slug, Lists::Name, Lists::CreatedAt])
+            .values_panic([
+                default_list_id.into(),
+                "newsletter".into(),
+                "Newsletter".into(),
+                Expr::current_timestamp().into(),
+            ])
+            .to_owned();
+        manager.exec_stmt(insert).await?;
+
+        let db = manager.get_connection();
+        db.execute_unprepared(&format!(
+            "INSERT INTO list_memberships (list_id, subscriber_id, status, created_at) \
+             SELECT '{DEFAULT_LIST_ID}', id, status, subscribed_at FROM subscriptions"
+        ))
+        .await?;
+
+        // Confirmation tokens are now issued for a specific list.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SubscriptionTokens::Table)
+                    .add_column(uuid_null(Alias::new("list_id")))
+                    .to_owned(),
+            )
+            .await?;
+        db.execute_unprepared(&format!(
+            "UPDATE subscription_tokens SET list_id = '{DEFAULT_LIST_ID}'"
+        ))
+        .await?;
+        db.execute_unprepared("ALTER TABLE subscription_tokens ALTER COLUMN list_id SET NOT NULL")
+            .await?;
+        manager
+            .create_foreign_key(
+                ForeignKey::create()
+                    .name("fk_subscription_tokens_list_id")
+                    .from(SubscriptionTokens::Table, Alias::new("list_id"))
+                    .to(Lists::Table, Lists::Id)
+                    .on_delete(ForeignKeyAction::Cascade)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SubscriptionTokens::Table)
+                    .drop_column(Alias::new("list_id"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(ListMemberships::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Lists::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Lists {
+    Table,
+    Id,
+    Slug,
+    Name,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum ListMemberships {
+    Table,
+    ListId,
+    SubscriberId,
+    Status,
+    CreatedAt,
+}
@@ -0,0 +1,99 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20251019_080000_create_lists_tables::Lists;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(NewsletterIssues::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(NewsletterIssues::Id))
+                    .col(text(NewsletterIssues::Title))
+                    .col(text(NewsletterIssues::TextContent))
+                    .col(text(NewsletterIssues::HtmlContent))
+                    .col(text(NewsletterIssues::State))
+                    .col(timestamp_with_time_zone_null(NewsletterIssues::SendAt))
+                    .col(timestamp_with_time_zone(NewsletterIssues::CreatedAt))
+                    .col(timestamp_with_time_zone_null(NewsletterIssues::PublishedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_newsletter_issues_state_send_at")
+                    .table(NewsletterIssues::Table)
+                    .col(NewsletterIssues::State)
+                    .col(NewsletterIssues::SendAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(NewsletterIssueLists::Table)
+                    .if_not_exists()
+                    .col(uuid(NewsletterIssueLists::IssueId).not_null())
+                    .col(uuid(NewsletterIssueLists::ListId).not_null())
+                    .primary_key(
+                        Index::create()
+                            .name("pk_newsletter_issue_lists")
+                            .col(NewsletterIssueLists::IssueId)
+                            .col(NewsletterIssueLists::ListId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_newsletter_issue_lists_issue_id")
+                            .from(NewsletterIssueLists::Table, NewsletterIssueLists::IssueId)
+                            .to(NewsletterIssues::Table, NewsletterIssues::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_newsletter_issue_lists_list_id")
+                            .from(NewsletterIssueLists::Table, NewsletterIssueLists::ListId)
+                            .to(Lists::Table, Lists::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(NewsletterIssueLists::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(NewsletterIssues::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum NewsletterIssues {
+    Table,
+    Id,
+    Title,
+    TextContent,
+    HtmlContent,
+    State,
+    SendAt,
+    CreatedAt,
+    PublishedAt,
+}
+
+#[derive(DeriveIden)]
+pub enum NewsletterIssueLists {
+    Table,
+    IssueId,
+    ListId,
+}
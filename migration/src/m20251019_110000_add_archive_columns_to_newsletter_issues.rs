@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20251019_090000_create_newsletter_issues_table::NewsletterIssues;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Published issues are listed in the archive under their slug,
+        // unless an admin hid them.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .add_column(text_null(Alias::new("slug")))
+                    .add_column(boolean(Alias::new("hidden")).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Issues published before the archive existed keep their id as slug.
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            "UPDATE newsletter_issues SET slug = id::text WHERE state = 'published'",
+        )
+        .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_newsletter_issues_slug")
+                    .table(NewsletterIssues::Table)
+                    .col(Alias::new("slug"))
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_newsletter_issues_slug")
+                    .table(NewsletterIssues::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .drop_column(Alias::new("slug"))
+                    .drop_column(Alias::new("hidden"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
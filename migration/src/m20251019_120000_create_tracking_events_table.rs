@@ -0,0 +1,87 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250116_212701_create_subscriptions_table::Subscriptions;
+use crate::m20251019_090000_create_newsletter_issues_table::NewsletterIssues;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Tracking is opt-in for every issue.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .add_column(boolean(Alias::new("tracking_enabled")).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(TrackingEvents::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(TrackingEvents::Id))
+                    .col(uuid(TrackingEvents::IssueId).not_null())
+                    .col(uuid(TrackingEvents::SubscriberId).not_null())
+                    .col(text(TrackingEvents::Kind))
+                    .col(text_null(TrackingEvents::Url))
+                    .col(timestamp_with_time_zone(TrackingEvents::CreatedAt))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tracking_events_issue_id")
+                            .from(TrackingEvents::Table, TrackingEvents::IssueId)
+                            .to(NewsletterIssues::Table, NewsletterIssues::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_tracking_events_subscriber_id")
+                            .from(TrackingEvents::Table, TrackingEvents::SubscriberId)
+                            .to(Subscriptions::Table, Subscriptions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_tracking_events_issue_id_kind")
+                    .table(TrackingEvents::Table)
+                    .col(TrackingEvents::IssueId)
+                    .col(TrackingEvents::Kind)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TrackingEvents::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .drop_column(Alias::new("tracking_enabled"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum TrackingEvents {
+    Table,
+    Id,
+    IssueId,
+    SubscriberId,
+    Kind,
+    Url,
+    CreatedAt,
+}
@@ -0,0 +1,38 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Suppressions::Table)
+                    .if_not_exists()
+                    .col(pk_uuid(Suppressions::Id))
+                    // Stored lowercased, addresses are matched case-insensitively.
+                    .col(text_uniq(Suppressions::Email))
+                    .col(text(Suppressions::Reason))
+                    .col(timestamp_with_time_zone(Suppressions::CreatedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Suppressions::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+pub enum Suppressions {
+    Table,
+    Id,
+    Email,
+    Reason,
+    CreatedAt,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250223_072332_create_users_table::Users;
+use crate::m20251019_130000_create_suppressions_table::Suppressions;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // An entry is either an exact address or a whole domain.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Suppressions::Table)
+                    .rename_column(Suppressions::Email, Alias::new("entry"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Suppressions::Table)
+                    .add_column(text(Alias::new("kind")).default("email"))
+                    // Entries created from provider events have no author.
+                    .add_column(uuid_null(Alias::new("added_by")))
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("fk_suppressions_added_by")
+                            .from_tbl(Suppressions::Table)
+                            .from_col(Alias::new("added_by"))
+                            .to_tbl(Users::Table)
+                            .to_col(Users::UserId)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM suppressions WHERE kind = 'domain'")
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Suppressions::Table)
+                    .drop_foreign_key(Alias::new("fk_suppressions_added_by"))
+                    .drop_column(Alias::new("added_by"))
+                    .drop_column(Alias::new("kind"))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Suppressions::Table)
+                    .rename_column(Alias::new("entry"), Suppressions::Email)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250116_212701_create_subscriptions_table::Subscriptions;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Everyone who subscribed so far did so in English.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Subscriptions::Table)
+                    .add_column(text(Alias::new("locale")).default("en"))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Subscriptions::Table)
+                    .drop_column(Alias::new("locale"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
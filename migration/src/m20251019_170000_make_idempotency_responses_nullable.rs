@@ -0,0 +1,61 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250420_132533_create_idempotency_table::Idempotency;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // A key is claimed before the request is handled, and its
+        // response is only filled in once there is one. The headers are
+        // stored as JSON, which the entity can read back.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Idempotency::Table)
+                    .modify_column(
+                        ColumnDef::new(Idempotency::ResponseStatusCode)
+                            .small_integer()
+                            .null(),
+                    )
+                    .modify_column(ColumnDef::new(Idempotency::ResponseBody).binary().null())
+                    .drop_column(Idempotency::ResponseHeaders)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Idempotency::Table)
+                    .add_column(json_binary_null(Idempotency::ResponseHeaders))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared("DROP TYPE IF EXISTS header_pair")
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        db.execute_unprepared("DELETE FROM idempotency WHERE response_status_code IS NULL")
+            .await?;
+        db.execute_unprepared(r#"CREATE TYPE header_pair AS (name TEXT, value BYTEA)"#)
+            .await?;
+        db.execute_unprepared(
+            r#"ALTER TABLE idempotency
+                DROP COLUMN response_headers,
+                ADD COLUMN response_headers header_pair[] NOT NULL DEFAULT '{}',
+                ALTER COLUMN response_status_code SET NOT NULL,
+                ALTER COLUMN response_body SET NOT NULL"#,
+        )
+        .await?;
+        db.execute_unprepared("ALTER TABLE idempotency ALTER COLUMN response_headers DROP DEFAULT")
+            .await?;
+        Ok(())
+    }
+}
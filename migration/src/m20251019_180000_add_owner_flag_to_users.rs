@@ -0,0 +1,44 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250223_072332_create_users_table::Users;
+
+/// The admin seeded with the first migrations.
+const SEED_USER_ID: &str = "ddf8994f-d522-4659-8d02-c1d479057be6";
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Owners may change settings affecting the whole deployment,
+        // such as the log level. The seeded admin is the first one.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(boolean(Alias::new("is_owner")).default(false))
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(&format!(
+                "UPDATE users SET is_owner = TRUE WHERE user_id = '{}'",
+                SEED_USER_ID
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(Alias::new("is_owner"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
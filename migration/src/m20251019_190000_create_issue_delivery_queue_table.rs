@@ -0,0 +1,58 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20250116_212701_create_subscriptions_table::Subscriptions;
+use crate::m20251019_090000_create_newsletter_issues_table::NewsletterIssues;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One row per email of an issue that has yet to go out, so that
+        // an interrupted delivery picks up where it stopped.
+        manager
+            .create_table(
+                Table::create()
+                    .table(IssueDeliveryQueue::Table)
+                    .if_not_exists()
+                    .col(uuid(IssueDeliveryQueue::IssueId).not_null())
+                    .col(uuid(IssueDeliveryQueue::SubscriberId).not_null())
+                    .primary_key(
+                        Index::create()
+                            .name("pk_issue_delivery_queue")
+                            .col(IssueDeliveryQueue::IssueId)
+                            .col(IssueDeliveryQueue::SubscriberId),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_issue_delivery_queue_issue_id")
+                            .from(IssueDeliveryQueue::Table, IssueDeliveryQueue::IssueId)
+                            .to(NewsletterIssues::Table, NewsletterIssues::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_issue_delivery_queue_subscriber_id")
+                            .from(IssueDeliveryQueue::Table, IssueDeliveryQueue::SubscriberId)
+                            .to(Subscriptions::Table, Subscriptions::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IssueDeliveryQueue::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum IssueDeliveryQueue {
+    Table,
+    IssueId,
+    SubscriberId,
+}
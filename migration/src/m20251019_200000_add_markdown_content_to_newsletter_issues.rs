@@ -0,0 +1,33 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::m20251019_090000_create_newsletter_issues_table::NewsletterIssues;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The Markdown an issue was written in, so that drafts can be
+        // edited in it again.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .add_column(text_null(Alias::new("markdown_content")))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(NewsletterIssues::Table)
+                    .drop_column(Alias::new("markdown_content"))
+                    .to_owned(),
+            )
+            .await
+    }
+}
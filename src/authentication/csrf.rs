@@ -0,0 +1,156 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::Request,
+    http::{HeaderValue, Method, StatusCode, header},
+    middleware::Next,
+    response::{Html, IntoResponse, Response},
+};
+
+use crate::{
+    session_state::TypedSession,
+    utils::{constant_time_eq, e500},
+};
+
+/// Name of the hidden field added to forms.
+pub const CSRF_FIELD: &str = "csrf_token";
+/// Header accepted in place of the form field, for scripts.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+/// Same as the default limit of axum's `Form` extractor.
+const MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+
+/// Synchronizer token pattern: every unsafe request must carry the token
+/// stored in the session, which a cross-site page cannot read. The token
+/// is added to the POST forms of the HTML pages passing through.
+pub async fn csrf_protection(
+    session: TypedSession,
+    request: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let token = session.csrf_token().await.map_err(e500)?;
+    let request = if is_safe(request.method()) {
+        request
+    } else {
+        let (parts, body) = request.into_parts();
+        let body = to_bytes(body, MAX_BODY_SIZE)
+            .await
+            .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE.into_response())?;
+        let submitted = match parts.headers.get(CSRF_HEADER) {
+            Some(value) => value.to_str().ok().map(str::to_owned),
+            None => form_field(&body, CSRF_FIELD),
+        };
+        if !submitted.is_some_and(|submitted| constant_time_eq(&submitted, &token)) {
+            tracing::warn!(
+                method = %parts.method,
+                path = %parts.uri.path(),
+                "Rejected a request without a valid CSRF token"
+            );
+            return Err(rejection());
+        }
+        Request::from_parts(parts, Body::from(body))
+    };
+
+    let response = next.run(request).await;
+    if !is_html(&response) {
+        return Ok(response);
+    }
+    let (mut parts, body) = response.into_parts();
+    let body = to_bytes(body, usize::MAX).await.map_err(e500)?;
+    let html = String::from_utf8_lossy(&body);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Ok(Response::from_parts(
+        parts,
+        Body::from(inject_csrf_field(&html, &token)),
+    ))
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+fn is_html(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value: &HeaderValue| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"))
+}
+
+fn form_field(body: &[u8], name: &str) -> Option<String> {
+    serde_urlencoded::from_bytes::<Vec<(String, String)>>(body)
+        .ok()?
+        .into_iter()
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn rejection() -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Html(include_str!("csrf_rejected.html")),
+    )
+        .into_response()
+}
+
+/// Add a hidden field with the token right after the opening tag of every
+/// form posting back to the application.
+fn inject_csrf_field(html: &str, token: &str) -> String {
+    let field = format!(
+        r#"<input type="hidden" name="{}" value="{}">"#,
+        CSRF_FIELD, token
+    );
+    // ASCII lowercasing keeps byte offsets intact.
+    let lowercase = html.to_ascii_lowercase();
+    let mut injected = String::with_capacity(html.len());
+    let mut copied_up_to = 0;
+    while let Some(i) = lowercase[copied_up_to..].find("<form") {
+        let tag_start = copied_up_to + i;
+        let Some(length) = lowercase[tag_start..].find('>') else {
+            break;
+        };
+        let tag_end = tag_start + length + 1;
+        injected.push_str(&html[copied_up_to..tag_end]);
+        let tag = lowercase[tag_start..tag_end].replace(['"', '\''], "");
+        if tag.contains("method=post") {
+            injected.push_str(&field);
+        }
+        copied_up_to = tag_end;
+    }
+    injected.push_str(&html[copied_up_to..]);
+    injected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{form_field, inject_csrf_field};
+    use crate::utils::constant_time_eq;
+
+    #[test]
+    fn the_token_is_added_to_post_forms_only() {
+        let html = r#"<form action="/a" method="post"><input name="x"></form>
+<FORM action="/b"
+      METHOD='POST'></FORM>
+<form action="/search" method="get"></form>"#;
+        let injected = inject_csrf_field(html, "abc");
+        let field = r#"<input type="hidden" name="csrf_token" value="abc">"#;
+        assert_eq!(injected.matches(field).count(), 2);
+        assert!(injected.contains(&format!(r#"method="post">{}<input name="x">"#, field)));
+        assert!(injected.contains(&format!("METHOD='POST'>{}</FORM>", field)));
+        assert!(injected.ends_with(r#"method="get"></form>"#));
+    }
+
+    #[test]
+    fn the_token_is_read_from_url_encoded_bodies() {
+        let body = b"username=a&csrf_token=abc%2Bdef&password=b";
+        assert_eq!(form_field(body, "csrf_token").as_deref(), Some("abc+def"));
+        assert_eq!(form_field(b"username=a", "csrf_token"), None);
+    }
+
+    #[test]
+    fn tokens_must_match_exactly() {
+        assert!(constant_time_eq("abc", "abc"));
+        assert!(!constant_time_eq("abc", "abd"));
+        assert!(!constant_time_eq("abc", "abcd"));
+    }
+}
@@ -1,13 +1,17 @@
 use std::ops::Deref;
 
 use axum::{
-    extract::Request,
+    Extension,
+    extract::{Request, State},
+    http::StatusCode,
     middleware::Next,
     response::{IntoResponse, Redirect, Response},
 };
+use entity::entities::prelude::Users;
+use sea_orm::{DatabaseConnection, EntityTrait};
 use uuid::Uuid;
 
-use crate::{session_state::TypedSession, utils::e500};
+use crate::{routes::AppState, session_state::TypedSession, utils::e500};
 
 #[derive(Copy, Clone, Debug)]
 pub struct UserId(Uuid);
@@ -34,7 +38,9 @@ pub async fn reject_anonymous_users(
     match session.get_user_id().await.map_err(e500)? {
         Some(user_id) => {
             req.extensions_mut().insert(UserId(user_id));
-            let response = next.run(req).await;
+            let mut response = next.run(req).await;
+            // For the access log, further out.
+            response.extensions_mut().insert(UserId(user_id));
             Ok(response)
         }
         None => {
@@ -44,3 +50,29 @@ pub async fn reject_anonymous_users(
         }
     }
 }
+
+/// Only let owners through, for settings affecting the whole deployment.
+/// Expected inside [`reject_anonymous_users`].
+pub async fn reject_non_owners(
+    State(state): State<AppState>,
+    user_id: Extension<UserId>,
+    req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    if is_owner(*user_id.0, &state.db_connection)
+        .await
+        .map_err(e500)?
+    {
+        Ok(next.run(req).await)
+    } else {
+        tracing::warn!(user_id = %*user_id, "The user is not an owner");
+        Err(StatusCode::FORBIDDEN.into_response())
+    }
+}
+
+pub async fn is_owner(user_id: Uuid, conn: &DatabaseConnection) -> Result<bool, sea_orm::DbErr> {
+    Ok(Users::find_by_id(user_id)
+        .one(conn)
+        .await?
+        .is_some_and(|user| user.is_owner))
+}
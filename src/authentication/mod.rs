@@ -1,5 +1,7 @@
+mod csrf;
 mod middleware;
 mod password;
 
-pub use middleware::{UserId, reject_anonymous_users};
+pub use csrf::{CSRF_FIELD, CSRF_HEADER, csrf_protection};
+pub use middleware::{UserId, is_owner, reject_anonymous_users, reject_non_owners};
 pub use password::{AuthError, Credentials, change_password, validate_credentials};
@@ -0,0 +1,460 @@
+//! Layered checks against scripted use of the public subscribe form,
+//! which would otherwise let anyone send confirmation emails to arbitrary
+//! victims.
+use std::collections::HashMap;
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Context;
+use axum::http::HeaderMap;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::configuration::{BotProtectionSettings, ChallengeSettings, RateLimitSettings};
+use crate::startup::HmacSecret;
+
+/// Name of the form field people cannot see and bots fill in.
+pub const HONEYPOT_FIELD: &str = "website";
+
+/// Why a submission was turned down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockReason {
+    Honeypot,
+    MissingFormToken,
+    InvalidFormToken,
+    TooFast,
+    FormExpired,
+    IpRateLimited,
+    EmailRateLimited,
+    ChallengeFailed,
+}
+
+impl BlockReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockReason::Honeypot => "honeypot",
+            BlockReason::MissingFormToken => "missing_form_token",
+            BlockReason::InvalidFormToken => "invalid_form_token",
+            BlockReason::TooFast => "too_fast",
+            BlockReason::FormExpired => "form_expired",
+            BlockReason::IpRateLimited => "ip_rate_limited",
+            BlockReason::EmailRateLimited => "email_rate_limited",
+            BlockReason::ChallengeFailed => "challenge_failed",
+        }
+    }
+
+    /// What the person behind the form is told. It does not say which
+    /// check failed, so as not to help tuning a script against them.
+    pub fn message(&self) -> &'static str {
+        match self {
+            BlockReason::IpRateLimited | BlockReason::EmailRateLimited => {
+                "Too many subscription requests, please try again later."
+            }
+            BlockReason::ChallengeFailed => "Please complete the challenge and try again.",
+            _ => "Please reload the page and submit the form again.",
+        }
+    }
+}
+
+impl std::fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum BotCheckError {
+    #[error("The request was blocked: {0}.")]
+    Blocked(BlockReason),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+/// The parts of a subscribe form the checks look at.
+#[derive(Debug)]
+pub struct Submission<'a> {
+    pub client_ip: IpAddr,
+    pub email: &'a str,
+    pub honeypot: &'a str,
+    pub form_token: Option<&'a str>,
+    pub challenge_response: Option<&'a str>,
+}
+
+pub type VerifyFuture<'a> = Pin<Box<dyn Future<Output = Result<bool, anyhow::Error>> + Send + 'a>>;
+
+/// Asks a challenge provider whether the response a form carries was
+/// produced by a person.
+pub trait ChallengeVerifier: Send + Sync {
+    fn verify<'a>(&'a self, response: &'a str, client_ip: IpAddr) -> VerifyFuture<'a>;
+}
+
+/// Talks the `siteverify` protocol shared by Turnstile, hCaptcha and
+/// reCAPTCHA.
+pub struct SiteVerifyClient {
+    http_client: reqwest::Client,
+    verify_url: String,
+    secret_key: SecretString,
+}
+
+impl SiteVerifyClient {
+    pub fn new(settings: &ChallengeSettings) -> Self {
+        Self {
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap(),
+            verify_url: settings.verify_url.clone(),
+            secret_key: settings.secret_key.clone(),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SiteVerifyResponse {
+    success: bool,
+}
+
+impl ChallengeVerifier for SiteVerifyClient {
+    fn verify<'a>(&'a self, response: &'a str, client_ip: IpAddr) -> VerifyFuture<'a> {
+        Box::pin(async move {
+            let client_ip = client_ip.to_string();
+            let outcome: SiteVerifyResponse = self
+                .http_client
+                .post(&self.verify_url)
+                .form(&[
+                    ("secret", self.secret_key.expose_secret()),
+                    ("response", response),
+                    ("remoteip", &client_ip),
+                ])
+                .send()
+                .await
+                .and_then(|r| r.error_for_status())
+                .context("Failed to reach the challenge provider.")?
+                .json()
+                .await
+                .context("Invalid response from the challenge provider.")?;
+            Ok(outcome.success)
+        })
+    }
+}
+
+/// Counts requests per key over fixed windows. Counts live in memory, so
+/// each instance of the application enforces the limit on its own.
+struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    windows: Mutex<HashMap<String, (Instant, u32)>>,
+}
+
+impl RateLimiter {
+    /// Past this many keys, expired windows are dropped.
+    const PRUNE_THRESHOLD: usize = 10_000;
+
+    fn new(settings: &RateLimitSettings) -> Self {
+        Self {
+            max_requests: settings.max_requests,
+            window: settings.window(),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a request, returns `false` if it goes over the limit.
+    fn hit(&self, key: &str, now: Instant) -> bool {
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() > Self::PRUNE_THRESHOLD {
+            windows.retain(|_, (start, _)| now.duration_since(*start) < self.window);
+        }
+        let (start, count) = windows.entry(key.to_string()).or_insert((now, 0));
+        if now.duration_since(*start) >= self.window {
+            *start = now;
+            *count = 0;
+        }
+        if *count >= self.max_requests {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
+
+pub struct BotProtection {
+    enabled: bool,
+    secret: HmacSecret,
+    min_fill: i64,
+    max_form_age: i64,
+    client_ip_header: Option<String>,
+    per_ip: RateLimiter,
+    per_email: RateLimiter,
+    challenge: Option<Arc<dyn ChallengeVerifier>>,
+}
+
+impl BotProtection {
+    pub fn new(settings: &BotProtectionSettings, secret: HmacSecret) -> Self {
+        Self {
+            enabled: settings.enabled,
+            secret,
+            min_fill: settings.min_fill_seconds as i64,
+            max_form_age: settings.max_form_age_seconds as i64,
+            client_ip_header: settings.client_ip_header.clone(),
+            per_ip: RateLimiter::new(&settings.per_ip),
+            per_email: RateLimiter::new(&settings.per_email),
+            challenge: settings
+                .challenge
+                .as_ref()
+                .map(|c| Arc::new(SiteVerifyClient::new(c)) as Arc<dyn ChallengeVerifier>),
+        }
+    }
+
+    /// Use another challenge provider than the configured one.
+    pub fn with_challenge_verifier(mut self, verifier: Arc<dyn ChallengeVerifier>) -> Self {
+        self.challenge = Some(verifier);
+        self
+    }
+
+    /// The token embedded in a freshly served form.
+    pub fn form_token(&self) -> String {
+        self.form_token_at(chrono::Utc::now().timestamp())
+    }
+
+    fn form_token_at(&self, issued_at: i64) -> String {
+        let tag = self.mac(issued_at).finalize().into_bytes();
+        format!("{}.{:x}", issued_at, tag)
+    }
+
+    /// The address of the client, as reported by the reverse proxy if
+    /// there is one. The last entry of the header is the one the proxy
+    /// added, the others come from the client.
+    pub fn client_ip(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        self.client_ip_header
+            .as_ref()
+            .and_then(|name| headers.get(name.as_str()))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer.ip())
+    }
+
+    /// Runs the checks, cheapest first. Rate limits only count the
+    /// requests that got past the checks before them.
+    pub async fn check(&self, submission: &Submission<'_>) -> Result<(), BotCheckError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let block = |reason| Err(BotCheckError::Blocked(reason));
+        if !submission.honeypot.is_empty() {
+            return block(BlockReason::Honeypot);
+        }
+        let Some(form_token) = submission.form_token else {
+            return block(BlockReason::MissingFormToken);
+        };
+        if let Err(reason) = self.verify_form_token(form_token, chrono::Utc::now().timestamp()) {
+            return block(reason);
+        }
+        let now = Instant::now();
+        if !self.per_ip.hit(&submission.client_ip.to_string(), now) {
+            return block(BlockReason::IpRateLimited);
+        }
+        if let Some(challenge) = &self.challenge {
+            let passed = match submission.challenge_response {
+                Some(response) if !response.is_empty() => {
+                    challenge.verify(response, submission.client_ip).await?
+                }
+                _ => false,
+            };
+            if !passed {
+                return block(BlockReason::ChallengeFailed);
+            }
+        }
+        if !self
+            .per_email
+            .hit(&submission.email.to_lowercase(), Instant::now())
+        {
+            return block(BlockReason::EmailRateLimited);
+        }
+        Ok(())
+    }
+
+    fn verify_form_token(&self, token: &str, now: i64) -> Result<(), BlockReason> {
+        let (issued_at, tag) = token.split_once('.').ok_or(BlockReason::InvalidFormToken)?;
+        let issued_at: i64 = issued_at
+            .parse()
+            .map_err(|_| BlockReason::InvalidFormToken)?;
+        let tag = hex::decode(tag).map_err(|_| BlockReason::InvalidFormToken)?;
+        self.mac(issued_at)
+            .verify_slice(&tag)
+            .map_err(|_| BlockReason::InvalidFormToken)?;
+        let age = now - issued_at;
+        if age < self.min_fill {
+            Err(BlockReason::TooFast)
+        } else if age > self.max_form_age {
+            Err(BlockReason::FormExpired)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn mac(&self, issued_at: i64) -> Hmac<sha2::Sha256> {
+        let mut mac =
+            Hmac::<sha2::Sha256>::new_from_slice(self.secret.0.expose_secret().as_bytes()).unwrap();
+        mac.update(format!("subscribe-form\n{}", issued_at).as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err_eq, assert_ok};
+
+    fn settings() -> BotProtectionSettings {
+        BotProtectionSettings {
+            enabled: true,
+            min_fill_seconds: 3,
+            max_form_age_seconds: 3600,
+            client_ip_header: None,
+            per_ip: RateLimitSettings {
+                max_requests: 2,
+                window_seconds: 60,
+            },
+            per_email: RateLimitSettings {
+                max_requests: 1,
+                window_seconds: 60,
+            },
+            challenge: None,
+        }
+    }
+
+    fn protection() -> BotProtection {
+        BotProtection::new(&settings(), HmacSecret(SecretString::from("secret")))
+    }
+
+    struct StubVerifier(bool);
+
+    impl ChallengeVerifier for StubVerifier {
+        fn verify<'a>(&'a self, response: &'a str, _: IpAddr) -> VerifyFuture<'a> {
+            Box::pin(async move { Ok(self.0 && response == "human") })
+        }
+    }
+
+    fn submission<'a>(email: &'a str, form_token: &'a str) -> Submission<'a> {
+        Submission {
+            client_ip: "192.0.2.1".parse().unwrap(),
+            email,
+            honeypot: "",
+            form_token: Some(form_token),
+            challenge_response: Some("human"),
+        }
+    }
+
+    fn blocked(result: Result<(), BotCheckError>) -> Option<BlockReason> {
+        match result {
+            Err(BotCheckError::Blocked(reason)) => Some(reason),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn form_tokens_are_accepted_between_the_minimum_and_maximum_age() {
+        let protection = protection();
+        let token = protection.form_token_at(1_000);
+        assert_err_eq!(
+            protection.verify_form_token(&token, 1_001),
+            BlockReason::TooFast
+        );
+        assert_ok!(protection.verify_form_token(&token, 1_003));
+        assert_err_eq!(
+            protection.verify_form_token(&token, 1_000 + 3601),
+            BlockReason::FormExpired
+        );
+    }
+
+    #[test]
+    fn tampered_form_tokens_are_rejected() {
+        let protection = protection();
+        let token = protection.form_token_at(1_000);
+        let (_, tag) = token.split_once('.').unwrap();
+        for forged in [format!("900.{}", tag), "900".to_string(), "abc.def".into()] {
+            assert_err_eq!(
+                protection.verify_form_token(&forged, 2_000),
+                BlockReason::InvalidFormToken
+            );
+        }
+        let other_secret = BotProtection::new(&settings(), HmacSecret(SecretString::from("other")));
+        assert_err_eq!(
+            other_secret.verify_form_token(&token, 2_000),
+            BlockReason::InvalidFormToken
+        );
+    }
+
+    #[test]
+    fn rate_limit_windows_reset() {
+        let limiter = RateLimiter::new(&RateLimitSettings {
+            max_requests: 2,
+            window_seconds: 60,
+        });
+        let start = Instant::now();
+        assert!(limiter.hit("a", start));
+        assert!(limiter.hit("a", start));
+        assert!(!limiter.hit("a", start));
+        assert!(limiter.hit("b", start));
+        assert!(limiter.hit("a", start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn the_client_ip_is_read_from_the_proxy_header_when_configured() {
+        let peer: SocketAddr = "10.0.0.1:1234".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "203.0.113.9, 198.51.100.7".parse().unwrap(),
+        );
+        assert_eq!(protection().client_ip(&headers, peer), peer.ip());
+
+        let mut settings = settings();
+        settings.client_ip_header = Some("X-Forwarded-For".into());
+        let protection = BotProtection::new(&settings, HmacSecret(SecretString::from("secret")));
+        assert_eq!(
+            protection.client_ip(&headers, peer),
+            "198.51.100.7".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn submissions_are_checked_in_layers() {
+        let protection = protection().with_challenge_verifier(Arc::new(StubVerifier(true)));
+        let token = protection.form_token_at(chrono::Utc::now().timestamp() - 10);
+
+        let mut honeypot = submission("a@example.com", &token);
+        honeypot.honeypot = "https://spam.example";
+        assert_eq!(
+            blocked(protection.check(&honeypot).await),
+            Some(BlockReason::Honeypot)
+        );
+
+        let mut no_challenge = submission("a@example.com", &token);
+        no_challenge.challenge_response = None;
+        assert_eq!(
+            blocked(protection.check(&no_challenge).await),
+            Some(BlockReason::ChallengeFailed)
+        );
+
+        assert_ok!(protection.check(&submission("A@example.com", &token)).await);
+        assert_eq!(
+            blocked(protection.check(&submission("a@example.com", &token)).await),
+            Some(BlockReason::IpRateLimited)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failed_challenge_blocks_the_submission() {
+        let protection = protection().with_challenge_verifier(Arc::new(StubVerifier(false)));
+        let token = protection.form_token_at(chrono::Utc::now().timestamp() - 10);
+        assert_eq!(
+            blocked(protection.check(&submission("a@example.com", &token)).await),
+            Some(BlockReason::ChallengeFailed)
+        );
+    }
+}
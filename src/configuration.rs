@@ -1,9 +1,14 @@
 use sea_orm::sqlx::ConnectOptions;
-use sea_orm::sqlx::postgres::{PgConnectOptions, PgSslMode};
+use sea_orm::sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
 use secrecy::{ExposeSecret, SecretString};
-use serde_aux::field_attributes::deserialize_number_from_string;
+use serde_aux::field_attributes::{
+    deserialize_number_from_string, deserialize_option_number_from_string,
+};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 
-use crate::domain::SubscriberEmail;
+use crate::domain::{BUNDLED_DISPOSABLE_DOMAINS, EmailPolicy, SubscriberEmail};
+use crate::email_client::EmailClient;
+use crate::telemetry::{LogDestination, LogFormat, LogRotation, Redaction, log_writer};
 
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct Settings {
@@ -11,6 +16,117 @@ pub struct Settings {
     pub application: ApplicationSettings,
     pub email_client: EmailClientSettings,
     pub redis_uri: SecretString,
+    pub scheduler: SchedulerSettings,
+    pub tracking: TrackingSettings,
+    pub webhooks: WebhookSettings,
+    pub email_policy: EmailPolicySettings,
+    pub bot_protection: BotProtectionSettings,
+    pub metrics: MetricsSettings,
+    pub health: HealthSettings,
+    pub shutdown: ShutdownSettings,
+    pub telemetry: TelemetrySettings,
+    pub log: LogSettings,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LogSettings {
+    pub format: LogFormat,
+    pub destination: LogDestination,
+    /// Used with `destination: file`.
+    pub directory: String,
+    pub file_prefix: String,
+    pub rotation: LogRotation,
+    /// Applies to every target without a level of its own in `targets`.
+    pub level: String,
+    /// Levels by target, e.g. `sqlx: warn`.
+    #[serde(default)]
+    pub targets: std::collections::BTreeMap<String, String>,
+}
+
+impl LogSettings {
+    /// The `EnvFilter` directives, e.g. `info,sqlx=warn`.
+    pub fn directives(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(
+                self.targets
+                    .iter()
+                    .map(|(target, level)| format!("{}={}", target, level)),
+            )
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    pub fn writer(&self) -> Result<BoxMakeWriter, anyhow::Error> {
+        log_writer(
+            self.destination,
+            &self.directory,
+            &self.file_prefix,
+            self.rotation,
+        )
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TelemetrySettings {
+    pub service_name: String,
+    /// Base URL of an OTLP/HTTP collector, such as `http://localhost:4318`.
+    /// Nothing is exported when unset.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Share of the traces starting here that are exported. Requests
+    /// carrying a trace context follow the decision of the caller.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub sampling_ratio: f64,
+    /// Attached to everything exported, e.g. `deployment.environment`.
+    #[serde(default)]
+    pub resource_attributes: std::collections::BTreeMap<String, String>,
+    /// How emails, names, tokens and IP addresses are logged.
+    #[serde(default)]
+    pub redaction: Redaction,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ShutdownSettings {
+    /// How long readiness reports the instance as down before it stops
+    /// accepting connections, for the load balancer to take it out of
+    /// rotation.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub drain_delay_seconds: u64,
+    /// How long requests and background tasks in flight get to finish
+    /// before they are dropped.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub grace_period_seconds: u64,
+}
+
+impl ShutdownSettings {
+    pub fn drain_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_delay_seconds)
+    }
+    pub fn grace_period(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.grace_period_seconds)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct HealthSettings {
+    /// How long each dependency has to answer the readiness probe.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub timeout_milliseconds: u64,
+    /// Whether the readiness probe also reaches the email provider. An
+    /// outage on their side then takes every instance out of rotation.
+    pub check_email_provider: bool,
+}
+
+impl HealthSettings {
+    pub fn timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.timeout_milliseconds)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct MetricsSettings {
+    /// Bearer token the Prometheus scraper authenticates with.
+    pub token: SecretString,
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
@@ -22,6 +138,17 @@ pub struct EmailClientSettings {
 }
 
 impl EmailClientSettings {
+    pub fn client(self) -> EmailClient {
+        let sender_email = self.sender().expect("Invalid sender email address.");
+        let timeout = self.timeout();
+        EmailClient::new(
+            self.base_url,
+            sender_email,
+            self.authorization_token,
+            timeout,
+        )
+    }
+
     pub fn sender(&self) -> Result<SubscriberEmail, String> {
         SubscriberEmail::parse(self.sender_email.clone())
     }
@@ -30,6 +157,112 @@ impl EmailClientSettings {
     }
 }
 
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SchedulerSettings {
+    /// How often the scheduler looks for issues that are due.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub poll_interval_seconds: u64,
+}
+
+impl SchedulerSettings {
+    pub fn poll_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.poll_interval_seconds)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TrackingSettings {
+    /// Master switch for open and click tracking. When off, no issue is
+    /// tracked and events from links already sent are not recorded.
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct WebhookSettings {
+    /// Shared with the email provider, which sends it back either as the
+    /// password of HTTP Basic auth or as the key signing the payload.
+    pub secret: SecretString,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct EmailPolicySettings {
+    pub block_disposable_domains: bool,
+    /// A list to use instead of the bundled one, in the same format.
+    #[serde(default)]
+    pub disposable_domains_path: Option<String>,
+    /// Mailboxes such as `noreply` or `postmaster`, which no person reads.
+    #[serde(default)]
+    pub role_prefixes: Vec<String>,
+    /// Addresses or domains that may not subscribe.
+    #[serde(default)]
+    pub deny_list: Vec<String>,
+    /// Addresses or domains exempt from every other rule.
+    #[serde(default)]
+    pub allow_list: Vec<String>,
+}
+
+impl EmailPolicySettings {
+    pub fn policy(&self) -> Result<EmailPolicy, std::io::Error> {
+        let disposable_domains = match &self.disposable_domains_path {
+            _ if !self.block_disposable_domains => String::new(),
+            Some(path) => std::fs::read_to_string(path)?,
+            None => BUNDLED_DISPOSABLE_DOMAINS.to_string(),
+        };
+        Ok(EmailPolicy::new(
+            &disposable_domains,
+            &self.role_prefixes,
+            &self.deny_list,
+            &self.allow_list,
+        ))
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct BotProtectionSettings {
+    /// Master switch for every check below. When off, the subscribe
+    /// endpoint accepts forms that were not served by this application.
+    pub enabled: bool,
+    /// Forms sent back sooner than this after being served were not
+    /// filled in by a person.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub min_fill_seconds: u64,
+    /// Forms older than this have to be reloaded.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_form_age_seconds: u64,
+    /// Header set by the reverse proxy with the address of the client,
+    /// e.g. `X-Forwarded-For`. The peer address is used when unset.
+    #[serde(default)]
+    pub client_ip_header: Option<String>,
+    pub per_ip: RateLimitSettings,
+    /// Limits the confirmation emails a single address can be sent.
+    pub per_email: RateLimitSettings,
+    /// A third-party challenge (Turnstile, hCaptcha, ...) the form must
+    /// pass, none when unset.
+    #[serde(default)]
+    pub challenge: Option<ChallengeSettings>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RateLimitSettings {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_requests: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub window_seconds: u64,
+}
+
+impl RateLimitSettings {
+    pub fn window(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.window_seconds)
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ChallengeSettings {
+    /// The provider's `siteverify` endpoint.
+    pub verify_url: String,
+    pub secret_key: SecretString,
+}
+
 #[derive(Debug, serde::Deserialize, Clone)]
 pub struct ApplicationSettings {
     #[serde(deserialize_with = "deserialize_number_from_string")]
@@ -37,6 +270,53 @@ pub struct ApplicationSettings {
     pub host: String,
     pub base_url: String,
     pub hmac_secret: SecretString,
+    pub security_headers: SecurityHeadersSettings,
+    /// Serve HTTPS directly, for deployments without a reverse proxy.
+    #[serde(default)]
+    pub tls: Option<TlsSettings>,
+}
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct TlsSettings {
+    /// PEM file with the certificate, followed by its chain.
+    pub certificate_path: String,
+    /// PEM file with the private key of the certificate.
+    pub private_key_path: String,
+    /// How often the files are checked for changes. SIGHUP reloads them
+    /// right away.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub reload_interval_seconds: u64,
+    /// Port of a plain HTTP listener redirecting every request to HTTPS.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub redirect_http_port: Option<u16>,
+}
+
+impl TlsSettings {
+    pub fn reload_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.reload_interval_seconds)
+    }
+}
+
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct SecurityHeadersSettings {
+    /// `{nonce}` is replaced with the nonce of the request, which pages
+    /// put on their inline `<script>` and `<style>` elements.
+    pub content_security_policy: String,
+    /// Report violations without enforcing the policy, to try one out.
+    pub csp_report_only: bool,
+    /// HSTS is only sent when set, as it pins browsers to HTTPS.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub hsts_max_age_seconds: Option<u64>,
+    pub frame_options: String,
+    pub referrer_policy: String,
+    pub permissions_policy: String,
+}
+
+impl ApplicationSettings {
+    /// The address links in emails point to.
+    pub fn public_url(&self) -> String {
+        format!("{}:{}", self.base_url, self.port)
+    }
 }
 
 #[derive(Debug, serde::Deserialize, Clone)]
@@ -48,6 +328,35 @@ pub struct DatabaseSettings {
     pub host: String,
     pub database_name: String,
     pub require_ssl: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_connections: u32,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub min_connections: u32,
+    /// How long a query waits for a connection from the pool.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub acquire_timeout_milliseconds: u64,
+    /// Connections unused for this long are closed, down to
+    /// `min_connections`.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub idle_timeout_seconds: Option<u64>,
+    /// Connections are replaced after this long, e.g. to follow a failover.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub max_lifetime_seconds: Option<u64>,
+    /// Postgres cancels statements running for longer.
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    pub statement_timeout_milliseconds: Option<u64>,
+    /// Statements taking longer are logged as warnings.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub slow_query_threshold_milliseconds: u64,
+    /// How many times startup tries to reach Postgres before giving up.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub connect_attempts: u32,
+    /// The wait after the first failed attempt, doubled after each one.
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub connect_backoff_milliseconds: u64,
+    /// Apply pending migrations on startup. Instances starting together
+    /// take turns, through an advisory lock.
+    pub migrate_on_start: bool,
 }
 
 impl DatabaseSettings {
@@ -58,18 +367,47 @@ impl DatabaseSettings {
             PgSslMode::Prefer
         };
 
-        PgConnectOptions::new()
+        let options = PgConnectOptions::new()
             .host(&self.host)
             .username(&self.username)
             .password(&self.password.expose_secret())
             .port(self.port)
-            .ssl_mode(ssl_mode)
+            .ssl_mode(ssl_mode);
+        match self.statement_timeout_milliseconds {
+            Some(timeout) => options.options([("statement_timeout", timeout.to_string())]),
+            None => options,
+        }
     }
 
     pub fn with_db(&self) -> PgConnectOptions {
         self.without_db()
             .database(&self.database_name)
-            .log_statements(tracing_log::log::LevelFilter::Trace)
+            .disable_statement_logging()
+            .log_slow_statements(
+                tracing_log::log::LevelFilter::Warn,
+                std::time::Duration::from_millis(self.slow_query_threshold_milliseconds),
+            )
+    }
+
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections)
+            .acquire_timeout(std::time::Duration::from_millis(
+                self.acquire_timeout_milliseconds,
+            ))
+            .idle_timeout(
+                self.idle_timeout_seconds
+                    .map(std::time::Duration::from_secs),
+            )
+            .max_lifetime(
+                self.max_lifetime_seconds
+                    .map(std::time::Duration::from_secs),
+            )
+    }
+
+    pub fn connect_backoff(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.connect_backoff_milliseconds)
     }
 }
 
@@ -0,0 +1,199 @@
+use std::collections::HashSet;
+
+use crate::domain::SubscriberEmail;
+
+/// The disposable domains shipped with the application.
+pub const BUNDLED_DISPOSABLE_DOMAINS: &str = include_str!("disposable_domains.txt");
+
+/// Which syntactically valid addresses may subscribe.
+///
+/// Kept apart from `SubscriberEmail::parse`, which also vets addresses we
+/// already store or send from: tightening the policy must not make
+/// existing subscribers unreachable.
+#[derive(Debug, Clone, Default)]
+pub struct EmailPolicy {
+    disposable_domains: HashSet<String>,
+    role_prefixes: Vec<String>,
+    deny_list: Vec<String>,
+    allow_list: Vec<String>,
+}
+
+/// Why an address was turned away, worded for the person subscribing.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("Addresses at {0} are disposable. Please subscribe with a permanent email address.")]
+    DisposableDomain(String),
+    #[error(
+        "{0}@ addresses belong to a role rather than a person. \
+        Please subscribe with a personal email address."
+    )]
+    RoleAddress(String),
+    #[error("Addresses at {0} cannot subscribe.")]
+    DeniedDomain(String),
+    #[error("This email address cannot subscribe.")]
+    DeniedAddress,
+}
+
+impl EmailPolicy {
+    /// `disposable_domains` is a list in the format of the bundled one.
+    /// Deny and allow list entries are either addresses or domains; the
+    /// allow list takes precedence over every other rule.
+    pub fn new(
+        disposable_domains: &str,
+        role_prefixes: &[String],
+        deny_list: &[String],
+        allow_list: &[String],
+    ) -> Self {
+        let normalise = |entries: &[String]| {
+            entries
+                .iter()
+                .map(|e| e.trim().trim_start_matches('@').to_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect::<Vec<_>>()
+        };
+        Self {
+            disposable_domains: disposable_domains
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                .map(str::to_lowercase)
+                .collect(),
+            role_prefixes: normalise(role_prefixes),
+            deny_list: normalise(deny_list),
+            allow_list: normalise(allow_list),
+        }
+    }
+
+    pub fn check(&self, email: &SubscriberEmail) -> Result<(), PolicyViolation> {
+        let email = email.as_ref().to_lowercase();
+        let Some((local, domain)) = email.rsplit_once('@') else {
+            return Ok(());
+        };
+        let matches = |list: &[String]| {
+            list.iter()
+                .any(|entry| *entry == email || is_within(domain, entry))
+        };
+
+        if matches(&self.allow_list) {
+            return Ok(());
+        }
+        if self.deny_list.contains(&email) {
+            return Err(PolicyViolation::DeniedAddress);
+        }
+        if let Some(entry) = self.deny_list.iter().find(|e| is_within(domain, e)) {
+            return Err(PolicyViolation::DeniedDomain(entry.clone()));
+        }
+        if let Some(disposable) =
+            parent_domains(domain).find(|d| self.disposable_domains.contains(*d))
+        {
+            return Err(PolicyViolation::DisposableDomain(disposable.to_string()));
+        }
+        // Tags such as `+newsletter` do not change who reads the mailbox.
+        let mailbox = local.split('+').next().unwrap_or(local);
+        if let Some(prefix) = self
+            .role_prefixes
+            .iter()
+            .find(|prefix| is_role_mailbox(mailbox, prefix))
+        {
+            return Err(PolicyViolation::RoleAddress(prefix.clone()));
+        }
+        Ok(())
+    }
+}
+
+/// `domain` itself, then each of its parents: `a.b.com`, `b.com`, `com`.
+fn parent_domains(domain: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(domain), |d| {
+        d.split_once('.').map(|(_, parent)| parent)
+    })
+}
+
+/// Whether `domain` is `entry` or one of its subdomains.
+fn is_within(domain: &str, entry: &str) -> bool {
+    parent_domains(domain).any(|d| d == entry)
+}
+
+/// `noreply`, but also `noreply.billing` or `noreply-42`.
+fn is_role_mailbox(mailbox: &str, prefix: &str) -> bool {
+    match mailbox.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with(['.', '-', '_']),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUNDLED_DISPOSABLE_DOMAINS, EmailPolicy, PolicyViolation};
+    use crate::domain::SubscriberEmail;
+    use claims::assert_ok;
+
+    fn policy(deny_list: &[&str], allow_list: &[&str]) -> EmailPolicy {
+        let strings = |l: &[&str]| l.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        EmailPolicy::new(
+            BUNDLED_DISPOSABLE_DOMAINS,
+            &strings(&["noreply", "postmaster"]),
+            &strings(deny_list),
+            &strings(allow_list),
+        )
+    }
+
+    fn check(policy: &EmailPolicy, email: &str) -> Result<(), PolicyViolation> {
+        policy.check(&SubscriberEmail::parse(email.to_string()).unwrap())
+    }
+
+    #[test]
+    fn personal_addresses_are_accepted() {
+        assert_ok!(check(&policy(&[], &[]), "ursula@example.com"));
+    }
+
+    #[test]
+    fn disposable_domains_and_their_subdomains_are_rejected() {
+        let policy = policy(&[], &[]);
+        assert_eq!(
+            check(&policy, "someone@Mailinator.com"),
+            Err(PolicyViolation::DisposableDomain("mailinator.com".into()))
+        );
+        assert_eq!(
+            check(&policy, "someone@eu.mailinator.com"),
+            Err(PolicyViolation::DisposableDomain("mailinator.com".into()))
+        );
+    }
+
+    #[test]
+    fn role_addresses_are_rejected() {
+        let policy = policy(&[], &[]);
+        for email in [
+            "noreply@example.com",
+            "NoReply+news@example.com",
+            "noreply-billing@example.com",
+            "postmaster@example.com",
+        ] {
+            assert!(
+                matches!(check(&policy, email), Err(PolicyViolation::RoleAddress(_))),
+                "{} was accepted",
+                email
+            );
+        }
+        assert_ok!(check(&policy, "postmasters@example.com"));
+    }
+
+    #[test]
+    fn the_deny_list_covers_addresses_and_domains() {
+        let policy = policy(&["ursula@example.com", "@competitor.com"], &[]);
+        assert_eq!(
+            check(&policy, "ursula@example.com"),
+            Err(PolicyViolation::DeniedAddress)
+        );
+        assert_eq!(
+            check(&policy, "spy@mail.competitor.com"),
+            Err(PolicyViolation::DeniedDomain("competitor.com".into()))
+        );
+    }
+
+    #[test]
+    fn the_allow_list_overrides_every_other_rule() {
+        let policy = policy(&["example.com"], &["postmaster@example.com", "yopmail.com"]);
+        assert_ok!(check(&policy, "postmaster@example.com"));
+        assert_ok!(check(&policy, "tester@yopmail.com"));
+    }
+}
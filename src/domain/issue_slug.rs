@@ -0,0 +1,81 @@
+const MAX_LENGTH: usize = 64;
+
+/// The URL-friendly name of a published issue in the archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueSlug(String);
+
+impl std::fmt::Display for IssueSlug {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl IssueSlug {
+    /// Derive a slug from an issue title: ASCII letters and digits are kept
+    /// (lowercased), every other run of characters becomes a single dash.
+    pub fn from_title(title: &str) -> IssueSlug {
+        let mut slug = String::new();
+        for c in title.chars() {
+            if slug.len() == MAX_LENGTH {
+                break;
+            }
+            if c.is_ascii_alphanumeric() {
+                slug.push(c.to_ascii_lowercase());
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug = slug.trim_end_matches('-');
+        if slug.is_empty() {
+            Self("issue".to_string())
+        } else {
+            Self(slug.to_string())
+        }
+    }
+
+    /// Tell apart issues that share a title.
+    pub fn with_suffix(&self, suffix: &str) -> IssueSlug {
+        Self(format!("{}-{}", self.0, suffix))
+    }
+}
+
+impl AsRef<str> for IssueSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IssueSlug;
+
+    #[test]
+    fn titles_are_lowercased_and_dashed() {
+        let slug = IssueSlug::from_title("Hello, World! Issue #3");
+        assert_eq!(slug.as_ref(), "hello-world-issue-3");
+    }
+
+    #[test]
+    fn non_ascii_characters_are_dropped() {
+        let slug = IssueSlug::from_title("Ça va — très bien");
+        assert_eq!(slug.as_ref(), "a-va-tr-s-bien");
+    }
+
+    #[test]
+    fn long_titles_are_truncated_without_a_trailing_dash() {
+        let title = format!("{} tail", "a".repeat(64));
+        let slug = IssueSlug::from_title(&title);
+        assert_eq!(slug.as_ref(), "a".repeat(64));
+    }
+
+    #[test]
+    fn titles_without_usable_characters_get_a_fallback() {
+        assert_eq!(IssueSlug::from_title("!!!").as_ref(), "issue");
+    }
+
+    #[test]
+    fn suffixes_are_dashed() {
+        let slug = IssueSlug::from_title("Weekly").with_suffix("1a2b3c4d");
+        assert_eq!(slug.as_ref(), "weekly-1a2b3c4d");
+    }
+}
@@ -0,0 +1,132 @@
+use handlebars::Handlebars;
+
+const HTML: &str = "html";
+const TEXT: &str = "text";
+
+/// The variables available to issue templates, one set per recipient.
+#[derive(Debug, serde::Serialize)]
+pub struct TemplateVariables<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+    pub unsubscribe_url: &'a str,
+    pub web_view_url: &'a str,
+    /// The subscriber's language, e.g. `fr`, for issues written in several
+    /// of them: `{{#if (eq locale "fr")}}...{{/if}}`.
+    pub locale: &'a str,
+}
+
+/// The bodies of an issue compiled as Handlebars templates.
+/// Rendering is strict: referencing a variable that does not exist fails
+/// instead of silently producing an empty string.
+pub struct IssueTemplate {
+    html: Handlebars<'static>,
+    // Plain-text bodies must not be HTML-escaped, hence a registry of its own.
+    text: Handlebars<'static>,
+}
+
+impl std::fmt::Debug for IssueTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IssueTemplate").finish_non_exhaustive()
+    }
+}
+
+impl IssueTemplate {
+    /// Compile both bodies and check them against a sample recipient,
+    /// so that mistakes surface before a single email goes out.
+    pub fn parse(html_content: &str, text_content: &str) -> Result<IssueTemplate, String> {
+        let mut html = Handlebars::new();
+        html.set_strict_mode(true);
+        html.register_template_string(HTML, html_content)
+            .map_err(|e| format!("The HTML body is not a valid template: {}", e))?;
+        let mut text = Handlebars::new();
+        text.set_strict_mode(true);
+        text.register_escape_fn(handlebars::no_escape);
+        text.register_template_string(TEXT, text_content)
+            .map_err(|e| format!("The text body is not a valid template: {}", e))?;
+
+        let template = Self { html, text };
+        template.render(&TemplateVariables {
+            name: "Ursula",
+            email: "ursula@example.com",
+            unsubscribe_url: "https://example.com/unsubscribe",
+            web_view_url: "https://example.com/issues/1",
+            locale: "en",
+        })?;
+        Ok(template)
+    }
+
+    /// Returns the HTML and the plain-text body for one recipient.
+    pub fn render(&self, variables: &TemplateVariables<'_>) -> Result<(String, String), String> {
+        let html = self
+            .html
+            .render(HTML, variables)
+            .map_err(|e| format!("Failed to render the HTML body: {}", e.reason()))?;
+        let text = self
+            .text
+            .render(TEXT, variables)
+            .map_err(|e| format!("Failed to render the text body: {}", e.reason()))?;
+        Ok((html, text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IssueTemplate, TemplateVariables};
+    use claims::{assert_err, assert_ok};
+
+    fn variables() -> TemplateVariables<'static> {
+        TemplateVariables {
+            name: "Le <Guin>",
+            email: "ursula@example.com",
+            unsubscribe_url: "https://example.com/unsubscribe?a=1&b=2",
+            web_view_url: "https://example.com/issues/1",
+            locale: "fr",
+        }
+    }
+
+    #[test]
+    fn known_variables_are_accepted() {
+        assert_ok!(IssueTemplate::parse(
+            "<p>Hi {{name}}</p><a href=\"{{unsubscribe_url}}\">Unsubscribe</a>",
+            "Hi {{name}} ({{email}}), read online at {{web_view_url}}"
+        ));
+    }
+
+    #[test]
+    fn issues_can_be_written_in_several_languages() {
+        let template = IssueTemplate::parse(
+            "{{#if (eq locale \"fr\")}}<p>Bonjour</p>{{else}}<p>Hello</p>{{/if}}",
+            "{{#if (eq locale \"fr\")}}Bonjour{{else}}Hello{{/if}}",
+        )
+        .unwrap();
+        let (html, text) = template.render(&variables()).unwrap();
+        assert_eq!(html, "<p>Bonjour</p>");
+        assert_eq!(text, "Bonjour");
+    }
+
+    #[test]
+    fn unknown_variables_are_rejected() {
+        assert_err!(IssueTemplate::parse("<p>Hi {{first_name}}</p>", "Hi"));
+        assert_err!(IssueTemplate::parse("<p>Hi</p>", "Hi {{first_name}}"));
+    }
+
+    #[test]
+    fn malformed_templates_are_rejected() {
+        assert_err!(IssueTemplate::parse("<p>Hi {{name</p>", "Hi"));
+    }
+
+    #[test]
+    fn variables_are_escaped_in_html_only() {
+        let template = IssueTemplate::parse(
+            "<p>{{name}}</p><a href=\"{{unsubscribe_url}}\">x</a>",
+            "{{name}} {{unsubscribe_url}}",
+        )
+        .unwrap();
+        let (html, text) = template.render(&variables()).unwrap();
+        assert_eq!(
+            html,
+            "<p>Le &lt;Guin&gt;</p><a href=\"https://example.com/unsubscribe?a&#x3D;1&amp;b&#x3D;2\">x</a>"
+        );
+        assert_eq!(text, "Le <Guin> https://example.com/unsubscribe?a=1&b=2");
+    }
+}
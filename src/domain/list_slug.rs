@@ -0,0 +1,87 @@
+const MAX_LENGTH: usize = 64;
+
+/// The slug of the list every installation starts with.
+/// Forms that do not name a list fall back to it.
+pub const DEFAULT_LIST_SLUG: &str = "newsletter";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListSlug(String);
+
+impl std::fmt::Display for ListSlug {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl ListSlug {
+    pub fn parse(s: String) -> Result<ListSlug, String> {
+        let is_empty = s.is_empty();
+        let is_too_long = s.len() > MAX_LENGTH;
+        let has_invalid_characters = !s
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        let has_dangling_dash = s.starts_with('-') || s.ends_with('-');
+
+        if is_empty || is_too_long || has_invalid_characters || has_dangling_dash {
+            Err(format!("{} is not a valid list slug.", s))
+        } else {
+            Ok(Self(s))
+        }
+    }
+}
+
+impl Default for ListSlug {
+    fn default() -> Self {
+        Self(DEFAULT_LIST_SLUG.to_string())
+    }
+}
+
+impl AsRef<str> for ListSlug {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListSlug;
+    use claims::{assert_err, assert_ok};
+
+    #[test]
+    fn empty_string_is_rejected() {
+        assert_err!(ListSlug::parse("".to_string()));
+    }
+
+    #[test]
+    fn a_64_character_long_slug_is_valid() {
+        assert_ok!(ListSlug::parse("a".repeat(64)));
+    }
+
+    #[test]
+    fn a_slug_longer_than_64_characters_is_rejected() {
+        assert_err!(ListSlug::parse("a".repeat(65)));
+    }
+
+    #[test]
+    fn uppercase_and_punctuation_are_rejected() {
+        for slug in ["Weekly", "weekly digest", "weekly_digest", "weekly/digest"] {
+            assert_err!(ListSlug::parse(slug.to_string()));
+        }
+    }
+
+    #[test]
+    fn leading_or_trailing_dashes_are_rejected() {
+        assert_err!(ListSlug::parse("-weekly".to_string()));
+        assert_err!(ListSlug::parse("weekly-".to_string()));
+    }
+
+    #[test]
+    fn a_valid_slug_is_parsed_successfully() {
+        assert_ok!(ListSlug::parse("rust-weekly-2".to_string()));
+    }
+
+    #[test]
+    fn the_default_slug_is_valid() {
+        assert_ok!(ListSlug::parse(ListSlug::default().to_string()));
+    }
+}
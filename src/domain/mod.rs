@@ -1,7 +1,21 @@
+mod email_policy;
+mod issue_slug;
+mod issue_template;
+mod list_slug;
 mod new_subscriber;
+mod newsletter_body;
+mod send_at;
 mod subscriber_email;
 mod subscriber_name;
+mod suppression_entry;
 
+pub use email_policy::{BUNDLED_DISPOSABLE_DOMAINS, EmailPolicy, PolicyViolation};
+pub use issue_slug::IssueSlug;
+pub use issue_template::{IssueTemplate, TemplateVariables};
+pub use list_slug::{DEFAULT_LIST_SLUG, ListSlug};
 pub use new_subscriber::NewSubscriber;
+pub use newsletter_body::NewsletterBody;
+pub use send_at::SendAt;
 pub use subscriber_email::SubscriberEmail;
 pub use subscriber_name::SubscriberName;
+pub use suppression_entry::SuppressionEntry;
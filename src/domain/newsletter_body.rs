@@ -0,0 +1,241 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+/// The HTML and plain-text bodies of a newsletter issue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsletterBody {
+    markdown_content: Option<String>,
+    html_content: String,
+    text_content: String,
+}
+
+impl NewsletterBody {
+    /// Build both bodies from the editor's input.
+    /// Markdown is rendered into sanitized HTML and readable plain text;
+    /// a non-empty hand-written body overrides the rendered one.
+    /// Without Markdown both hand-written bodies are required.
+    pub fn parse(
+        markdown: Option<String>,
+        html_content: String,
+        text_content: String,
+    ) -> Result<NewsletterBody, String> {
+        let markdown = markdown.filter(|m| !m.trim().is_empty());
+        let html_content = Some(html_content).filter(|h| !h.trim().is_empty());
+        let text_content = Some(text_content).filter(|t| !t.trim().is_empty());
+        let (html_content, text_content) = match (&markdown, html_content, text_content) {
+            (_, Some(html_content), Some(text_content)) => (html_content, text_content),
+            (Some(markdown), html_content, text_content) => (
+                html_content.unwrap_or_else(|| markdown_to_html(markdown)),
+                text_content.unwrap_or_else(|| markdown_to_text(markdown)),
+            ),
+            (None, _, _) => {
+                return Err("Write the issue in Markdown, \
+                    or provide both an HTML and a plain-text body."
+                    .to_string());
+            }
+        };
+        Ok(Self {
+            markdown_content: markdown,
+            html_content,
+            text_content,
+        })
+    }
+
+    /// The Markdown the bodies were rendered from, if any.
+    pub fn markdown_content(&self) -> Option<&str> {
+        self.markdown_content.as_deref()
+    }
+
+    pub fn html_content(&self) -> &str {
+        &self.html_content
+    }
+
+    pub fn text_content(&self) -> &str {
+        &self.text_content
+    }
+}
+
+fn parser(markdown: &str) -> Parser<'_> {
+    Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    )
+}
+
+fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser(markdown));
+    // Raw HTML is allowed in Markdown, so the output needs cleaning up.
+    // Link targets come out percent-encoded, which would hide template
+    // placeholders such as `{{unsubscribe_url}}` from the renderer.
+    ammonia::Builder::default()
+        .attribute_filter(|_, attribute, value| match attribute {
+            "href" | "src" => Some(value.replace("%7B%7B", "{{").replace("%7D%7D", "}}").into()),
+            _ => Some(value.into()),
+        })
+        .clean(&html)
+        .to_string()
+}
+
+/// Render Markdown as plain text meant to be read as-is in a mail client:
+/// markup is dropped, list markers are kept and link targets are spelled
+/// out after the link text.
+fn markdown_to_text(markdown: &str) -> String {
+    let mut text = String::new();
+    // One entry per open list, holding the next number for ordered lists.
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    // Link targets and where their text starts in `text`.
+    let mut links: Vec<(String, usize)> = Vec::new();
+
+    for event in parser(markdown) {
+        match event {
+            Event::Start(Tag::Item) => {
+                text.push_str(&"  ".repeat(lists.len().saturating_sub(1)));
+                match lists.last_mut() {
+                    Some(Some(n)) => {
+                        text.push_str(&format!("{}. ", n));
+                        *n += 1;
+                    }
+                    _ => text.push_str("- "),
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                if !lists.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                lists.push(start);
+            }
+            Event::Start(Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. }) => {
+                links.push((dest_url.to_string(), text.len()));
+            }
+            Event::End(TagEnd::Link | TagEnd::Image) => {
+                if let Some((dest_url, start)) = links.pop()
+                    && text[start..] != *dest_url
+                {
+                    text.push_str(&format!(" ({})", dest_url));
+                }
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::CodeBlock) => {
+                if lists.is_empty() {
+                    text.push_str("\n\n");
+                } else {
+                    text.push('\n');
+                }
+            }
+            Event::End(TagEnd::Item) if !text.ends_with('\n') => text.push('\n'),
+            Event::End(TagEnd::List(_)) => {
+                lists.pop();
+                if lists.is_empty() {
+                    text.push('\n');
+                }
+            }
+            Event::End(TagEnd::TableCell) => text.push('\t'),
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => text.push('\n'),
+            Event::End(TagEnd::Table) => text.push('\n'),
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push('\n'),
+            Event::Rule => text.push_str("---\n\n"),
+            _ => {}
+        }
+    }
+
+    text.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NewsletterBody;
+    use claims::{assert_err, assert_ok};
+
+    fn from_markdown(markdown: &str) -> NewsletterBody {
+        NewsletterBody::parse(Some(markdown.to_string()), String::new(), String::new()).unwrap()
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_html() {
+        let body = from_markdown("# Hello\n\nThis is *important*.");
+        assert_eq!(
+            body.html_content(),
+            "<h1>Hello</h1>\n<p>This is <em>important</em>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn scripts_are_stripped_from_the_rendered_html() {
+        let body = from_markdown("Hi <script>alert('boo')</script> there");
+        assert!(!body.html_content().contains("script"));
+        assert!(body.html_content().contains("Hi"));
+    }
+
+    #[test]
+    fn markdown_is_rendered_to_plain_text() {
+        let body = from_markdown(
+            "# Hello\n\nRead [the post](https://example.com/post).\n\n- one\n- two\n\n1. first\n2. second",
+        );
+        assert_eq!(
+            body.text_content(),
+            "Hello\n\nRead the post (https://example.com/post).\n\n- one\n- two\n\n1. first\n2. second"
+        );
+    }
+
+    #[test]
+    fn bare_links_are_not_repeated_in_plain_text() {
+        let body = from_markdown("<https://example.com>");
+        assert_eq!(body.text_content(), "https://example.com");
+    }
+
+    #[test]
+    fn nested_lists_are_indented_in_plain_text() {
+        let body = from_markdown("- one\n  - one.one\n- two");
+        assert_eq!(body.text_content(), "- one\n  - one.one\n- two");
+    }
+
+    #[test]
+    fn template_placeholders_survive_in_link_targets() {
+        let body = from_markdown("[Unsubscribe]({{unsubscribe_url}})");
+        assert!(body.html_content().contains("href=\"{{unsubscribe_url}}\""));
+    }
+
+    #[test]
+    fn encoded_braces_in_the_text_are_left_alone() {
+        let body = from_markdown("Braces are encoded as %7B%7B and %7D%7D.");
+        assert_eq!(
+            body.html_content(),
+            "<p>Braces are encoded as %7B%7B and %7D%7D.</p>\n"
+        );
+    }
+
+    #[test]
+    fn hand_written_bodies_override_the_rendered_ones() {
+        let body = NewsletterBody::parse(
+            Some("*rendered*".to_string()),
+            "<p>hand-written</p>".to_string(),
+            String::new(),
+        )
+        .unwrap();
+        assert_eq!(body.html_content(), "<p>hand-written</p>");
+        assert_eq!(body.text_content(), "rendered");
+    }
+
+    #[test]
+    fn hand_written_bodies_are_enough_without_markdown() {
+        assert_ok!(NewsletterBody::parse(
+            None,
+            "<p>html</p>".to_string(),
+            "text".to_string()
+        ));
+    }
+
+    #[test]
+    fn a_missing_body_is_rejected() {
+        assert_err!(NewsletterBody::parse(
+            None,
+            "<p>html</p>".to_string(),
+            String::new()
+        ));
+        assert_err!(NewsletterBody::parse(
+            Some("   ".to_string()),
+            String::new(),
+            "text".to_string()
+        ));
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::{DateTime, FixedOffset, Utc};
+
+/// The moment a scheduled issue should go out. It is shown back to the
+/// editor in the offset they picked it in; once stored as a `timestamptz`
+/// only the moment is kept, and it reads back in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendAt(DateTime<FixedOffset>);
+
+impl std::fmt::Display for SendAt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.to_rfc3339().fmt(f)
+    }
+}
+
+impl SendAt {
+    /// Parse an RFC 3339 timestamp such as `2025-05-01T09:00:00+02:00`.
+    /// The offset is mandatory and the moment must lie after `now`.
+    pub fn parse(s: String, now: DateTime<Utc>) -> Result<SendAt, String> {
+        let send_at = DateTime::parse_from_rfc3339(s.trim()).map_err(|_| {
+            format!(
+                "{} is not a valid send time. \
+                Use a timestamp with a timezone, e.g. 2025-05-01T09:00:00+02:00.",
+                s
+            )
+        })?;
+        if send_at <= now {
+            return Err(format!("{} is in the past.", s));
+        }
+        Ok(Self(send_at))
+    }
+}
+
+impl From<SendAt> for DateTime<FixedOffset> {
+    fn from(s: SendAt) -> Self {
+        s.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SendAt;
+    use chrono::{DateTime, Utc};
+    use claims::{assert_err, assert_ok};
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-04-30T20:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn a_future_timestamp_with_an_offset_is_accepted() {
+        assert_ok!(SendAt::parse(
+            "2025-05-01T09:00:00+02:00".to_string(),
+            now()
+        ));
+    }
+
+    #[test]
+    fn a_timestamp_without_an_offset_is_rejected() {
+        assert_err!(SendAt::parse("2025-05-01T09:00:00".to_string(), now()));
+    }
+
+    #[test]
+    fn a_timestamp_in_the_past_is_rejected() {
+        assert_err!(SendAt::parse(
+            "2025-04-30T21:00:00+02:00".to_string(),
+            now()
+        ));
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert_err!(SendAt::parse("tomorrow at 9".to_string(), now()));
+    }
+
+    #[test]
+    fn the_send_time_is_shown_in_the_editors_offset() {
+        let send_at = SendAt::parse("2025-05-01T09:00:00+02:00".to_string(), now()).unwrap();
+        assert_eq!(send_at.to_string(), "2025-05-01T09:00:00+02:00");
+    }
+}
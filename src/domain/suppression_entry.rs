@@ -0,0 +1,120 @@
+use validator::ValidateEmail;
+
+/// Something on the suppression list: one address, or every address at a
+/// domain. Both are stored lowercased.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SuppressionEntry {
+    Email(String),
+    Domain(String),
+}
+
+impl std::fmt::Display for SuppressionEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_ref().fmt(f)
+    }
+}
+
+impl SuppressionEntry {
+    /// Parse an address such as `jane@example.com`, or a domain such as
+    /// `example.com` or `@example.com`.
+    pub fn parse(s: String) -> Result<SuppressionEntry, String> {
+        let s = s.trim();
+        match s.rsplit_once('@') {
+            Some(("", domain)) => Self::parse_domain(domain),
+            Some(_) => Self::parse_email(s),
+            None => Self::parse_domain(s),
+        }
+    }
+
+    /// Parse an exact address, never a domain.
+    pub fn parse_email(s: &str) -> Result<SuppressionEntry, String> {
+        let email = s.trim().to_lowercase();
+        if email.validate_email() {
+            Ok(Self::Email(email))
+        } else {
+            Err(format!("{} is not a valid email address.", s))
+        }
+    }
+
+    fn parse_domain(s: &str) -> Result<SuppressionEntry, String> {
+        let domain = s.trim().to_lowercase();
+        let is_valid_label = |label: &str| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        };
+        if domain.contains('.') && domain.len() <= 253 && domain.split('.').all(is_valid_label) {
+            Ok(Self::Domain(domain))
+        } else {
+            Err(format!("{} is not a valid email address or domain.", s))
+        }
+    }
+
+    /// How the entry is stored in the `kind` column.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SuppressionEntry::Email(_) => "email",
+            SuppressionEntry::Domain(_) => "domain",
+        }
+    }
+}
+
+impl AsRef<str> for SuppressionEntry {
+    fn as_ref(&self) -> &str {
+        match self {
+            SuppressionEntry::Email(email) => email,
+            SuppressionEntry::Domain(domain) => domain,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SuppressionEntry;
+    use claims::assert_err;
+
+    #[test]
+    fn an_address_is_an_email_entry() {
+        assert_eq!(
+            SuppressionEntry::parse("Jane@Example.com ".into()),
+            Ok(SuppressionEntry::Email("jane@example.com".into()))
+        );
+    }
+
+    #[test]
+    fn a_bare_domain_is_a_domain_entry() {
+        assert_eq!(
+            SuppressionEntry::parse("Example.com".into()),
+            Ok(SuppressionEntry::Domain("example.com".into()))
+        );
+    }
+
+    #[test]
+    fn a_domain_with_a_leading_at_is_a_domain_entry() {
+        assert_eq!(
+            SuppressionEntry::parse("@mail.example.com".into()),
+            Ok(SuppressionEntry::Domain("mail.example.com".into()))
+        );
+    }
+
+    #[test]
+    fn invalid_domains_are_rejected() {
+        for domain in [
+            "",
+            "localhost",
+            "-bad.com",
+            "bad-.com",
+            "a..com",
+            "exa mple.com",
+        ] {
+            assert_err!(SuppressionEntry::parse(domain.into()));
+        }
+    }
+
+    #[test]
+    fn parse_email_rejects_domains() {
+        assert_err!(SuppressionEntry::parse_email("example.com"));
+    }
+}
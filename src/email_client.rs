@@ -1,6 +1,25 @@
 use crate::domain::SubscriberEmail;
+use crate::metrics::Metrics;
+use crate::suppression::SuppressionList;
+use crate::telemetry::inject_trace_context;
 use reqwest::Client;
+use reqwest::header::HeaderMap;
 use secrecy::{ExposeSecret, SecretString};
+use tracing::Instrument;
+use tracing::field::Empty;
+
+#[derive(thiserror::Error, Debug)]
+pub enum SendEmailError {
+    #[error("The recipient is on the suppression list.")]
+    Suppressed,
+    #[error("Failed to check the suppression list.")]
+    SuppressionCheck(#[source] sea_orm::DbErr),
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+/// The API spoken by the client, used to label metrics.
+const PROVIDER: &str = "postmark";
 
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -12,12 +31,21 @@ struct SendEmailRequest<'a> {
     text_body: &'a str,
 }
 
+/// The part of the provider's answer worth keeping.
+#[derive(serde::Deserialize)]
+struct SendEmailResponse {
+    #[serde(rename = "MessageID")]
+    message_id: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct EmailClient {
     http_client: Client,
     base_url: String,
     sender: SubscriberEmail,
     authorization_token: SecretString,
+    suppression_list: Option<SuppressionList>,
+    metrics: Option<Metrics>,
 }
 
 impl EmailClient {
@@ -34,16 +62,47 @@ impl EmailClient {
             base_url,
             sender,
             authorization_token,
+            suppression_list: None,
+            metrics: None,
         }
     }
 
+    /// Refuse to email anyone on the list. Callers are expected to filter
+    /// suppressed recipients out beforehand; this is the last line of
+    /// defence.
+    pub fn with_suppression_list(mut self, suppression_list: SuppressionList) -> Self {
+        self.suppression_list = Some(suppression_list);
+        self
+    }
+
+    /// Count the emails sent and failed.
+    pub fn with_metrics(mut self, metrics: Metrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Check the provider can be reached. Any answer will do, as the API
+    /// has no endpoint meant for it.
+    pub async fn ping(&self) -> Result<(), reqwest::Error> {
+        self.http_client.head(&self.base_url).send().await?;
+        Ok(())
+    }
+
     pub async fn send_email(
         &self,
         recipient: &SubscriberEmail,
         subject: &str,
         html_content: &str,
         text_content: &str,
-    ) -> Result<(), reqwest::Error> {
+    ) -> Result<(), SendEmailError> {
+        if let Some(suppression_list) = &self.suppression_list
+            && suppression_list
+                .is_suppressed(recipient.as_ref())
+                .await
+                .map_err(SendEmailError::SuppressionCheck)?
+        {
+            return Err(SendEmailError::Suppressed);
+        }
         // You can do better using `reqwest::Url::join` if you change
         // `base_url`'s type from `String` to `request::Url`.
         let url = format!("{}/email", self.base_url);
@@ -55,18 +114,66 @@ impl EmailClient {
             text_body: text_content,
         };
 
-        let _ = self
+        let outcome = self.post_email(&url, &request_body).await;
+        if let Some(metrics) = &self.metrics {
+            match &outcome {
+                Ok(_) => metrics.email_sent(PROVIDER),
+                Err(_) => metrics.email_failed(PROVIDER),
+            }
+        }
+        outcome?;
+        Ok(())
+    }
+
+    /// One request to the provider, in a client span carrying the trace
+    /// on to it.
+    async fn post_email(
+        &self,
+        url: &str,
+        request_body: &SendEmailRequest<'_>,
+    ) -> Result<(), reqwest::Error> {
+        let span = tracing::info_span!(
+            "POST /email",
+            otel.kind = "client",
+            otel.status_code = Empty,
+            http.request.method = "POST",
+            http.response.status_code = Empty,
+            url.full = %url,
+            email.provider = PROVIDER,
+            // Emails are sent once: the provider may have accepted one it
+            // failed to answer for, and a retry would send it twice.
+            email.attempt = 1,
+            email.message_id = Empty,
+        );
+        let mut headers = HeaderMap::new();
+        inject_trace_context(&span, &mut headers);
+        let request = self
             .http_client
-            .post(&url)
+            .post(url)
+            .headers(headers)
             .header(
                 "X-Postmark-Server-Token",
                 self.authorization_token.expose_secret(),
             )
-            .json(&request_body)
-            .send()
-            .await?
-            .error_for_status()?;
-        Ok(())
+            .json(request_body);
+        async {
+            let span = tracing::Span::current();
+            let response = request.send().await.inspect_err(|_| {
+                span.record("otel.status_code", "error");
+            })?;
+            span.record("http.response.status_code", response.status().as_u16());
+            if let Err(e) = response.error_for_status_ref() {
+                span.record("otel.status_code", "error");
+                return Err(e);
+            }
+            // The email was accepted, whether or not the answer makes sense.
+            if let Ok(body) = response.json::<SendEmailResponse>().await {
+                span.record("email.message_id", body.message_id);
+            }
+            Ok(())
+        }
+        .instrument(span)
+        .await
     }
 }
 
@@ -78,7 +185,15 @@ mod tests {
     use fake::faker::lorem::en::Paragraph;
     use fake::faker::lorem::en::Sentence;
     use fake::{Fake, Faker};
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::TracerProvider;
     use secrecy::SecretString;
+    use std::collections::HashMap;
+    use tracing::Instrument;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::Registry;
+    use tracing_subscriber::layer::SubscriberExt;
     use wiremock::Request;
     use wiremock::matchers::any;
     use wiremock::matchers::header;
@@ -223,4 +338,49 @@ mod tests {
         // Assert
         assert_err!(outcome);
     }
+
+    #[tokio::test]
+    async fn send_email_continues_the_trace_of_the_caller() {
+        // Arrange
+        opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+        let tracer = TracerProvider::builder().build().tracer("test");
+        let subscriber =
+            Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mock_server = MockServer::start().await;
+        let email_client = email_client(mock_server.uri());
+        Mock::given(header_exists("traceparent"))
+            .and(header("tracestate", "vendor=value"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let caller = tracing::info_span!("caller");
+        let incoming = HashMap::from([
+            (
+                "traceparent".to_string(),
+                "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01".to_string(),
+            ),
+            ("tracestate".to_string(), "vendor=value".to_string()),
+        ]);
+        caller.set_parent(opentelemetry::global::get_text_map_propagator(
+            |propagator| propagator.extract(&incoming),
+        ));
+
+        // Act
+        let outcome = email_client
+            .send_email(&email(), &subject(), &content(), &content())
+            .instrument(caller)
+            .await;
+
+        // Assert
+        assert_ok!(outcome);
+        let requests = mock_server.received_requests().await.unwrap();
+        let traceparent = requests[0].headers["traceparent"].to_str().unwrap();
+        assert!(traceparent.starts_with("00-0af7651916cd43dd8448eb211c80319c-"));
+        // The provider call is a span of its own, not the caller's.
+        assert!(!traceparent.contains("b7ad6b7169203331"));
+    }
 }
@@ -0,0 +1,30 @@
+# Public pages
+home-title = Startseite
+home-welcome = Willkommen bei unserem Newsletter!
+home-archive = Frühere Ausgaben lesen
+home-subscribe-name = Name
+home-subscribe-email = E-Mail-Adresse
+home-subscribe-honeypot = Dieses Feld leer lassen
+home-subscribe-submit = Abonnieren
+
+login-title = Anmelden
+login-username = Benutzername
+login-username-placeholder = Benutzernamen eingeben
+login-password = Passwort
+login-password-placeholder = Passwort eingeben
+login-submit = Anmelden
+
+confirmed-title = Anmeldung bestätigt
+confirmed-message = Deine Anmeldung für { $list } ist bestätigt. Vielen Dank!
+
+unsubscribe-title = Abmelden
+unsubscribe-explanation = Du erhältst diesen Newsletter nicht mehr.
+unsubscribe-submit = Abmelden
+unsubscribed-message = Du wurdest abgemeldet.
+
+# Confirmation email. `$list` is HTML-escaped in the HTML body.
+confirmation-subject = Willkommen!
+confirmation-text =
+    Willkommen bei { $list }!
+    Besuche { $link }, um deine Anmeldung zu bestätigen.
+confirmation-html = Willkommen bei { $list }!<br />Klicke <a href="{ $link }">hier</a>, um deine Anmeldung zu bestätigen.
@@ -0,0 +1,30 @@
+# Public pages
+home-title = Home
+home-welcome = Welcome to our newsletter!
+home-archive = Read past issues
+home-subscribe-name = Name
+home-subscribe-email = Email
+home-subscribe-honeypot = Leave this field empty
+home-subscribe-submit = Subscribe
+
+login-title = Login
+login-username = Username
+login-username-placeholder = Enter Username
+login-password = Password
+login-password-placeholder = Enter Password
+login-submit = Login
+
+confirmed-title = Subscription confirmed
+confirmed-message = Your subscription to { $list } is confirmed. Thank you!
+
+unsubscribe-title = Unsubscribe
+unsubscribe-explanation = You will no longer receive this newsletter.
+unsubscribe-submit = Unsubscribe
+unsubscribed-message = You have been unsubscribed.
+
+# Confirmation email. `$list` is HTML-escaped in the HTML body.
+confirmation-subject = Welcome!
+confirmation-text =
+    Welcome to { $list }!
+    Visit { $link } to confirm your subscription.
+confirmation-html = Welcome to { $list }!<br />Click <a href="{ $link }">here</a> to confirm your subscription.
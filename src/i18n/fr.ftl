@@ -0,0 +1,30 @@
+# Public pages
+home-title = Accueil
+home-welcome = Bienvenue dans notre newsletter !
+home-archive = Lire les numéros précédents
+home-subscribe-name = Nom
+home-subscribe-email = Adresse e-mail
+home-subscribe-honeypot = Laissez ce champ vide
+home-subscribe-submit = S’inscrire
+
+login-title = Connexion
+login-username = Nom d’utilisateur
+login-username-placeholder = Saisissez votre nom d’utilisateur
+login-password = Mot de passe
+login-password-placeholder = Saisissez votre mot de passe
+login-submit = Se connecter
+
+confirmed-title = Inscription confirmée
+confirmed-message = Votre inscription à { $list } est confirmée. Merci !
+
+unsubscribe-title = Se désinscrire
+unsubscribe-explanation = Vous ne recevrez plus cette newsletter.
+unsubscribe-submit = Se désinscrire
+unsubscribed-message = Vous avez été désinscrit.
+
+# Confirmation email. `$list` is HTML-escaped in the HTML body.
+confirmation-subject = Bienvenue !
+confirmation-text =
+    Bienvenue dans { $list } !
+    Rendez-vous sur { $link } pour confirmer votre inscription.
+confirmation-html = Bienvenue dans { $list } !<br />Cliquez <a href="{ $link }">ici</a> pour confirmer votre inscription.
@@ -0,0 +1,225 @@
+//! Translations of the public pages and of the emails we write ourselves.
+//! Newsletter issues are sent as their editors wrote them.
+
+use axum::extract::FromRequestParts;
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::http::request::Parts;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{NegotiationStrategy, accepted_languages, negotiate_languages};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use unic_langid::LanguageIdentifier;
+
+/// The languages we have a message catalog for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Fr,
+    De,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 3] = [Locale::En, Locale::Fr, Locale::De];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Fr => "fr",
+            Locale::De => "de",
+        }
+    }
+
+    fn catalog(&self) -> &'static str {
+        match self {
+            Locale::En => include_str!("en.ftl"),
+            Locale::Fr => include_str!("fr.ftl"),
+            Locale::De => include_str!("de.ftl"),
+        }
+    }
+
+    /// The supported locale closest to a language tag such as `fr-CA`.
+    pub fn parse(tag: &str) -> Option<Locale> {
+        let requested: LanguageIdentifier = tag.trim().parse().ok()?;
+        Self::negotiate(&[requested])
+    }
+
+    /// The best match for an `Accept-Language` header, the default locale
+    /// if none of the requested languages is supported.
+    pub fn from_accept_language(header: &str) -> Locale {
+        Self::negotiate(&accepted_languages::parse(header)).unwrap_or_default()
+    }
+
+    fn negotiate(requested: &[LanguageIdentifier]) -> Option<Locale> {
+        let available = Self::ALL.map(|l| l.as_str().parse::<LanguageIdentifier>().unwrap());
+        let negotiated =
+            negotiate_languages(requested, &available, None, NegotiationStrategy::Lookup);
+        let language = negotiated.first()?.language;
+        Self::ALL
+            .into_iter()
+            .find(|l| l.as_str() == language.as_str())
+    }
+
+    /// The message `id` in this locale, falling back to English for
+    /// messages that have not been translated yet.
+    pub fn translate(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        [*self, Locale::En]
+            .into_iter()
+            .find_map(|locale| {
+                let bundle = &CATALOGS[&locale];
+                let pattern = bundle.get_message(id)?.value()?;
+                let mut errors = vec![];
+                let message = bundle.format_pattern(pattern, args, &mut errors);
+                if !errors.is_empty() {
+                    tracing::warn!(
+                        id,
+                        locale = locale.as_str(),
+                        ?errors,
+                        "Failed to format a message"
+                    );
+                }
+                Some(message.into_owned())
+            })
+            .unwrap_or_else(|| {
+                tracing::error!(id, "Missing translation");
+                id.to_string()
+            })
+    }
+
+    /// A template registry whose `{{t "message-id" name=value}}` helper
+    /// translates into this locale.
+    pub fn templates(&self) -> Handlebars<'static> {
+        let mut reg = Handlebars::new();
+        reg.register_helper("t", Box::new(Translate(*self)));
+        reg
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+static CATALOGS: LazyLock<HashMap<Locale, FluentBundle<FluentResource>>> = LazyLock::new(|| {
+    Locale::ALL
+        .into_iter()
+        .map(|locale| {
+            let resource = FluentResource::try_new(locale.catalog().to_string())
+                .unwrap_or_else(|_| panic!("Invalid message catalog for {}.", locale));
+            let mut bundle = FluentBundle::new_concurrent(vec![locale.as_str().parse().unwrap()]);
+            // Unicode isolation marks would end up verbatim in plain-text
+            // emails.
+            bundle.set_use_isolating(false);
+            bundle
+                .add_resource(resource)
+                .unwrap_or_else(|_| panic!("Duplicate messages in the catalog for {}.", locale));
+            (locale, bundle)
+        })
+        .collect()
+});
+
+/// The locale a visitor asked for through `Accept-Language`.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestLocale(pub Locale);
+
+impl<S: Send + Sync> FromRequestParts<S> for RequestLocale {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let locale = parts
+            .headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|h| h.to_str().ok())
+            .map(Locale::from_accept_language)
+            .unwrap_or_default();
+        Ok(Self(locale))
+    }
+}
+
+struct Translate(Locale);
+
+impl HelperDef for Translate {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let id = h
+            .param(0)
+            .and_then(|p| p.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("t", 0))?;
+        let mut args = FluentArgs::new();
+        for (name, value) in h.hash() {
+            let value = match value.value() {
+                serde_json::Value::String(s) => FluentValue::from(s.clone()),
+                serde_json::Value::Number(n) => FluentValue::from(n.as_f64().unwrap_or_default()),
+                other => FluentValue::from(other.to_string()),
+            };
+            args.set(*name, value);
+        }
+        // Helper output is not escaped by Handlebars.
+        out.write(&htmlescape::encode_minimal(
+            &self.0.translate(id, Some(&args)),
+        ))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locale;
+
+    /// Messages start at the beginning of a line, comments and
+    /// continuation lines do not.
+    fn message_ids(locale: Locale) -> Vec<&'static str> {
+        locale
+            .catalog()
+            .lines()
+            .filter(|l| l.starts_with(|c: char| c.is_ascii_lowercase()))
+            .filter_map(|l| l.split_once(" =").map(|(id, _)| id))
+            .collect()
+    }
+
+    #[test]
+    fn every_catalog_translates_every_message() {
+        let english = message_ids(Locale::En);
+        for locale in Locale::ALL {
+            assert_eq!(message_ids(locale), english, "{} is out of sync", locale);
+        }
+    }
+
+    #[test]
+    fn regional_variants_fall_back_to_their_language() {
+        assert_eq!(Locale::parse("fr-CA"), Some(Locale::Fr));
+        assert_eq!(Locale::parse("pt-BR"), None);
+    }
+
+    #[test]
+    fn accept_language_preferences_are_honoured() {
+        assert_eq!(
+            Locale::from_accept_language("pt-BR, de;q=0.8, fr;q=0.5"),
+            Locale::De
+        );
+        assert_eq!(Locale::from_accept_language("pt-BR"), Locale::En);
+        assert_eq!(Locale::from_accept_language(""), Locale::En);
+    }
+
+    #[test]
+    fn messages_are_formatted_with_their_arguments() {
+        let mut args = fluent_bundle::FluentArgs::new();
+        args.set("list", "Rust Weekly");
+        assert_eq!(
+            Locale::Fr.translate("confirmed-message", Some(&args)),
+            "Votre inscription à Rust Weekly est confirmée. Merci !"
+        );
+    }
+}
@@ -1,12 +1,68 @@
-use axum::response::{IntoResponse, Response};
+use anyhow::Context;
+use axum::body::{Body, to_bytes};
+use axum::http::{HeaderName, HeaderValue};
+use axum::response::Response;
+use chrono::Utc;
 use entity::entities::{idempotency, prelude::Idempotency};
 use reqwest::StatusCode;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DatabaseTransaction, EntityTrait,
+    QueryFilter, Set, TransactionTrait, Unchanged,
+};
 use uuid::Uuid;
 
 use super::IdempotencyKey;
 
-pub async fn get_saved_response(
+/// A response header as stored. Values that are not valid UTF-8 are not
+/// kept, none of ours are.
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HeaderPairRecord {
+    name: String,
+    value: String,
+}
+
+pub enum NextAction {
+    /// The key is ours: the transaction holds on to it until the
+    /// response is saved, and lets it go if it is dropped instead.
+    StartProcessing(DatabaseTransaction),
+    ReturnSavedResponse(Response),
+}
+
+/// Claim the key, or get the response of the request that did. A
+/// concurrent request with the same key waits for that one to finish.
+pub async fn try_processing(
+    db_connection: &DatabaseConnection,
+    idempotency_key: &IdempotencyKey,
+    user_id: Uuid,
+) -> Result<NextAction, anyhow::Error> {
+    let transaction = db_connection.begin().await?;
+    let inserted = Idempotency::insert(idempotency::ActiveModel {
+        user_id: Set(user_id),
+        idempotency_key: Set(idempotency_key.as_ref().to_owned()),
+        created_at: Set(Utc::now().into()),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::columns([
+            idempotency::Column::UserId,
+            idempotency::Column::IdempotencyKey,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(&transaction)
+    .await?;
+    if inserted > 0 {
+        return Ok(NextAction::StartProcessing(transaction));
+    }
+    let saved_response = get_saved_response(db_connection, idempotency_key, user_id)
+        .await?
+        .context("We expected a saved response, we didn't find it.")?;
+    Ok(NextAction::ReturnSavedResponse(saved_response))
+}
+
+async fn get_saved_response(
     db_connection: &DatabaseConnection,
     idempotency_key: &IdempotencyKey,
     user_id: Uuid,
@@ -16,18 +72,61 @@ pub async fn get_saved_response(
         .filter(idempotency::Column::IdempotencyKey.eq(idempotency_key.as_ref()))
         .one(db_connection)
         .await?;
-    if let Some(r) = saved_response {
-        let status_code = StatusCode::from_u16(r.response_status_code.try_into()?)?;
-        let mut response = Response::new(status_code);
+    let Some(idempotency::Model {
+        response_status_code: Some(status_code),
+        response_headers,
+        response_body,
+        ..
+    }) = saved_response
+    else {
+        return Ok(None);
+    };
+    let mut response = Response::new(Body::from(response_body.unwrap_or_default()));
+    *response.status_mut() = StatusCode::from_u16(status_code.try_into()?)?;
+    let headers: Vec<HeaderPairRecord> = match response_headers {
+        Some(headers) => serde_json::from_value(headers)?,
+        None => Vec::new(),
+    };
+    for HeaderPairRecord { name, value } in headers {
+        response
+            .headers_mut()
+            .append(HeaderName::try_from(name)?, HeaderValue::try_from(value)?);
     }
-    Ok(Some("".into_response()))
+    Ok(Some(response))
 }
 
+/// Store the response for the key claimed by `try_processing` and let
+/// the requests waiting on it through.
 pub async fn save_response(
-    db_connection: &DatabaseConnection,
+    transaction: DatabaseTransaction,
     idempotency_key: &IdempotencyKey,
     user_id: Uuid,
-    http_response: &Response,
-) -> Result<(), anyhow::Error> {
-    Ok(())
+    http_response: Response,
+) -> Result<Response, anyhow::Error> {
+    let (response_head, body) = http_response.into_parts();
+    let body = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to read the response body: {}", e))?;
+    let headers: Vec<HeaderPairRecord> = response_head
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some(HeaderPairRecord {
+                name: name.as_str().to_owned(),
+                value: value.to_str().ok()?.to_owned(),
+            })
+        })
+        .collect();
+    idempotency::ActiveModel {
+        user_id: Unchanged(user_id),
+        idempotency_key: Unchanged(idempotency_key.as_ref().to_owned()),
+        response_status_code: Set(Some(response_head.status.as_u16().try_into()?)),
+        response_headers: Set(Some(serde_json::to_value(headers)?)),
+        response_body: Set(Some(body.to_vec())),
+        ..Default::default()
+    }
+    .update(&transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(Response::from_parts(response_head, Body::from(body)))
 }
@@ -0,0 +1,302 @@
+use anyhow::Context;
+use chrono::Utc;
+use entity::entities::{
+    issue_delivery_queue, list_memberships, newsletter_issue_lists, newsletter_issues, prelude::*,
+    subscriptions,
+};
+use migration::SimpleExpr;
+use sea_orm::prelude::*;
+use sea_orm::sea_query::{LockBehavior, LockType};
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DatabaseTransaction, EntityTrait, PaginatorTrait,
+    QueryFilter, QuerySelect, Set, TransactionTrait, Value,
+};
+
+use crate::domain::{IssueSlug, IssueTemplate, SubscriberEmail, TemplateVariables};
+use crate::email_client::{EmailClient, SendEmailError};
+use crate::issue_links::IssueLinks;
+use crate::suppression::not_suppressed;
+use crate::telemetry::Redacted;
+
+/// Queue an email to every confirmed member of the target lists of an
+/// issue. Members of several target lists are only queued once,
+/// suppressed addresses not at all.
+/// Meant to run in the transaction that moves the issue into `sending`.
+#[tracing::instrument(name = "Queue the emails of a newsletter issue", skip(connection))]
+pub async fn enqueue_delivery<C: ConnectionTrait>(
+    connection: &C,
+    issue_id: Uuid,
+) -> Result<(), anyhow::Error> {
+    let list_ids = NewsletterIssueLists::find()
+        .filter(newsletter_issue_lists::Column::IssueId.eq(issue_id))
+        .all(connection)
+        .await
+        .context("Failed to fetch the target lists of a newsletter issue.")?
+        .into_iter()
+        .map(|l| l.list_id)
+        .collect::<Vec<_>>();
+    let subscriber_ids: Vec<Uuid> = Subscriptions::find()
+        .select_only()
+        .column(subscriptions::Column::Id)
+        .inner_join(ListMemberships)
+        .filter(list_memberships::Column::ListId.is_in(list_ids))
+        .filter(list_memberships::Column::Status.eq("confirmed"))
+        .filter(not_suppressed())
+        .distinct()
+        .into_tuple()
+        .all(connection)
+        .await
+        .context("Failed to fetch confirmed subscribers.")?;
+    if subscriber_ids.is_empty() {
+        return Ok(());
+    }
+    IssueDeliveryQueue::insert_many(subscriber_ids.into_iter().map(|subscriber_id| {
+        issue_delivery_queue::ActiveModel {
+            issue_id: Set(issue_id),
+            subscriber_id: Set(subscriber_id),
+        }
+    }))
+    .exec_without_returning(connection)
+    .await
+    .context("Failed to queue the emails of a newsletter issue.")?;
+    Ok(())
+}
+
+/// Send the queued emails of an issue and record the outcome on it:
+/// `published` once none are left, `failed` if some could not be sent.
+/// Those stay queued, for the scheduler to retry.
+/// The caller is expected to have moved the issue into the `sending` state
+/// and queued its emails.
+#[tracing::instrument(
+    name = "Deliver and publish a newsletter issue",
+    skip(db_connection, email_client, links, issue),
+    fields(issue_id = %issue.id)
+)]
+pub async fn deliver_and_publish(
+    db_connection: &DatabaseConnection,
+    email_client: &EmailClient,
+    links: &IssueLinks,
+    issue: &newsletter_issues::Model,
+) -> Result<(), anyhow::Error> {
+    if let Err(e) = deliver_issue(db_connection, email_client, links, issue).await {
+        NewsletterIssues::update_many()
+            .col_expr(
+                newsletter_issues::Column::State,
+                SimpleExpr::Value(Value::String(Some(Box::new("failed".to_string())))),
+            )
+            .filter(newsletter_issues::Column::Id.eq(issue.id))
+            .filter(newsletter_issues::Column::State.eq("sending"))
+            .exec(db_connection)
+            .await
+            .context("Failed to record the delivery outcome of a newsletter issue.")?;
+        return Err(e);
+    }
+    if pending_emails(db_connection, issue.id).await? > 0 {
+        // Still being sent by another delivery, which publishes it.
+        return Ok(());
+    }
+
+    // The emails are out, so the issue is published either way, under a
+    // slug nothing else can have taken if need be.
+    let mut slug_error = None;
+    let slug = archive_slug(db_connection, issue)
+        .await
+        .unwrap_or_else(|e| {
+            slug_error = Some(e);
+            IssueSlug::from_title(&issue.title).with_suffix(&issue.id.simple().to_string())
+        });
+    NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::State,
+            SimpleExpr::Value(Value::String(Some(Box::new("published".to_string())))),
+        )
+        .col_expr(
+            newsletter_issues::Column::PublishedAt,
+            SimpleExpr::Value(Value::ChronoDateTimeWithTimeZone(Some(Box::new(
+                Utc::now().into(),
+            )))),
+        )
+        .col_expr(
+            newsletter_issues::Column::Slug,
+            SimpleExpr::Value(Value::String(Some(Box::new(slug.to_string())))),
+        )
+        .filter(newsletter_issues::Column::Id.eq(issue.id))
+        .filter(newsletter_issues::Column::State.is_in(["sending", "failed"]))
+        .exec(db_connection)
+        .await
+        .context("Failed to record the delivery outcome of a newsletter issue.")?;
+    if let Some(e) = slug_error {
+        return Err(e.context("The issue was published under a fallback archive slug."));
+    }
+    Ok(())
+}
+
+/// The number of emails of an issue that have yet to go out.
+pub async fn pending_emails(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+) -> Result<u64, anyhow::Error> {
+    IssueDeliveryQueue::find()
+        .filter(issue_delivery_queue::Column::IssueId.eq(issue_id))
+        .count(db_connection)
+        .await
+        .context("Failed to count the queued emails of a newsletter issue.")
+}
+
+/// The slug the issue is archived under, derived from its title.
+/// Issues sharing a title are told apart by the start of their id.
+async fn archive_slug(
+    db_connection: &DatabaseConnection,
+    issue: &newsletter_issues::Model,
+) -> Result<IssueSlug, anyhow::Error> {
+    let slug = IssueSlug::from_title(&issue.title);
+    let taken = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::Slug.eq(slug.as_ref()))
+        .one(db_connection)
+        .await
+        .context("Failed to check whether an archive slug is taken.")?
+        .is_some();
+    if taken {
+        Ok(slug.with_suffix(&issue.id.simple().to_string()[..8]))
+    } else {
+        Ok(slug)
+    }
+}
+
+/// Send the queued emails of an issue, personalised for each subscriber.
+/// An email leaves the queue in the transaction that sends it, which
+/// keeps any other delivery of the issue from sending it as well; the
+/// ones that cannot be sent are left queued.
+#[tracing::instrument(
+    name = "Deliver a newsletter issue",
+    skip(db_connection, email_client, links, issue)
+)]
+async fn deliver_issue(
+    db_connection: &DatabaseConnection,
+    email_client: &EmailClient,
+    links: &IssueLinks,
+    issue: &newsletter_issues::Model,
+) -> Result<(), anyhow::Error> {
+    let template = IssueTemplate::parse(&issue.html_content, &issue.text_content)
+        .map_err(anyhow::Error::msg)?;
+    let subscriber_ids: Vec<Uuid> = IssueDeliveryQueue::find()
+        .select_only()
+        .column(issue_delivery_queue::Column::SubscriberId)
+        .filter(issue_delivery_queue::Column::IssueId.eq(issue.id))
+        .into_tuple()
+        .all(db_connection)
+        .await
+        .context("Failed to fetch the queued emails of a newsletter issue.")?;
+    let mut failed = 0;
+    let mut last_error = None;
+    for subscriber_id in subscriber_ids {
+        let transaction = db_connection
+            .begin()
+            .await
+            .context("Failed to start a transaction.")?;
+        let Some(subscriber) = dequeue(&transaction, issue.id, subscriber_id).await? else {
+            // Sent, or being sent, by another delivery of the issue.
+            continue;
+        };
+        match send_issue(email_client, links, issue, &template, subscriber).await {
+            Ok(()) => transaction
+                .commit()
+                .await
+                .context("Failed to take a sent email off the queue.")?,
+            // Dropping the transaction puts the email back on the queue.
+            Err(e) => {
+                tracing::warn!(
+                    error.cause_chain = ?e,
+                    error.message = %e,
+                    "Leaving an email of a newsletter issue queued",
+                );
+                failed += 1;
+                last_error = Some(e);
+            }
+        }
+    }
+    match last_error {
+        Some(e) => Err(e.context(format!(
+            "Failed to send the newsletter issue to {} subscribers.",
+            failed
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Take the email to a subscriber off the queue, within `transaction`,
+/// and fetch the subscriber. Returns `None` if it is no longer queued or
+/// another delivery holds it.
+async fn dequeue(
+    transaction: &DatabaseTransaction,
+    issue_id: Uuid,
+    subscriber_id: Uuid,
+) -> Result<Option<subscriptions::Model>, anyhow::Error> {
+    let queued = IssueDeliveryQueue::find_by_id((issue_id, subscriber_id))
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .one(transaction)
+        .await
+        .context("Failed to fetch a queued email.")?;
+    if queued.is_none() {
+        return Ok(None);
+    }
+    IssueDeliveryQueue::delete_by_id((issue_id, subscriber_id))
+        .exec(transaction)
+        .await
+        .context("Failed to take an email off the queue.")?;
+    Subscriptions::find_by_id(subscriber_id)
+        .one(transaction)
+        .await
+        .context("Failed to fetch the recipient of a queued email.")
+}
+
+/// Send the issue to one subscriber. Subscribers whose address is invalid
+/// or suppressed are skipped.
+async fn send_issue(
+    email_client: &EmailClient,
+    links: &IssueLinks,
+    issue: &newsletter_issues::Model,
+    template: &IssueTemplate,
+    subscriber: subscriptions::Model,
+) -> Result<(), anyhow::Error> {
+    let email = match SubscriberEmail::parse(subscriber.email) {
+        Ok(email) => email,
+        Err(error) => {
+            tracing::warn!(
+                error.cause_chain = ?error,
+                "Skipping a confirmed subscriber. \
+                Their stored contact details are invalid",
+            );
+            return Ok(());
+        }
+    };
+    let (html_content, text_content) = template
+        .render(&TemplateVariables {
+            name: &subscriber.name,
+            email: email.as_ref(),
+            unsubscribe_url: &links.unsubscribe_url(subscriber.id, issue.id),
+            web_view_url: &links.web_view_url(issue.id),
+            locale: &subscriber.locale,
+        })
+        .map_err(anyhow::Error::msg)?;
+    let html_content = if issue.tracking_enabled && links.tracking_enabled() {
+        links.track_html(&html_content, subscriber.id, issue.id)
+    } else {
+        html_content
+    };
+    match email_client
+        .send_email(&email, &issue.title, &html_content, &text_content)
+        .await
+    {
+        Ok(()) => Ok(()),
+        // Suppressed after the email was queued.
+        Err(SendEmailError::Suppressed) => {
+            tracing::info!("Skipping a subscriber on the suppression list");
+            Ok(())
+        }
+        Err(e) => Err(anyhow::Error::new(e).context(format!(
+            "Failed to send newsletter issue to {}",
+            Redacted::email(&email)
+        ))),
+    }
+}
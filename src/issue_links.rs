@@ -0,0 +1,281 @@
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use hmac::{Hmac, Mac};
+use secrecy::ExposeSecret;
+use uuid::Uuid;
+
+use crate::startup::HmacSecret;
+
+const CLICK: &str = "c";
+const OPEN: &str = "o";
+
+/// Builds the per-recipient links embedded in newsletter issues.
+/// Links that act on a subscriber's behalf carry an HMAC tag, so they
+/// cannot be forged by tweaking the ids in the query string.
+#[derive(Debug, Clone)]
+pub struct IssueLinks {
+    base_url: String,
+    secret: HmacSecret,
+    tracking_enabled: bool,
+}
+
+/// What a verified tracking link was issued for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingToken {
+    pub issue_id: Uuid,
+    pub subscriber_id: Uuid,
+    /// The original link target, for clicks only.
+    pub url: Option<String>,
+}
+
+impl IssueLinks {
+    /// `tracking_enabled` is the deployment-wide switch; issues opt in on
+    /// top of it.
+    pub fn new(base_url: String, secret: HmacSecret, tracking_enabled: bool) -> Self {
+        Self {
+            base_url,
+            secret,
+            tracking_enabled,
+        }
+    }
+
+    pub fn tracking_enabled(&self) -> bool {
+        self.tracking_enabled
+    }
+
+    /// Where the issue can be read in a browser.
+    pub fn web_view_url(&self, issue_id: Uuid) -> String {
+        format!("{}/issues/{}", self.base_url, issue_id)
+    }
+
+    /// Unsubscribes the recipient from the lists the issue was sent to.
+    pub fn unsubscribe_url(&self, subscriber_id: Uuid, issue_id: Uuid) -> String {
+        let query_string = unsubscribe_query(subscriber_id, issue_id);
+        let tag = self.tag(&query_string);
+        format!(
+            "{}/subscriptions/unsubscribe?{}&tag={}",
+            self.base_url, query_string, tag
+        )
+    }
+
+    pub fn verify_unsubscribe(&self, subscriber_id: Uuid, issue_id: Uuid, tag: &str) -> bool {
+        self.verify(&unsubscribe_query(subscriber_id, issue_id), tag)
+    }
+
+    /// Route every web link of an HTML body through the click tracker and
+    /// add an open-tracking pixel. The unsubscribe link is left alone.
+    pub fn track_html(&self, html: &str, subscriber_id: Uuid, issue_id: Uuid) -> String {
+        let unsubscribe_prefix = format!("{}/subscriptions/unsubscribe", self.base_url);
+        let html = rewrite_links(html, |url| {
+            let is_web_link = url.starts_with("http://") || url.starts_with("https://");
+            (is_web_link && !url.starts_with(&unsubscribe_prefix))
+                .then(|| self.click_url(subscriber_id, issue_id, url))
+        });
+        let pixel = format!(
+            r#"<img src="{}" width="1" height="1" alt="" />"#,
+            self.open_url(subscriber_id, issue_id)
+        );
+        match html.to_ascii_lowercase().rfind("</body>") {
+            Some(i) => format!("{}{}{}", &html[..i], pixel, &html[i..]),
+            None => html + &pixel,
+        }
+    }
+
+    pub fn click_url(&self, subscriber_id: Uuid, issue_id: Uuid, url: &str) -> String {
+        let token = self.tracking_token(&[
+            CLICK,
+            &issue_id.to_string(),
+            &subscriber_id.to_string(),
+            url,
+        ]);
+        format!("{}/t/c/{}", self.base_url, token)
+    }
+
+    pub fn open_url(&self, subscriber_id: Uuid, issue_id: Uuid) -> String {
+        let token = self.tracking_token(&[OPEN, &issue_id.to_string(), &subscriber_id.to_string()]);
+        format!("{}/t/o/{}", self.base_url, token)
+    }
+
+    pub fn verify_click(&self, token: &str) -> Option<TrackingToken> {
+        let payload = self.verify_tracking_token(token)?;
+        match payload.splitn(4, '\n').collect::<Vec<_>>().as_slice() {
+            [CLICK, issue_id, subscriber_id, url] => Some(TrackingToken {
+                issue_id: issue_id.parse().ok()?,
+                subscriber_id: subscriber_id.parse().ok()?,
+                url: Some(url.to_string()),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn verify_open(&self, token: &str) -> Option<TrackingToken> {
+        let payload = self.verify_tracking_token(token)?;
+        match payload.splitn(4, '\n').collect::<Vec<_>>().as_slice() {
+            [OPEN, issue_id, subscriber_id] => Some(TrackingToken {
+                issue_id: issue_id.parse().ok()?,
+                subscriber_id: subscriber_id.parse().ok()?,
+                url: None,
+            }),
+            _ => None,
+        }
+    }
+
+    /// The fields travel in the token itself, followed by their tag,
+    /// so that no lookup table is needed to resolve a link.
+    fn tracking_token(&self, fields: &[&str]) -> String {
+        let payload = fields.join("\n");
+        format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(&payload),
+            self.tag(&payload)
+        )
+    }
+
+    /// Returns the payload of a token whose tag checks out.
+    fn verify_tracking_token(&self, token: &str) -> Option<String> {
+        let (payload, tag) = token.split_once('.')?;
+        let payload = String::from_utf8(URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        self.verify(&payload, tag).then_some(payload)
+    }
+
+    fn mac(&self, message: &str) -> Hmac<sha2::Sha256> {
+        let mut mac =
+            Hmac::<sha2::Sha256>::new_from_slice(self.secret.0.expose_secret().as_bytes()).unwrap();
+        mac.update(message.as_bytes());
+        mac
+    }
+
+    fn tag(&self, message: &str) -> String {
+        format!("{:x}", self.mac(message).finalize().into_bytes())
+    }
+
+    fn verify(&self, message: &str, tag: &str) -> bool {
+        match hex::decode(tag) {
+            Ok(tag) => self.mac(message).verify_slice(&tag).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+fn unsubscribe_query(subscriber_id: Uuid, issue_id: Uuid) -> String {
+    format!("subscriber_id={}&issue_id={}", subscriber_id, issue_id)
+}
+
+/// Replace the value of every `href` attribute for which `rewrite` returns
+/// a new URL. Entities in the original value are decoded before it is
+/// handed over; replacements are inserted as they are.
+fn rewrite_links(html: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    // ASCII lowercasing keeps byte offsets intact.
+    let lowercase = html.to_ascii_lowercase();
+    let mut rewritten = String::with_capacity(html.len());
+    let mut copied_up_to = 0;
+    let mut search_from = 0;
+    while let Some(i) = lowercase[search_from..].find("href=") {
+        let value_start = search_from + i + "href=".len() + 1;
+        let quote = match html.as_bytes().get(value_start - 1) {
+            Some(b'"') => '"',
+            Some(b'\'') => '\'',
+            _ => {
+                search_from = value_start - 1;
+                continue;
+            }
+        };
+        let Some(length) = html[value_start..].find(quote) else {
+            break;
+        };
+        let value_end = value_start + length;
+        let raw = &html[value_start..value_end];
+        let url = htmlescape::decode_html(raw).unwrap_or_else(|_| raw.to_string());
+        if let Some(new_url) = rewrite(&url) {
+            rewritten.push_str(&html[copied_up_to..value_start]);
+            rewritten.push_str(&new_url);
+            copied_up_to = value_end;
+        }
+        search_from = value_end;
+    }
+    rewritten.push_str(&html[copied_up_to..]);
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IssueLinks, rewrite_links};
+    use crate::startup::HmacSecret;
+    use claims::{assert_none, assert_some_eq};
+    use secrecy::SecretString;
+    use uuid::Uuid;
+
+    fn links() -> IssueLinks {
+        IssueLinks::new(
+            "https://news.example.com".to_string(),
+            HmacSecret(SecretString::from("secret")),
+            true,
+        )
+    }
+
+    #[test]
+    fn only_matching_links_are_rewritten() {
+        let html = r#"<a href="https://a.example">a</a> <A HREF='mailto:x@example.com'>b</A>"#;
+        let rewritten = rewrite_links(html, |url| {
+            url.starts_with("https://")
+                .then(|| "https://t.example".to_string())
+        });
+        assert_eq!(
+            rewritten,
+            r#"<a href="https://t.example">a</a> <A HREF='mailto:x@example.com'>b</A>"#
+        );
+    }
+
+    #[test]
+    fn entities_are_decoded_before_rewriting() {
+        let mut seen = Vec::new();
+        rewrite_links(r#"<a href="https://a.example/?x=1&amp;y=2">a</a>"#, |url| {
+            seen.push(url.to_string());
+            None
+        });
+        assert_eq!(seen, vec!["https://a.example/?x=1&y=2"]);
+    }
+
+    #[test]
+    fn unquoted_and_unterminated_attributes_are_left_alone() {
+        let html = r#"<a href=https://a.example>a</a><a href="https://b.example"#;
+        assert_eq!(rewrite_links(html, |_| Some("x".to_string())), html);
+    }
+
+    #[test]
+    fn click_tokens_round_trip() {
+        let (issue_id, subscriber_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let url = links().click_url(subscriber_id, issue_id, "https://a.example/?x=1&y=2");
+        let token = url.strip_prefix("https://news.example.com/t/c/").unwrap();
+        let tracked = links().verify_click(token).unwrap();
+        assert_eq!(tracked.issue_id, issue_id);
+        assert_eq!(tracked.subscriber_id, subscriber_id);
+        assert_some_eq!(tracked.url, "https://a.example/?x=1&y=2");
+        // An open token is not a click token and vice versa.
+        assert_none!(links().verify_open(token));
+    }
+
+    #[test]
+    fn tampered_tokens_are_rejected() {
+        let url = links().open_url(Uuid::new_v4(), Uuid::new_v4());
+        let token = url.strip_prefix("https://news.example.com/t/o/").unwrap();
+        let (payload, tag) = token.split_once('.').unwrap();
+        assert_none!(links().verify_open(&format!("{}A.{}", payload, tag)));
+        assert_none!(links().verify_open(&format!("{}.{}", payload, &tag[1..])));
+    }
+
+    #[test]
+    fn tracked_html_keeps_the_unsubscribe_link_and_gets_a_pixel() {
+        let (issue_id, subscriber_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let unsubscribe_url = links().unsubscribe_url(subscriber_id, issue_id);
+        let html = format!(
+            r#"<body><a href="https://a.example">a</a><a href="{}">u</a></body>"#,
+            unsubscribe_url
+        );
+        let tracked = links().track_html(&html, subscriber_id, issue_id);
+        assert!(!tracked.contains("https://a.example"));
+        assert!(tracked.contains(&unsubscribe_url));
+        assert!(tracked.contains(r#"<img src="https://news.example.com/t/o/"#));
+        assert!(tracked.ends_with(r#"width="1" height="1" alt="" /></body>"#));
+    }
+}
@@ -1,10 +1,22 @@
 pub mod authentication;
+pub mod bot_protection;
 pub mod configuration;
 pub mod domain;
 pub mod email_client;
+pub mod i18n;
 pub mod idempotency;
+pub mod issue_delivery;
+pub mod issue_links;
+pub mod metrics;
+pub mod migrations;
+pub mod request_id;
 pub mod routes;
+pub mod scheduler;
+pub mod security_headers;
 pub mod session_state;
+pub mod shutdown;
 pub mod startup;
+pub mod suppression;
 pub mod telemetry;
+pub mod tls;
 pub mod utils;
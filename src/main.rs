@@ -1,17 +1,105 @@
+use std::fmt::{Debug, Display};
+use tokio::task::JoinError;
 use zero2prod::{
     configuration::get_configuration,
-    startup::Application,
-    telemetry::{get_subscriber, init_subscriber},
+    migrations::MigrateCommand,
+    scheduler::run_scheduler_until_stopped,
+    shutdown::shutdown_signal,
+    startup::{Application, connect_with_retry},
+    telemetry::{OtlpExporter, Redactor, get_subscriber, init_subscriber},
 };
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
+    let configuration = get_configuration().expect("Failed to read configuration.");
+
+    Redactor::new(
+        configuration.telemetry.redaction,
+        configuration.application.hmac_secret.clone(),
+    )
+    .init();
     // redirect all `log`'s events to our subscriber
-    let subscriber = get_subscriber("zero2prod".into(), "info".into(), std::io::stdout);
-    init_subscriber(subscriber);
+    let otlp = OtlpExporter::new(&configuration.telemetry)?;
+    let (subscriber, log_filter) = get_subscriber(
+        configuration.telemetry.service_name.clone(),
+        configuration.log.directives(),
+        configuration.log.format,
+        configuration.log.destination.is_terminal(),
+        configuration.log.writer()?,
+        otlp.as_ref(),
+    );
+    init_subscriber(subscriber, log_filter);
 
-    let configuration = get_configuration().expect("Failed to read configuration.");
-    let application = Application::build(configuration).await?;
-    application.run_until_stopped().await?;
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        None => {}
+        Some("migrate") => {
+            let command = MigrateCommand::parse(args)?;
+            let db_connection = connect_with_retry(&configuration.database).await?;
+            return command.run(&configuration.database, &db_connection).await;
+        }
+        Some(other) => anyhow::bail!(
+            "Unknown command `{}`. Run without arguments to serve the API, or: {}",
+            other,
+            zero2prod::migrations::USAGE
+        ),
+    }
+
+    let application = Application::build(configuration.clone()).await?;
+    let metrics = application.metrics();
+    let shutdown = application.shutdown_token();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.cancel();
+        }
+    });
+
+    let application_task = tokio::spawn(application.run_until_stopped());
+    let scheduler_task = tokio::spawn(run_scheduler_until_stopped(
+        configuration,
+        metrics,
+        shutdown.clone(),
+    ));
+    // When either task exits, the other is asked to stop as well.
+    let application_task = async {
+        let outcome = application_task.await;
+        shutdown.cancel();
+        report_exit("API", outcome)
+    };
+    let scheduler_task = async {
+        let outcome = scheduler_task.await;
+        shutdown.cancel();
+        report_exit("Newsletter scheduler", outcome)
+    };
+    tokio::join!(application_task, scheduler_task);
+    if let Some(otlp) = otlp {
+        tokio::task::spawn_blocking(move || otlp.shutdown()).await?;
+    }
     Ok(())
 }
+
+fn report_exit(task_name: &str, outcome: Result<Result<(), impl Debug + Display>, JoinError>) {
+    match outcome {
+        Ok(Ok(())) => {
+            tracing::info!("{} has exited", task_name)
+        }
+        Ok(Err(e)) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "{} failed",
+                task_name
+            )
+        }
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "{} task failed to complete",
+                task_name
+            )
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Prometheus metrics, exposed on `/metrics`.
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use sea_orm::DatabaseConnection;
+use tower_sessions_redis_store::fred::prelude::{ClientLike, Pool};
+
+/// Every metric of the application. Cloning is cheap, clones update the
+/// same values.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    redis_pool_connections: IntGaugeVec,
+    pub subscriptions: IntCounter,
+    pub confirmations: IntCounter,
+    emails_sent: IntCounterVec,
+    emails_failed: IntCounterVec,
+    pub idempotent_replays: IntCounter,
+}
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Metrics").finish_non_exhaustive()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let registry = Registry::new_custom(Some("zero2prod".into()), None).unwrap();
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests handled."),
+                &["method", "route", "status"],
+            )
+            .unwrap(),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "http_request_duration_seconds",
+                    "Time taken to handle HTTP requests.",
+                ),
+                &["method", "route"],
+            )
+            .unwrap(),
+            db_pool_connections: IntGaugeVec::new(
+                Opts::new("db_pool_connections", "Connections in the Postgres pool."),
+                &["state"],
+            )
+            .unwrap(),
+            redis_pool_connections: IntGaugeVec::new(
+                Opts::new("redis_pool_connections", "Connections in the Redis pool."),
+                &["state"],
+            )
+            .unwrap(),
+            subscriptions: IntCounter::new(
+                "subscriptions_total",
+                "Subscription requests awaiting confirmation.",
+            )
+            .unwrap(),
+            confirmations: IntCounter::new("confirmations_total", "Confirmed subscriptions.")
+                .unwrap(),
+            emails_sent: IntCounterVec::new(
+                Opts::new("emails_sent_total", "Emails accepted by the provider."),
+                &["provider"],
+            )
+            .unwrap(),
+            emails_failed: IntCounterVec::new(
+                Opts::new("emails_failed_total", "Emails the provider did not accept."),
+                &["provider"],
+            )
+            .unwrap(),
+            idempotent_replays: IntCounter::new(
+                "idempotent_replays_total",
+                "Requests answered with a saved response.",
+            )
+            .unwrap(),
+            registry,
+        };
+        let collectors: [Box<dyn prometheus::core::Collector>; 9] = [
+            Box::new(metrics.http_requests.clone()),
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.db_pool_connections.clone()),
+            Box::new(metrics.redis_pool_connections.clone()),
+            Box::new(metrics.subscriptions.clone()),
+            Box::new(metrics.confirmations.clone()),
+            Box::new(metrics.emails_sent.clone()),
+            Box::new(metrics.emails_failed.clone()),
+            Box::new(metrics.idempotent_replays.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+
+    pub fn email_sent(&self, provider: &str) {
+        self.emails_sent.with_label_values(&[provider]).inc();
+    }
+
+    pub fn email_failed(&self, provider: &str) {
+        self.emails_failed.with_label_values(&[provider]).inc();
+    }
+
+    /// Read the pool sizes, which are not tracked as they change.
+    pub fn observe_pools(&self, db_connection: &DatabaseConnection, redis_pool: &Pool) {
+        let db_pool = db_connection.get_postgres_connection_pool();
+        let idle = db_pool.num_idle() as i64;
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set(db_pool.size() as i64 - idle);
+        let connected = redis_pool
+            .clients()
+            .iter()
+            .filter(|client| client.is_connected())
+            .count();
+        self.redis_pool_connections
+            .with_label_values(&["connected"])
+            .set(connected as i64);
+        self.redis_pool_connections
+            .with_label_values(&["disconnected"])
+            .set((redis_pool.size() - connected) as i64);
+    }
+
+    /// The Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+/// Count and time requests, per route template rather than per path so
+/// that ids do not each get their own series.
+pub async fn track_http_requests(
+    State(metrics): State<Metrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".into());
+    let method = request.method().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    metrics
+        .http_request_duration
+        .with_label_values(&[&method, &route])
+        .observe(start.elapsed().as_secs_f64());
+    metrics
+        .http_requests
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .inc();
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+
+    #[test]
+    fn metrics_are_rendered_in_the_text_format() {
+        let metrics = Metrics::new();
+        metrics.subscriptions.inc();
+        metrics.email_failed("postmark");
+        let text = metrics.render();
+        assert!(text.contains("# TYPE zero2prod_subscriptions_total counter"));
+        assert!(text.contains("zero2prod_subscriptions_total 1"));
+        assert!(text.contains(r#"zero2prod_emails_failed_total{provider="postmark"} 1"#));
+    }
+}
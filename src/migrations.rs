@@ -0,0 +1,195 @@
+//! Running the migrations of the `migration` crate from the main binary,
+//! with `zero2prod migrate` or on startup.
+use anyhow::Context;
+use migration::{Migrator, MigratorTrait};
+use sea_orm::sqlx::{self, Connection, PgConnection, postgres::PgPoolOptions};
+use sea_orm::{DatabaseConnection, DbErr, SqlxPostgresConnector};
+use sea_orm_migration::MigrationStatus;
+
+use crate::configuration::DatabaseSettings;
+
+/// Key of the Postgres advisory lock held while migrating. Any value
+/// works, as long as every instance uses the same one.
+pub const MIGRATION_LOCK_KEY: i64 = 0x7a32_705f_6d69_6772;
+
+pub const USAGE: &str = "Usage: zero2prod migrate up [STEPS] | down [STEPS] | status";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrateCommand {
+    /// Apply pending migrations, all of them unless a number is given.
+    Up {
+        steps: Option<u32>,
+    },
+    /// Roll back the last migrations, one unless a number is given.
+    Down {
+        steps: u32,
+    },
+    Status,
+}
+
+impl MigrateCommand {
+    /// Parse the arguments following `migrate`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, anyhow::Error> {
+        let mut args = args.into_iter();
+        let command = args.next();
+        let steps = args
+            .next()
+            .map(|steps| steps.parse::<u32>())
+            .transpose()
+            .with_context(|| format!("The number of steps is not a number. {}", USAGE))?;
+        if let Some(extra) = args.next() {
+            anyhow::bail!("Unexpected argument `{}`. {}", extra, USAGE);
+        }
+        match (command.as_deref(), steps) {
+            (Some("up"), steps) => Ok(Self::Up { steps }),
+            (Some("down"), steps) => Ok(Self::Down {
+                steps: steps.unwrap_or(1),
+            }),
+            (Some("status"), None) => Ok(Self::Status),
+            _ => anyhow::bail!("{}", USAGE),
+        }
+    }
+
+    pub async fn run(
+        self,
+        configuration: &DatabaseSettings,
+        db_connection: &DatabaseConnection,
+    ) -> Result<(), anyhow::Error> {
+        match self {
+            Self::Up { steps } => {
+                with_migration_lock(configuration, async |db_connection| {
+                    Migrator::up(&db_connection, steps).await
+                })
+                .await
+            }
+            Self::Down { steps } => {
+                with_migration_lock(configuration, async |db_connection| {
+                    Migrator::down(&db_connection, Some(steps)).await
+                })
+                .await
+            }
+            Self::Status => {
+                for (name, status) in migration_status(db_connection).await? {
+                    println!("{:<8} {}", status, name);
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Every migration, in order, with whether it was applied.
+pub async fn migration_status(
+    db_connection: &DatabaseConnection,
+) -> Result<Vec<(String, MigrationStatus)>, anyhow::Error> {
+    let migrations = Migrator::get_migration_with_status(db_connection)
+        .await
+        .context("Failed to read the migration status.")?;
+    Ok(migrations
+        .iter()
+        .map(|migration| (migration.name().to_owned(), migration.status()))
+        .collect())
+}
+
+/// Apply pending migrations. When several instances start at once, one
+/// of them applies them while the others wait.
+#[tracing::instrument(name = "Apply pending migrations", skip_all)]
+pub async fn migrate_on_start(configuration: &DatabaseSettings) -> Result<(), anyhow::Error> {
+    with_migration_lock(configuration, async |db_connection| {
+        Migrator::up(&db_connection, None).await
+    })
+    .await
+}
+
+/// Run `migrate` while holding the advisory lock. The lock lives on a
+/// connection of its own and goes away with it if the process dies.
+///
+/// Neither connection has the configured `statement_timeout`: waiting
+/// for another instance to finish, or a migration rewriting a large
+/// table, may take longer than any query serving a request should.
+async fn with_migration_lock(
+    configuration: &DatabaseSettings,
+    migrate: impl AsyncFnOnce(DatabaseConnection) -> Result<(), DbErr>,
+) -> Result<(), anyhow::Error> {
+    let mut lock_connection = PgConnection::connect_with(&configuration.with_db())
+        .await
+        .context("Failed to connect to Postgres to take the migration lock.")?;
+    disable_statement_timeout(&mut lock_connection)
+        .await
+        .context("Failed to prepare the migration lock connection.")?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut lock_connection)
+        .await
+        .context("Failed to take the migration lock.")?;
+    let outcome = run_migrations(configuration, migrate).await;
+    if let Err(e) = lock_connection.close().await {
+        tracing::warn!(error = %e, "Failed to release the migration lock");
+    }
+    outcome
+}
+
+/// Run `migrate` on a connection of its own rather than the app pool.
+async fn run_migrations(
+    configuration: &DatabaseSettings,
+    migrate: impl AsyncFnOnce(DatabaseConnection) -> Result<(), DbErr>,
+) -> Result<(), anyhow::Error> {
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .after_connect(|connection, _| Box::pin(disable_statement_timeout(connection)))
+        .connect_with(configuration.with_db())
+        .await
+        .context("Failed to connect to Postgres to run the migrations.")?;
+    let outcome = migrate(SqlxPostgresConnector::from_sqlx_postgres_pool(pool.clone()))
+        .await
+        .context("Failed to run the migrations.");
+    pool.close().await;
+    outcome
+}
+
+async fn disable_statement_timeout(connection: &mut PgConnection) -> Result<(), sqlx::Error> {
+    sqlx::query("SET statement_timeout = 0")
+        .execute(connection)
+        .await
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MigrateCommand;
+
+    fn parse(args: &[&str]) -> Result<MigrateCommand, anyhow::Error> {
+        MigrateCommand::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn up_applies_every_pending_migration_by_default() {
+        assert_eq!(parse(&["up"]).unwrap(), MigrateCommand::Up { steps: None });
+        assert_eq!(
+            parse(&["up", "2"]).unwrap(),
+            MigrateCommand::Up { steps: Some(2) }
+        );
+    }
+
+    #[test]
+    fn down_rolls_back_one_migration_by_default() {
+        assert_eq!(parse(&["down"]).unwrap(), MigrateCommand::Down { steps: 1 });
+        assert_eq!(
+            parse(&["down", "3"]).unwrap(),
+            MigrateCommand::Down { steps: 3 }
+        );
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        for args in [
+            &[][..],
+            &["sideways"],
+            &["up", "many"],
+            &["status", "1"],
+            &["down", "1", "2"],
+        ] {
+            assert!(parse(args).is_err(), "{:?} was accepted", args);
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Tying every log line of a request together, and to what the client
+//! saw, with an `X-Request-Id`.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use axum::{
+    extract::{FromRequestParts, MatchedPath, Request},
+    http::{HeaderName, HeaderValue, request::Parts},
+    middleware::Next,
+    response::Response,
+};
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+use crate::authentication::UserId;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+/// Longer ids sent by clients are replaced rather than logged.
+const MAX_LENGTH: usize = 128;
+
+/// The id of the request being handled, either sent by the client (or a
+/// proxy in front of us) or generated.
+#[derive(Debug, Clone)]
+pub struct RequestId(String);
+
+impl RequestId {
+    fn generate() -> Self {
+        Self(Uuid::new_v4().to_string())
+    }
+
+    /// Only ids that are safe to log and to send back as they are.
+    fn parse(value: &HeaderValue) -> Option<Self> {
+        let value = value.to_str().ok()?;
+        let valid = !value.is_empty()
+            && value.len() <= MAX_LENGTH
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"-_.:".contains(&b));
+        valid.then(|| Self(value.to_owned()))
+    }
+}
+
+impl AsRef<str> for RequestId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    /// Outside of [`RequestIdLayer`] nobody else knows the id, any will do.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(RequestId::generate))
+    }
+}
+
+/// Accepts or generates the request id, records it on the request span
+/// (which every log line of the request is nested in) and sends it back.
+/// Expected to sit inside the layer creating the request span.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService { inner }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for RequestIdService<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let request_id = request
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(RequestId::parse)
+            .unwrap_or_else(RequestId::generate);
+        tracing::Span::current().record("request_id", request_id.as_ref());
+        request.extensions_mut().insert(request_id.clone());
+        // The clone may not be ready, use the service that was polled.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(async move {
+            let mut response = inner.call(request).await?;
+            if let Ok(value) = HeaderValue::try_from(request_id.0) {
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// One structured line per request once it was answered, within the
+/// request span so that it carries the request id.
+pub async fn access_log(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".into());
+    let start = Instant::now();
+    let response = next.run(request).await;
+    let user_id = response.extensions().get::<UserId>().copied();
+    tracing::event!(
+        target: "access_log",
+        tracing::Level::INFO,
+        http.request.method = %method,
+        http.route = %route,
+        http.response.status_code = response.status().as_u16(),
+        latency_ms = start.elapsed().as_millis() as u64,
+        user_id = user_id.map(tracing::field::display),
+        "Request completed",
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RequestId;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn ids_sent_by_clients_are_kept() {
+        let id = RequestId::parse(&HeaderValue::from_static("req-42_a.b:c")).unwrap();
+        assert_eq!(id.as_ref(), "req-42_a.b:c");
+    }
+
+    #[test]
+    fn unsafe_ids_are_replaced() {
+        for value in ["", "with space", "quote\"", "<script>"] {
+            assert!(
+                RequestId::parse(&HeaderValue::from_str(value).unwrap()).is_none(),
+                "{:?} was accepted",
+                value
+            );
+        }
+        let too_long = "a".repeat(129);
+        assert!(RequestId::parse(&HeaderValue::from_str(&too_long).unwrap()).is_none());
+    }
+}
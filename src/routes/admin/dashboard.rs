@@ -10,7 +10,11 @@ use reqwest::StatusCode;
 use sea_orm::{DatabaseConnection, EntityTrait};
 use uuid::Uuid;
 
-use crate::{authentication::UserId, routes::AppState, utils::e500};
+use crate::{
+    authentication::{UserId, is_owner},
+    routes::AppState,
+    utils::e500,
+};
 
 pub async fn admin_dashboard(
     State(state): State<AppState>,
@@ -20,12 +24,15 @@ pub async fn admin_dashboard(
     let username = get_username(*user_id, &state.db_connection)
         .await
         .map_err(e500)?;
+    let is_owner = is_owner(*user_id, &state.db_connection)
+        .await
+        .map_err(e500)?;
 
     let reg = Handlebars::new();
     let html = reg
         .render_template(
             include_str!("dashboard.html"),
-            &serde_json::json!({"username": username}),
+            &serde_json::json!({"username": username, "is_owner": is_owner}),
         )
         .map_err(e500)?;
     Ok((StatusCode::OK, Html::from(html)).into_response())
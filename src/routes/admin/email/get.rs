@@ -0,0 +1,43 @@
+use axum::{
+    Extension,
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
+use axum_messages::Messages;
+use entity::entities::prelude::Users;
+use handlebars::Handlebars;
+use sea_orm::EntityTrait;
+use std::fmt::Write;
+
+use crate::{authentication::UserId, routes::AppState, utils::e500};
+
+pub async fn change_email_form(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+) -> Result<Response, Response> {
+    let user_id = user_id.0;
+    let email = Users::find_by_id(*user_id)
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .and_then(|user| user.email);
+
+    let mut error_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(error_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("get.html"),
+            &serde_json::json!({
+                "error_html": error_html,
+                "email": email,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
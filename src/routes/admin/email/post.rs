@@ -0,0 +1,51 @@
+use axum::{
+    Extension, Form,
+    extract::State,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use entity::entities::users;
+use sea_orm::{ActiveModelTrait, Set};
+
+use crate::{
+    authentication::UserId, domain::SubscriberEmail, routes::AppState, telemetry::Redacted,
+    utils::e500,
+};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct FormData {
+    email: String,
+}
+
+#[tracing::instrument(
+    name = "Change the admin email address",
+    skip(state, flash, user_id, form),
+    fields(email = %Redacted::email(&form.email))
+)]
+pub async fn change_email(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Form(form): Form<FormData>,
+) -> Result<Response, Response> {
+    let user_id = user_id.0;
+    let email = match SubscriberEmail::parse(form.email) {
+        Ok(email) => email,
+        Err(e) => {
+            flash.error(e);
+            return Ok(Redirect::to("/admin/email").into_response());
+        }
+    };
+
+    users::ActiveModel {
+        user_id: Set(*user_id),
+        email: Set(Some(email.as_ref().to_string())),
+        ..Default::default()
+    }
+    .update(&state.db_connection)
+    .await
+    .map_err(e500)?;
+
+    flash.success("Your email address has been changed.");
+    Ok(Redirect::to("/admin/email").into_response())
+}
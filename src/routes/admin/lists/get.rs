@@ -0,0 +1,54 @@
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
+use axum_messages::Messages;
+use entity::entities::{list_memberships, lists, prelude::*};
+use handlebars::Handlebars;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use std::fmt::Write;
+
+use crate::{routes::AppState, utils::e500};
+
+pub async fn manage_lists_form(
+    State(state): State<AppState>,
+    flash: Messages,
+) -> Result<Response, Response> {
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let all_lists = Lists::find()
+        .order_by_asc(lists::Column::Name)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?;
+    let mut rows = Vec::with_capacity(all_lists.len());
+    for list in all_lists {
+        let confirmed = ListMemberships::find()
+            .filter(list_memberships::Column::ListId.eq(list.id))
+            .filter(list_memberships::Column::Status.eq("confirmed"))
+            .count(&state.db_connection)
+            .await
+            .map_err(e500)?;
+        rows.push(serde_json::json!({
+            "name": list.name,
+            "slug": list.slug,
+            "confirmed": confirmed,
+        }));
+    }
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("get.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "lists": rows,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
@@ -0,0 +1,63 @@
+use axum::{
+    Form,
+    extract::State,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use chrono::Utc;
+use entity::entities::lists;
+use sea_orm::{ActiveModelTrait, Set, prelude::DateTimeWithTimeZone};
+use uuid::Uuid;
+
+use crate::{
+    domain::ListSlug,
+    routes::{AppState, get_list_by_slug},
+    utils::e500,
+};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct FormData {
+    name: String,
+    slug: String,
+}
+
+#[tracing::instrument(name = "Create a mailing list", skip(state, flash))]
+pub async fn create_list(
+    State(state): State<AppState>,
+    flash: Messages,
+    Form(form): Form<FormData>,
+) -> Result<Response, Response> {
+    let name = form.name.trim();
+    if name.is_empty() {
+        flash.error("The list name cannot be empty.");
+        return Ok(Redirect::to("/admin/lists").into_response());
+    }
+    let slug = match ListSlug::parse(form.slug) {
+        Ok(slug) => slug,
+        Err(e) => {
+            flash.error(e);
+            return Ok(Redirect::to("/admin/lists").into_response());
+        }
+    };
+    if get_list_by_slug(&state.db_connection, &slug)
+        .await
+        .map_err(e500)?
+        .is_some()
+    {
+        flash.error(format!("A list with the slug {} already exists.", slug));
+        return Ok(Redirect::to("/admin/lists").into_response());
+    }
+
+    lists::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        slug: Set(slug.as_ref().to_string()),
+        name: Set(name.to_string()),
+        created_at: Set(DateTimeWithTimeZone::from(Utc::now())),
+    }
+    .insert(&state.db_connection)
+    .await
+    .map_err(e500)?;
+
+    flash.success(format!("The list {} has been created.", slug));
+    Ok(Redirect::to("/admin/lists").into_response())
+}
@@ -0,0 +1,31 @@
+use axum::response::{Html, IntoResponse, Response};
+use axum_messages::Messages;
+use handlebars::Handlebars;
+use std::fmt::Write;
+
+use crate::{telemetry::LogFilter, utils::e500};
+
+pub async fn log_level_form(flash: Messages) -> Result<Response, Response> {
+    let directives = LogFilter::global()
+        .ok_or_else(|| anyhow::anyhow!("No subscriber was set up."))
+        .and_then(LogFilter::current)
+        .map_err(e500)?;
+
+    let mut error_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(error_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("get.html"),
+            &serde_json::json!({
+                "error_html": error_html,
+                "directives": directives,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
@@ -0,0 +1,5 @@
+mod get;
+mod post;
+
+pub use get::log_level_form;
+pub use post::change_log_level;
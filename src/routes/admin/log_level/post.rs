@@ -0,0 +1,34 @@
+use axum::{
+    Extension, Form,
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+
+use crate::{authentication::UserId, telemetry::LogFilter, utils::e500};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct FormData {
+    directives: String,
+}
+
+#[tracing::instrument(name = "Change the log level", skip(flash, user_id), fields(user_id = %*user_id))]
+pub async fn change_log_level(
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Form(form): Form<FormData>,
+) -> Result<Response, Response> {
+    let log_filter = LogFilter::global()
+        .ok_or_else(|| anyhow::anyhow!("No subscriber was set up."))
+        .map_err(e500)?;
+    let directives = form.directives.trim();
+    // Logged beforehand, the new level could leave it out.
+    tracing::info!(directives, "Changing the log level");
+    match log_filter.set(directives) {
+        Ok(()) => flash.success("The log level has been changed."),
+        Err(e) => {
+            tracing::warn!(error.cause_chain = ?e, "Rejected the new log level");
+            flash.error("These are not valid filter directives.")
+        }
+    };
+    Ok(Redirect::to("/admin/log-level").into_response())
+}
@@ -1,9 +1,17 @@
 mod dashboard;
+mod email;
+mod lists;
+mod log_level;
 mod logout;
 mod newsletter;
 mod password;
+mod suppressions;
 
 pub use dashboard::*;
+pub use email::*;
+pub use lists::*;
+pub use log_level::*;
 pub use logout::*;
 pub use newsletter::*;
 pub use password::*;
+pub use suppressions::*;
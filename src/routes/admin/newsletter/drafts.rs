@@ -0,0 +1,381 @@
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_extra::extract::Form;
+use axum_messages::Messages;
+use entity::entities::{lists, newsletter_issue_lists, newsletter_issues, prelude::*};
+use handlebars::Handlebars;
+use migration::SimpleExpr;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, TransactionTrait, Value,
+};
+use std::fmt::Write;
+use uuid::Uuid;
+
+use super::post::{
+    PublishError, insert_newsletter_issue, parse_send_at, replace_issue_lists, resolve_lists,
+};
+use crate::{
+    domain::{IssueTemplate, NewsletterBody},
+    issue_delivery::{deliver_and_publish, enqueue_delivery},
+    routes::AppState,
+    telemetry::TraceContext,
+    utils::e500,
+};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct DraftFormData {
+    title: String,
+    #[serde(default)]
+    markdown_content: Option<String>,
+    #[serde(default)]
+    text_content: String,
+    #[serde(default)]
+    html_content: String,
+    #[serde(default)]
+    lists: Vec<String>,
+    #[serde(default)]
+    track: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct PublishDraftFormData {
+    #[serde(default)]
+    send_at: Option<String>,
+}
+
+pub async fn newsletter_drafts(
+    State(state): State<AppState>,
+    flash: Messages,
+) -> Result<Response, Response> {
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let drafts = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.eq("draft"))
+        .order_by_desc(newsletter_issues::Column::CreatedAt)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|issue| {
+            serde_json::json!({
+                "id": issue.id,
+                "title": issue.title,
+                "created_at": issue.created_at.to_rfc3339(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("drafts.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "drafts": drafts,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+#[tracing::instrument(name = "Save a newsletter draft", skip(state, flash, form))]
+pub async fn create_draft(
+    State(state): State<AppState>,
+    flash: Messages,
+    Form(form): Form<DraftFormData>,
+) -> Result<Response, Response> {
+    let body =
+        match NewsletterBody::parse(form.markdown_content, form.html_content, form.text_content) {
+            Ok(body) => body,
+            Err(e) => {
+                flash.error(e);
+                return Ok(Redirect::to("/admin/newsletters").into_response());
+            }
+        };
+    let list_ids = match resolve_lists(&state.db_connection, form.lists).await {
+        Ok(list_ids) => list_ids,
+        Err(e) => return Ok(flash_validation_error(flash, e, "/admin/newsletters")),
+    };
+    let transaction = state.db_connection.begin().await.map_err(e500)?;
+    let draft = insert_newsletter_issue(
+        &transaction,
+        &form.title,
+        &body,
+        "draft",
+        None,
+        &list_ids,
+        form.track,
+    )
+    .await
+    .map_err(e500)?;
+    transaction.commit().await.map_err(e500)?;
+
+    flash.success("The draft has been saved.");
+    Ok(Redirect::to(&format!("/admin/newsletters/drafts/{}", draft.id)).into_response())
+}
+
+pub async fn edit_draft_form(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(draft_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let Some(draft) = get_draft(&state.db_connection, draft_id)
+        .await
+        .map_err(e500)?
+    else {
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    };
+
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let selected = NewsletterIssueLists::find()
+        .filter(newsletter_issue_lists::Column::IssueId.eq(draft.id))
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|l| l.list_id)
+        .collect::<Vec<_>>();
+    let lists = Lists::find()
+        .order_by_asc(lists::Column::Name)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|l| {
+            serde_json::json!({
+                "slug": l.slug,
+                "name": l.name,
+                "checked": selected.contains(&l.id),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // Bodies rendered from the Markdown are left out of the form, so that
+    // saving renders them again; hand-written ones are kept as overrides.
+    let rendered = draft.markdown_content.clone().and_then(|markdown| {
+        NewsletterBody::parse(Some(markdown), String::new(), String::new()).ok()
+    });
+    let html_content = match &rendered {
+        Some(rendered) if rendered.html_content() == draft.html_content => "",
+        _ => &draft.html_content,
+    };
+    let text_content = match &rendered {
+        Some(rendered) if rendered.text_content() == draft.text_content => "",
+        _ => &draft.text_content,
+    };
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("draft.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "id": draft.id,
+                "title": draft.title,
+                "markdown_content": draft.markdown_content,
+                "text_content": text_content,
+                "html_content": html_content,
+                "lists": lists,
+                "tracking_available": state.tracking.enabled,
+                "tracking_enabled": draft.tracking_enabled,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+#[tracing::instrument(name = "Update a newsletter draft", skip(state, flash, form))]
+pub async fn update_draft(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(draft_id): Path<Uuid>,
+    Form(form): Form<DraftFormData>,
+) -> Result<Response, Response> {
+    let edit_url = format!("/admin/newsletters/drafts/{}", draft_id);
+    let body =
+        match NewsletterBody::parse(form.markdown_content, form.html_content, form.text_content) {
+            Ok(body) => body,
+            Err(e) => {
+                flash.error(e);
+                return Ok(Redirect::to(&edit_url).into_response());
+            }
+        };
+    let list_ids = match resolve_lists(&state.db_connection, form.lists).await {
+        Ok(list_ids) => list_ids,
+        Err(e) => return Ok(flash_validation_error(flash, e, &edit_url)),
+    };
+
+    let transaction = state.db_connection.begin().await.map_err(e500)?;
+    let result = NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::Title,
+            SimpleExpr::Value(Value::String(Some(Box::new(form.title)))),
+        )
+        .col_expr(
+            newsletter_issues::Column::TextContent,
+            SimpleExpr::Value(Value::String(Some(Box::new(
+                body.text_content().to_string(),
+            )))),
+        )
+        .col_expr(
+            newsletter_issues::Column::HtmlContent,
+            SimpleExpr::Value(Value::String(Some(Box::new(
+                body.html_content().to_string(),
+            )))),
+        )
+        .col_expr(
+            newsletter_issues::Column::MarkdownContent,
+            SimpleExpr::Value(Value::String(
+                body.markdown_content().map(|m| Box::new(m.to_string())),
+            )),
+        )
+        .col_expr(
+            newsletter_issues::Column::TrackingEnabled,
+            SimpleExpr::Value(Value::Bool(Some(form.track))),
+        )
+        .filter(newsletter_issues::Column::Id.eq(draft_id))
+        .filter(newsletter_issues::Column::State.eq("draft"))
+        .exec(&transaction)
+        .await
+        .map_err(e500)?;
+    if result.rows_affected != 1 {
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    }
+    replace_issue_lists(&transaction, draft_id, &list_ids)
+        .await
+        .map_err(e500)?;
+    transaction.commit().await.map_err(e500)?;
+
+    flash.success("The draft has been saved.");
+    Ok(Redirect::to(&edit_url).into_response())
+}
+
+/// Hand a draft over to delivery, either right away or at the chosen time.
+#[tracing::instrument(name = "Publish a newsletter draft", skip(state, flash))]
+pub async fn publish_draft(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(draft_id): Path<Uuid>,
+    Form(form): Form<PublishDraftFormData>,
+) -> Result<Response, Response> {
+    let edit_url = format!("/admin/newsletters/drafts/{}", draft_id);
+    let send_at = match parse_send_at(form.send_at) {
+        Ok(send_at) => send_at,
+        Err(e) => return Ok(flash_validation_error(flash, e, &edit_url)),
+    };
+    let Some(draft) = get_draft(&state.db_connection, draft_id)
+        .await
+        .map_err(e500)?
+    else {
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    };
+    if let Err(e) = IssueTemplate::parse(&draft.html_content, &draft.text_content) {
+        flash.error(e);
+        return Ok(Redirect::to(&edit_url).into_response());
+    }
+
+    let (next_state, send_at_value) = match send_at {
+        Some(send_at) => (
+            "scheduled",
+            Value::ChronoDateTimeWithTimeZone(Some(Box::new(send_at.into()))),
+        ),
+        None => ("sending", Value::ChronoDateTimeWithTimeZone(None)),
+    };
+    let transaction = state.db_connection.begin().await.map_err(e500)?;
+    let result = NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::State,
+            SimpleExpr::Value(Value::String(Some(Box::new(next_state.to_string())))),
+        )
+        .col_expr(
+            newsletter_issues::Column::SendAt,
+            SimpleExpr::Value(send_at_value),
+        )
+        .col_expr(
+            newsletter_issues::Column::TraceContext,
+            SimpleExpr::Value(Value::String(
+                TraceContext::current().encode().map(Box::new),
+            )),
+        )
+        .filter(newsletter_issues::Column::Id.eq(draft.id))
+        .filter(newsletter_issues::Column::State.eq("draft"))
+        .exec(&transaction)
+        .await
+        .map_err(e500)?;
+    if result.rows_affected != 1 {
+        // Published from another tab in the meantime.
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    }
+    if send_at.is_none() {
+        enqueue_delivery(&transaction, draft.id)
+            .await
+            .map_err(e500)?;
+    }
+    transaction.commit().await.map_err(e500)?;
+
+    if let Some(send_at) = send_at {
+        flash.info(format!(
+            "The newsletter issue has been scheduled for {}.",
+            send_at
+        ));
+        return Ok(Redirect::to("/admin/newsletters/scheduled").into_response());
+    }
+    match deliver_and_publish(
+        &state.db_connection,
+        &state.email_client,
+        &state.issue_links(),
+        &draft,
+    )
+    .await
+    {
+        Ok(()) => flash.info("The newsletter issue has been published!"),
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to deliver a newsletter issue",
+            );
+            flash.error(
+                "The newsletter issue could not be delivered to every subscriber. \
+                The rest of the emails will be retried.",
+            )
+        }
+    };
+    Ok(Redirect::to("/admin/newsletters/drafts").into_response())
+}
+
+/// Fetch an issue, provided it is still a draft.
+pub(super) async fn get_draft(
+    db_connection: &DatabaseConnection,
+    draft_id: Uuid,
+) -> Result<Option<newsletter_issues::Model>, sea_orm::DbErr> {
+    NewsletterIssues::find_by_id(draft_id)
+        .filter(newsletter_issues::Column::State.eq("draft"))
+        .one(db_connection)
+        .await
+}
+
+fn flash_validation_error(flash: Messages, e: PublishError, redirect_to: &str) -> Response {
+    match e {
+        PublishError::ValidationError(message) => {
+            flash.error(message);
+            Redirect::to(redirect_to).into_response()
+        }
+        e => e500(e),
+    }
+}
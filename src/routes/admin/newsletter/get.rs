@@ -1,14 +1,18 @@
 use axum::{
     Extension,
+    extract::State,
     response::{Html, IntoResponse, Response},
 };
 use axum_messages::Messages;
+use entity::entities::{lists, prelude::*};
 use handlebars::Handlebars;
+use sea_orm::{EntityTrait, QueryOrder};
 use std::fmt::Write;
 
-use crate::authentication::UserId;
+use crate::{authentication::UserId, routes::AppState, utils::e500};
 
 pub async fn publish_newsletter_form(
+    State(state): State<AppState>,
     flash: Messages,
     user_id: Extension<UserId>,
 ) -> Result<Response, Response> {
@@ -18,6 +22,14 @@ pub async fn publish_newsletter_form(
     for m in flash.into_iter() {
         writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
     }
+    let lists = Lists::find()
+        .order_by_asc(lists::Column::Name)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|l| serde_json::json!({"slug": l.slug, "name": l.name}))
+        .collect::<Vec<_>>();
     let reg = Handlebars::new();
     let html = reg
         .render_template(
@@ -25,6 +37,8 @@ pub async fn publish_newsletter_form(
             &serde_json::json!({
                 "messages": msg_html,
                 "idempotency_key": idempotency_key,
+                "lists": lists,
+                "tracking_available": state.tracking.enabled,
             }),
         )
         .expect("Failed to render password page.");
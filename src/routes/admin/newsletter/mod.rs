@@ -1,5 +1,15 @@
+mod drafts;
 mod get;
 mod post;
+mod preview;
+mod published;
+mod report;
+mod scheduled;
 
+pub use drafts::{create_draft, edit_draft_form, newsletter_drafts, publish_draft, update_draft};
 pub use get::publish_newsletter_form;
 pub use post::publish_newsletter;
+pub use preview::{preview_draft, send_test_email};
+pub use published::{hide_newsletter, published_newsletters, show_newsletter};
+pub use report::newsletter_report;
+pub use scheduled::{cancel_newsletter, reschedule_newsletter, scheduled_newsletters};
@@ -1,34 +1,52 @@
 use crate::authentication::UserId;
-use crate::domain::SubscriberEmail;
-use crate::idempotency::{IdempotencyKey, get_saved_response};
-use crate::routes::{AppState, error_chain_fmt};
-use crate::utils::{e400, e500};
+use crate::domain::{IssueTemplate, ListSlug, NewsletterBody, SendAt};
+use crate::idempotency::{IdempotencyKey, NextAction, save_response, try_processing};
+use crate::issue_delivery::{deliver_and_publish, enqueue_delivery};
+use crate::routes::{AppState, error_chain_fmt, get_list_by_slug};
+use crate::telemetry::TraceContext;
 use anyhow::Context;
+use axum::Extension;
 use axum::extract::State;
 use axum::http::HeaderValue;
-use axum::response::{IntoResponse, Response};
-use axum::{Extension, Form};
+use axum::http::header::LOCATION;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_extra::extract::Form;
 use axum_messages::Messages;
-use entity::entities::{prelude::*, subscriptions};
+use chrono::Utc;
+use entity::entities::{newsletter_issue_lists, newsletter_issues, prelude::*};
 use reqwest::StatusCode;
 use sea_orm::prelude::*;
-use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, DatabaseTransaction, QueryFilter, Set};
+
+const SCHEDULED_LOCATION: &str = "/admin/newsletters/scheduled";
 
 #[derive(Debug, serde::Deserialize)]
 pub struct FormData {
     title: String,
+    /// Rendered into both bodies unless they are written by hand.
+    #[serde(default)]
+    markdown_content: Option<String>,
+    #[serde(default)]
     text_content: String,
+    #[serde(default)]
     html_content: String,
     idempotency_key: String,
-}
-
-#[derive(Debug)]
-struct ConfirmedSubscriber {
-    email: SubscriberEmail,
+    /// Slugs of the lists to deliver the issue to, the default list when empty.
+    #[serde(default)]
+    lists: Vec<String>,
+    /// RFC 3339 timestamp to schedule the issue for, sent right away when empty.
+    #[serde(default)]
+    send_at: Option<String>,
+    /// Track opens and clicks, if the deployment allows it.
+    #[serde(default)]
+    track: bool,
 }
 
 #[derive(thiserror::Error)]
+#[allow(clippy::enum_variant_names)]
 pub enum PublishError {
+    #[error("{0}")]
+    ValidationError(String),
     #[error(transparent)]
     UnexpectedError(#[from] anyhow::Error),
     #[error("Authentication failed")]
@@ -44,6 +62,7 @@ impl std::fmt::Debug for PublishError {
 impl IntoResponse for PublishError {
     fn into_response(self) -> Response {
         match self {
+            PublishError::ValidationError(_) => StatusCode::BAD_REQUEST.into_response(),
             PublishError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
             PublishError::AuthError(_) => {
                 let mut response = StatusCode::UNAUTHORIZED.into_response();
@@ -69,63 +88,189 @@ pub async fn publish_newsletter(
     user_id: Extension<UserId>,
     Form(form): Form<FormData>,
 ) -> Result<Response, PublishError> {
-    let idempotency_key: IdempotencyKey = form.idempotency_key.try_into().map_err(e400).unwrap();
-    // Return early if er have a saved response in the database
-    if let Some(saved_response) =
-        get_saved_response(&state.db_connection, &idempotency_key, *user_id.0)
-            .await
-            .map_err(e500)
-            .unwrap()
+    let idempotency_key: IdempotencyKey = form
+        .idempotency_key
+        .try_into()
+        .map_err(|e: anyhow::Error| PublishError::ValidationError(e.to_string()))?;
+    let transaction =
+        match try_processing(&state.db_connection, &idempotency_key, *user_id.0).await? {
+            NextAction::StartProcessing(transaction) => transaction,
+            NextAction::ReturnSavedResponse(saved_response) => {
+                state.metrics.idempotent_replays.inc();
+                flash.info(replay_message(&saved_response));
+                return Ok(saved_response);
+            }
+        };
+
+    tracing::info!("Publishing a newsletter issue: {}", *user_id);
+    let body = NewsletterBody::parse(form.markdown_content, form.html_content, form.text_content)
+        .map_err(PublishError::ValidationError)?;
+    IssueTemplate::parse(body.html_content(), body.text_content())
+        .map_err(PublishError::ValidationError)?;
+    let send_at = parse_send_at(form.send_at)?;
+    let list_ids = resolve_lists(&state.db_connection, form.lists).await?;
+    // Stored along with the key, so that a retry cannot create it again.
+    let issue = insert_newsletter_issue(
+        &transaction,
+        &form.title,
+        &body,
+        if send_at.is_some() {
+            "scheduled"
+        } else {
+            "sending"
+        },
+        send_at,
+        &list_ids,
+        form.track,
+    )
+    .await
+    .context("Failed to store newsletter issue details.")?;
+
+    if let Some(send_at) = send_at {
+        let response = Redirect::to(SCHEDULED_LOCATION).into_response();
+        let response = save_response(transaction, &idempotency_key, *user_id.0, response).await?;
+        flash.info(format!(
+            "The newsletter issue has been scheduled for {}.",
+            send_at
+        ));
+        return Ok(response);
+    }
+    enqueue_delivery(&transaction, issue.id).await?;
+    let response = Redirect::to("/admin/newsletters").into_response();
+    let response = save_response(transaction, &idempotency_key, *user_id.0, response).await?;
+    match deliver_and_publish(
+        &state.db_connection,
+        &state.email_client,
+        &state.issue_links(),
+        &issue,
+    )
+    .await
     {
-        flash.info("The newsletter issue has been published!");
-        return Ok(saved_response);
+        Ok(()) => flash.info("The newsletter issue has been published!"),
+        Err(e) => {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to deliver a newsletter issue",
+            );
+            flash.error(
+                "The newsletter issue could not be delivered to every subscriber. \
+                The rest of the emails will be retried.",
+            )
+        }
+    };
+    Ok(response)
+}
+
+/// What the first submission was told, going by where it was sent.
+fn replay_message(saved_response: &Response) -> &'static str {
+    let location = saved_response
+        .headers()
+        .get(LOCATION)
+        .and_then(|location| location.to_str().ok());
+    if location == Some(SCHEDULED_LOCATION) {
+        "The newsletter issue has been scheduled."
+    } else {
+        "The newsletter issue has been published!"
+    }
+}
+
+/// An empty or missing send time means "send right away".
+pub(super) fn parse_send_at(send_at: Option<String>) -> Result<Option<SendAt>, PublishError> {
+    match send_at.as_deref().map(str::trim) {
+        Some(send_at) if !send_at.is_empty() => SendAt::parse(send_at.to_string(), Utc::now())
+            .map(Some)
+            .map_err(PublishError::ValidationError),
+        _ => Ok(None),
     }
+}
 
-    tracing::info!("Publishing a newsletter issue: {}", *user_id);
-    let subscribers = get_confirmed_subscribers(&state.db_connection).await?;
-    for subscriber in subscribers {
-        match subscriber {
-            Ok(subscriber) => {
-                state
-                    .email_client
-                    .send_email(
-                        &subscriber.email,
-                        &form.title,
-                        &form.html_content,
-                        &form.text_content,
-                    )
-                    .await
-                    .with_context(|| {
-                        format!("Failed to send newsletter issue to {}", subscriber.email)
-                    })?;
-            }
-            Err(error) => {
-                tracing::warn!(
-                    error.cause_chain = ?error,
-                    "Skipping a confirmed subscriber. \
-                    Their stored contact details are invalid",
-                )
-            }
+/// Store an issue with its target lists, as part of `transaction`.
+#[tracing::instrument(
+    name = "Saving newsletter issue details in the database",
+    skip(transaction, body)
+)]
+pub(super) async fn insert_newsletter_issue(
+    transaction: &DatabaseTransaction,
+    title: &str,
+    body: &NewsletterBody,
+    state: &str,
+    send_at: Option<SendAt>,
+    list_ids: &[Uuid],
+    tracking_enabled: bool,
+) -> Result<newsletter_issues::Model, sea_orm::DbErr> {
+    let issue = newsletter_issues::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        title: Set(title.to_string()),
+        text_content: Set(body.text_content().to_string()),
+        html_content: Set(body.html_content().to_string()),
+        markdown_content: Set(body.markdown_content().map(str::to_string)),
+        state: Set(state.to_string()),
+        send_at: Set(send_at.map(Into::into)),
+        created_at: Set(DateTimeWithTimeZone::from(Utc::now())),
+        published_at: Set(None),
+        slug: Set(None),
+        hidden: Set(false),
+        tracking_enabled: Set(tracking_enabled),
+        // Delivery happens later, from the scheduler, within this trace.
+        trace_context: Set(if state == "scheduled" {
+            TraceContext::current().encode()
+        } else {
+            None
+        }),
+    }
+    .insert(transaction)
+    .await?;
+    replace_issue_lists(transaction, issue.id, list_ids).await?;
+    Ok(issue)
+}
+
+/// Point an issue at exactly the given lists.
+pub(super) async fn replace_issue_lists(
+    transaction: &DatabaseTransaction,
+    issue_id: Uuid,
+    list_ids: &[Uuid],
+) -> Result<(), sea_orm::DbErr> {
+    NewsletterIssueLists::delete_many()
+        .filter(newsletter_issue_lists::Column::IssueId.eq(issue_id))
+        .exec(transaction)
+        .await?;
+    for list_id in list_ids {
+        newsletter_issue_lists::ActiveModel {
+            issue_id: Set(issue_id),
+            list_id: Set(*list_id),
         }
+        .insert(transaction)
+        .await?;
     }
-    Ok(StatusCode::OK.into_response())
+    Ok(())
 }
 
-#[tracing::instrument(name = "Get confirmed subscribers", skip(db_connection))]
-async fn get_confirmed_subscribers(
+/// Map the submitted list slugs to list ids, rejecting unknown lists.
+#[tracing::instrument(name = "Resolve target lists", skip(db_connection))]
+pub(super) async fn resolve_lists(
     db_connection: &DatabaseConnection,
-) -> Result<Vec<Result<ConfirmedSubscriber, anyhow::Error>>, anyhow::Error> {
-    let subscribers = Subscriptions::find()
-        .filter(subscriptions::Column::Status.eq("confirmed"))
-        .all(db_connection)
-        .await
-        .expect("Failed to fetch data.")
-        .into_iter()
-        .map(|r| match SubscriberEmail::parse(r.email) {
-            Ok(email) => Ok(ConfirmedSubscriber { email }),
-            Err(error) => Err(anyhow::anyhow!(error)),
-        })
-        .collect::<Vec<Result<ConfirmedSubscriber, anyhow::Error>>>();
+    slugs: Vec<String>,
+) -> Result<Vec<Uuid>, PublishError> {
+    let slugs = if slugs.is_empty() {
+        vec![ListSlug::default()]
+    } else {
+        slugs
+            .into_iter()
+            .map(ListSlug::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(PublishError::ValidationError)?
+    };
 
-    Ok(subscribers)
+    let mut list_ids = Vec::with_capacity(slugs.len());
+    for slug in slugs {
+        let list = get_list_by_slug(db_connection, &slug)
+            .await
+            .context("Failed to look up a target list.")?
+            .ok_or_else(|| {
+                PublishError::ValidationError(format!("{} is not a known list.", slug))
+            })?;
+        list_ids.push(list.id);
+    }
+    Ok(list_ids)
 }
@@ -0,0 +1,151 @@
+use axum::{
+    Extension,
+    extract::{Path, State},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use entity::entities::{newsletter_issues, prelude::Users};
+use handlebars::Handlebars;
+use sea_orm::EntityTrait;
+use uuid::Uuid;
+
+use super::drafts::get_draft;
+use crate::{
+    authentication::UserId,
+    domain::{IssueTemplate, SubscriberEmail, TemplateVariables},
+    i18n::Locale,
+    issue_links::IssueLinks,
+    routes::AppState,
+    utils::e500,
+};
+
+/// Show a draft the way subscribers will get it, both the HTML and the
+/// plain-text body, personalised as for a test email.
+pub async fn preview_draft(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Path(draft_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let Some(draft) = get_draft(&state.db_connection, draft_id)
+        .await
+        .map_err(e500)?
+    else {
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    };
+    let user = Users::find_by_id(*user_id.0)
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e500("User not found"))?;
+
+    let rendered = render_for_admin(
+        &state.issue_links(),
+        &draft,
+        &user.username,
+        user.email.as_deref().unwrap_or_default(),
+    );
+    let (html_content, text_content, error) = match rendered {
+        Ok((html_content, text_content)) => (html_content, text_content, None),
+        Err(e) => (String::new(), String::new(), Some(e)),
+    };
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("preview.html"),
+            &serde_json::json!({
+                "id": draft.id,
+                "title": draft.title,
+                "html_content": html_content,
+                "text_content": text_content,
+                "error": error,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+/// Render a draft as if the admin were a subscriber. The unsubscribe link
+/// points at no subscriber, so following it is harmless.
+fn render_for_admin(
+    links: &IssueLinks,
+    draft: &newsletter_issues::Model,
+    name: &str,
+    email: &str,
+) -> Result<(String, String), String> {
+    IssueTemplate::parse(&draft.html_content, &draft.text_content).and_then(|t| {
+        t.render(&TemplateVariables {
+            name,
+            email,
+            unsubscribe_url: &links.unsubscribe_url(Uuid::nil(), draft.id),
+            web_view_url: &links.web_view_url(draft.id),
+            locale: Locale::default().as_str(),
+        })
+    })
+}
+
+/// Send a draft to the logged-in admin only.
+#[tracing::instrument(
+    name = "Send a test email for a newsletter draft",
+    skip(state, flash, user_id)
+)]
+pub async fn send_test_email(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Path(draft_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let edit_url = format!("/admin/newsletters/drafts/{}", draft_id);
+    let Some(draft) = get_draft(&state.db_connection, draft_id)
+        .await
+        .map_err(e500)?
+    else {
+        flash.error("The issue is no longer a draft.");
+        return Ok(Redirect::to("/admin/newsletters/drafts").into_response());
+    };
+
+    let user_id = user_id.0;
+    let user = Users::find_by_id(*user_id)
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| e500("User not found"))?;
+    let email = user.email.map(SubscriberEmail::parse);
+    let email = match email {
+        Some(Ok(email)) => email,
+        Some(Err(e)) => {
+            flash.error(e);
+            return Ok(Redirect::to(&edit_url).into_response());
+        }
+        None => {
+            flash.error("Set your email address before sending a test email.");
+            return Ok(Redirect::to(&edit_url).into_response());
+        }
+    };
+
+    let rendered = render_for_admin(&state.issue_links(), &draft, &user.username, email.as_ref());
+    let (html_content, text_content) = match rendered {
+        Ok(bodies) => bodies,
+        Err(e) => {
+            flash.error(e);
+            return Ok(Redirect::to(&edit_url).into_response());
+        }
+    };
+
+    state
+        .email_client
+        .send_email(
+            &email,
+            &format!("[TEST] {}", draft.title),
+            &html_content,
+            &text_content,
+        )
+        .await
+        .map_err(e500)?;
+
+    flash.success(format!("A test email has been sent to {}.", email));
+    Ok(Redirect::to(&edit_url).into_response())
+}
@@ -0,0 +1,127 @@
+use axum::{
+    extract::{Path, State},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use entity::entities::{newsletter_issues, prelude::*};
+use handlebars::Handlebars;
+use migration::SimpleExpr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Value};
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::{issue_delivery::pending_emails, routes::AppState, utils::e500};
+
+pub async fn published_newsletters(
+    State(state): State<AppState>,
+    flash: Messages,
+) -> Result<Response, Response> {
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let issues = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.eq("published"))
+        .order_by_desc(newsletter_issues::Column::PublishedAt)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|issue| {
+            serde_json::json!({
+                "id": issue.id,
+                "slug": issue.slug,
+                "title": issue.title,
+                "published_at": issue.published_at.map(|t| t.to_rfc3339()),
+                "hidden": issue.hidden,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut undelivered = Vec::new();
+    for issue in NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.is_in(["sending", "failed"]))
+        .order_by_asc(newsletter_issues::Column::CreatedAt)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+    {
+        let pending_emails = pending_emails(&state.db_connection, issue.id)
+            .await
+            .map_err(e500)?;
+        undelivered.push(serde_json::json!({
+            "title": issue.title,
+            "state": issue.state,
+            "created_at": issue.created_at.to_rfc3339(),
+            "pending_emails": pending_emails,
+        }));
+    }
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("published.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "issues": issues,
+                "undelivered": undelivered,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+#[tracing::instrument(name = "Hide an issue from the archive", skip(state, flash))]
+pub async fn hide_newsletter(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    if set_hidden(&state.db_connection, issue_id, true)
+        .await
+        .map_err(e500)?
+    {
+        flash.success("The issue has been hidden from the archive.");
+    } else {
+        flash.error("The issue is not published.");
+    }
+    Ok(Redirect::to("/admin/newsletters/published").into_response())
+}
+
+#[tracing::instrument(name = "Show an issue in the archive", skip(state, flash))]
+pub async fn show_newsletter(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    if set_hidden(&state.db_connection, issue_id, false)
+        .await
+        .map_err(e500)?
+    {
+        flash.success("The issue is listed in the archive again.");
+    } else {
+        flash.error("The issue is not published.");
+    }
+    Ok(Redirect::to("/admin/newsletters/published").into_response())
+}
+
+/// Hide or show a published issue.
+/// Returns `false` if there is no such published issue.
+async fn set_hidden(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+    hidden: bool,
+) -> Result<bool, sea_orm::DbErr> {
+    let result = NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::Hidden,
+            SimpleExpr::Value(Value::Bool(Some(hidden))),
+        )
+        .filter(newsletter_issues::Column::Id.eq(issue_id))
+        .filter(newsletter_issues::Column::State.eq("published"))
+        .exec(db_connection)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use entity::entities::{prelude::*, tracking_events};
+use handlebars::Handlebars;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter, QueryOrder,
+    QuerySelect, sea_query::Expr,
+};
+use uuid::Uuid;
+
+use crate::{routes::AppState, utils::e500};
+
+#[derive(Debug, FromQueryResult, serde::Serialize)]
+struct EventCount {
+    kind: String,
+    total: i64,
+    subscribers: i64,
+}
+
+#[derive(Debug, FromQueryResult, serde::Serialize)]
+struct LinkCount {
+    url: Option<String>,
+    total: i64,
+    subscribers: i64,
+}
+
+/// Opens and clicks of a single issue, in total and per subscriber.
+pub async fn newsletter_report(
+    State(state): State<AppState>,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let Some(issue) = NewsletterIssues::find_by_id(issue_id)
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+    else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let counts = event_counts(&state.db_connection, issue_id)
+        .await
+        .map_err(e500)?;
+    let count_of = |kind: &str| counts.iter().find(|c| c.kind == kind);
+    let links = link_counts(&state.db_connection, issue_id)
+        .await
+        .map_err(e500)?;
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("report.html"),
+            &serde_json::json!({
+                "title": issue.title,
+                "tracking_enabled": issue.tracking_enabled,
+                "opens": count_of("open"),
+                "clicks": count_of("click"),
+                "links": links,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
+
+async fn event_counts(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+) -> Result<Vec<EventCount>, sea_orm::DbErr> {
+    TrackingEvents::find()
+        .select_only()
+        .column(tracking_events::Column::Kind)
+        .column_as(tracking_events::Column::Id.count(), "total")
+        .column_as(Expr::cust("COUNT(DISTINCT subscriber_id)"), "subscribers")
+        .filter(tracking_events::Column::IssueId.eq(issue_id))
+        .group_by(tracking_events::Column::Kind)
+        .into_model::<EventCount>()
+        .all(db_connection)
+        .await
+}
+
+async fn link_counts(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+) -> Result<Vec<LinkCount>, sea_orm::DbErr> {
+    TrackingEvents::find()
+        .select_only()
+        .column(tracking_events::Column::Url)
+        .column_as(tracking_events::Column::Id.count(), "total")
+        .column_as(Expr::cust("COUNT(DISTINCT subscriber_id)"), "subscribers")
+        .filter(tracking_events::Column::IssueId.eq(issue_id))
+        .filter(tracking_events::Column::Kind.eq("click"))
+        .group_by(tracking_events::Column::Url)
+        .order_by_desc(Expr::cust("total"))
+        .into_model::<LinkCount>()
+        .all(db_connection)
+        .await
+}
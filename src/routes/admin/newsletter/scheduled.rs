@@ -0,0 +1,130 @@
+use axum::{
+    Form,
+    extract::{Path, State},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use chrono::Utc;
+use entity::entities::{newsletter_issues, prelude::*};
+use handlebars::Handlebars;
+use migration::SimpleExpr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, Value};
+use std::fmt::Write;
+use uuid::Uuid;
+
+use crate::{domain::SendAt, routes::AppState, utils::e500};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct RescheduleFormData {
+    send_at: String,
+}
+
+pub async fn scheduled_newsletters(
+    State(state): State<AppState>,
+    flash: Messages,
+) -> Result<Response, Response> {
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let issues = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.eq("scheduled"))
+        .order_by_asc(newsletter_issues::Column::SendAt)
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|issue| {
+            serde_json::json!({
+                "id": issue.id,
+                "title": issue.title,
+                "send_at": issue.send_at.map(|t| t.to_rfc3339()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("scheduled.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "issues": issues,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+#[tracing::instrument(name = "Reschedule a newsletter issue", skip(state, flash))]
+pub async fn reschedule_newsletter(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(issue_id): Path<Uuid>,
+    Form(form): Form<RescheduleFormData>,
+) -> Result<Response, Response> {
+    let send_at = match SendAt::parse(form.send_at, Utc::now()) {
+        Ok(send_at) => send_at,
+        Err(e) => {
+            flash.error(e);
+            return Ok(Redirect::to("/admin/newsletters/scheduled").into_response());
+        }
+    };
+    let send_at_value = Value::ChronoDateTimeWithTimeZone(Some(Box::new(send_at.into())));
+    if update_scheduled_issue(
+        &state.db_connection,
+        issue_id,
+        newsletter_issues::Column::SendAt,
+        send_at_value,
+    )
+    .await
+    .map_err(e500)?
+    {
+        flash.success(format!("The issue has been rescheduled for {}.", send_at));
+    } else {
+        flash.error("The issue is no longer scheduled.");
+    }
+    Ok(Redirect::to("/admin/newsletters/scheduled").into_response())
+}
+
+#[tracing::instrument(name = "Cancel a scheduled newsletter issue", skip(state, flash))]
+pub async fn cancel_newsletter(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let cancelled = Value::String(Some(Box::new("cancelled".to_string())));
+    if update_scheduled_issue(
+        &state.db_connection,
+        issue_id,
+        newsletter_issues::Column::State,
+        cancelled,
+    )
+    .await
+    .map_err(e500)?
+    {
+        flash.success("The issue has been cancelled.");
+    } else {
+        flash.error("The issue is no longer scheduled.");
+    }
+    Ok(Redirect::to("/admin/newsletters/scheduled").into_response())
+}
+
+/// Set a column on an issue, provided it is still waiting to be sent.
+/// Returns `false` if the scheduler already picked it up.
+async fn update_scheduled_issue(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+    column: newsletter_issues::Column,
+    value: Value,
+) -> Result<bool, sea_orm::DbErr> {
+    let result = NewsletterIssues::update_many()
+        .col_expr(column, SimpleExpr::Value(value))
+        .filter(newsletter_issues::Column::Id.eq(issue_id))
+        .filter(newsletter_issues::Column::State.eq("scheduled"))
+        .exec(db_connection)
+        .await?;
+    Ok(result.rows_affected == 1)
+}
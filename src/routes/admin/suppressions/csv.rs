@@ -0,0 +1,102 @@
+//! Just enough CSV for the suppression list: one record per line, fields
+//! quoted when they need to be, which lets them span several lines.
+
+/// Append a record, quoting fields that contain a separator or a quote.
+pub(super) fn write_row(csv: &mut String, fields: &[&str]) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push('\n');
+}
+
+/// Split the input into records and their fields. Quoted fields may span
+/// several lines; blank lines are skipped.
+/// Line breaks are read as `\n`, whether they were sent as `\r\n` or not.
+pub(super) fn parse_rows(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => end_row(&mut rows, &mut row, &mut field),
+            c => field.push(c),
+        }
+    }
+    end_row(&mut rows, &mut row, &mut field);
+    rows
+}
+
+fn end_row(rows: &mut Vec<Vec<String>>, row: &mut Vec<String>, field: &mut String) {
+    row.push(std::mem::take(field));
+    let row = std::mem::take(row);
+    if row.len() > 1 || !row[0].trim().is_empty() {
+        rows.push(row);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_rows, write_row};
+
+    #[test]
+    fn plain_fields_are_split_on_commas() {
+        assert_eq!(
+            parse_rows("a@example.com,spam"),
+            vec![vec!["a@example.com", "spam"]]
+        );
+    }
+
+    #[test]
+    fn quoted_fields_keep_their_commas_and_quotes() {
+        assert_eq!(
+            parse_rows(r#"a@example.com,"Asked us to, ""politely""""#),
+            vec![vec!["a@example.com", r#"Asked us to, "politely""#]]
+        );
+    }
+
+    #[test]
+    fn written_rows_parse_back() {
+        let fields = ["example.com", "domain", r#"Legal, "case 12""#];
+        let mut csv = String::new();
+        write_row(&mut csv, &fields);
+        assert_eq!(parse_rows(&csv), vec![fields]);
+    }
+
+    #[test]
+    fn written_rows_with_line_breaks_parse_back() {
+        let first = ["a@example.com", "email", "Called twice.\nThen wrote."];
+        let second = ["example.com", "domain", "Legal"];
+        let mut csv = String::new();
+        write_row(&mut csv, &first);
+        write_row(&mut csv, &second);
+        assert_eq!(parse_rows(&csv), vec![first, second]);
+        // As pasted into a form, which sends line breaks as `\r\n`.
+        assert_eq!(parse_rows(&csv.replace('\n', "\r\n")), vec![first, second]);
+    }
+
+    #[test]
+    fn blank_lines_are_skipped() {
+        assert_eq!(
+            parse_rows("a@example.com,spam\n\n  \nexample.com,legal\n"),
+            vec![vec!["a@example.com", "spam"], vec!["example.com", "legal"]]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use axum::{
+    extract::State,
+    http::header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+    response::{Html, IntoResponse, Response},
+};
+use axum_messages::Messages;
+use entity::entities::{prelude::*, suppressions, users};
+use handlebars::Handlebars;
+use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder};
+use std::fmt::Write;
+
+use super::csv::write_row;
+use crate::{routes::AppState, utils::e500};
+
+pub async fn suppression_list_form(
+    State(state): State<AppState>,
+    flash: Messages,
+) -> Result<Response, Response> {
+    let mut msg_html = String::new();
+    for m in flash.into_iter() {
+        writeln!(msg_html, "<p><i>{}</i></p>", m.message).unwrap();
+    }
+
+    let entries = all_entries(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|(entry, user)| {
+            serde_json::json!({
+                "id": entry.id,
+                "entry": entry.entry,
+                "kind": entry.kind,
+                "reason": entry.reason,
+                "added_by": added_by(user.as_ref()),
+                "created_at": entry.created_at.to_rfc3339(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("get.html"),
+            &serde_json::json!({
+                "messages": msg_html,
+                "entries": entries,
+            }),
+        )
+        .map_err(e500)?;
+
+    Ok(Html::from(html).into_response())
+}
+
+/// The whole list as CSV, in a format `import_suppressions` accepts.
+pub async fn export_suppressions(State(state): State<AppState>) -> Result<Response, Response> {
+    let mut csv = String::new();
+    write_row(
+        &mut csv,
+        &["entry", "kind", "reason", "added_by", "created_at"],
+    );
+    for (entry, user) in all_entries(&state.db_connection).await.map_err(e500)? {
+        write_row(
+            &mut csv,
+            &[
+                &entry.entry,
+                &entry.kind,
+                &entry.reason,
+                added_by(user.as_ref()),
+                &entry.created_at.to_rfc3339(),
+            ],
+        );
+    }
+    Ok((
+        [
+            (CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (
+                CONTENT_DISPOSITION,
+                r#"attachment; filename="suppressions.csv""#,
+            ),
+        ],
+        csv,
+    )
+        .into_response())
+}
+
+async fn all_entries(
+    db_connection: &DatabaseConnection,
+) -> Result<Vec<(suppressions::Model, Option<users::Model>)>, sea_orm::DbErr> {
+    Suppressions::find()
+        .find_also_related(Users)
+        .order_by_desc(suppressions::Column::CreatedAt)
+        .all(db_connection)
+        .await
+}
+
+/// Entries without an author come from the email provider.
+fn added_by(user: Option<&users::Model>) -> &str {
+    user.map(|u| u.username.as_str())
+        .unwrap_or("email provider")
+}
@@ -0,0 +1,6 @@
+mod csv;
+mod get;
+mod post;
+
+pub use get::{export_suppressions, suppression_list_form};
+pub use post::{add_suppression, import_suppressions, remove_suppression};
@@ -0,0 +1,150 @@
+use axum::{
+    Extension, Form,
+    extract::{Path, State},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_messages::Messages;
+use htmlescape::encode_minimal;
+use uuid::Uuid;
+
+use super::csv::parse_rows;
+use crate::{
+    authentication::UserId, domain::SuppressionEntry, routes::AppState, telemetry::Redacted,
+    utils::e500,
+};
+
+#[derive(Debug, serde::Deserialize)]
+pub struct FormData {
+    entry: String,
+    reason: String,
+}
+
+#[tracing::instrument(
+    name = "Add a suppression list entry by hand",
+    skip(state, flash, user_id, form),
+    fields(entry = %Redacted::email(&form.entry), reason = %form.reason)
+)]
+pub async fn add_suppression(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Form(form): Form<FormData>,
+) -> Result<Response, Response> {
+    let entry = match SuppressionEntry::parse(form.entry) {
+        Ok(entry) => entry,
+        Err(e) => {
+            flash.error(encode_minimal(&e));
+            return Ok(Redirect::to("/admin/suppressions").into_response());
+        }
+    };
+    let reason = form.reason.trim();
+    if reason.is_empty() {
+        flash.error("Every entry needs a reason.");
+        return Ok(Redirect::to("/admin/suppressions").into_response());
+    }
+
+    let added = state
+        .suppression_list()
+        .add(&entry, reason, Some(**user_id))
+        .await
+        .map_err(e500)?;
+    if added {
+        flash.success(format!("{} has been suppressed.", entry));
+    } else {
+        flash.info(format!("{} was already on the suppression list.", entry));
+    }
+    Ok(Redirect::to("/admin/suppressions").into_response())
+}
+
+#[tracing::instrument(name = "Remove a suppression list entry", skip(state, flash))]
+pub async fn remove_suppression(
+    State(state): State<AppState>,
+    flash: Messages,
+    Path(suppression_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    state
+        .suppression_list()
+        .remove(suppression_id)
+        .await
+        .map_err(e500)?;
+    flash.success("The entry has been removed from the suppression list.");
+    Ok(Redirect::to("/admin/suppressions").into_response())
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub struct ImportFormData {
+    entries: String,
+    #[serde(default)]
+    reason: String,
+}
+
+/// Add every line of a CSV paste: an address or domain, optionally
+/// followed by a reason. Exports carry a header naming the reason column.
+#[tracing::instrument(
+    name = "Import suppression list entries",
+    skip(state, flash, user_id, form)
+)]
+pub async fn import_suppressions(
+    State(state): State<AppState>,
+    flash: Messages,
+    user_id: Extension<UserId>,
+    Form(form): Form<ImportFormData>,
+) -> Result<Response, Response> {
+    let suppression_list = state.suppression_list();
+    let default_reason = form.reason.trim();
+    let mut rows = parse_rows(&form.entries);
+    let mut reason_column = 1;
+    if rows
+        .first()
+        .is_some_and(|header| header[0].trim() == "entry")
+    {
+        let header = rows.remove(0);
+        reason_column = header
+            .iter()
+            .position(|f| f.trim() == "reason")
+            .unwrap_or(reason_column);
+    }
+
+    let (mut added, mut existing) = (0, 0);
+    let mut skipped = Vec::new();
+    for row in rows {
+        let entry = match SuppressionEntry::parse(row[0].clone()) {
+            Ok(entry) => entry,
+            Err(e) => {
+                skipped.push(e);
+                continue;
+            }
+        };
+        let reason = row
+            .get(reason_column)
+            .map(|r| r.trim())
+            .filter(|r| !r.is_empty())
+            .unwrap_or(default_reason);
+        if reason.is_empty() {
+            skipped.push(format!("No reason was given for {}.", entry));
+            continue;
+        }
+        if suppression_list
+            .add(&entry, reason, Some(**user_id))
+            .await
+            .map_err(e500)?
+        {
+            added += 1;
+        } else {
+            existing += 1;
+        }
+    }
+
+    let flash = flash.success(format!(
+        "{} entries imported, {} were already on the list.",
+        added, existing
+    ));
+    if !skipped.is_empty() {
+        flash.error(format!(
+            "{} lines were skipped. {}",
+            skipped.len(),
+            encode_minimal(&skipped.join(" "))
+        ));
+    }
+    Ok(Redirect::to("/admin/suppressions").into_response())
+}
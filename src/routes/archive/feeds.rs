@@ -0,0 +1,89 @@
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, FixedOffset};
+use entity::entities::newsletter_issues;
+use handlebars::Handlebars;
+use sea_orm::{DatabaseConnection, QuerySelect};
+
+use super::visible_issues;
+use crate::{
+    i18n::Locale,
+    issue_links::IssueLinks,
+    routes::{AppState, render_public_html},
+    utils::e500,
+};
+
+/// Feed readers only look at recent entries; the archive has the rest.
+const FEED_LENGTH: u64 = 20;
+
+pub async fn rss_feed(State(state): State<AppState>) -> Result<Response, Response> {
+    let issues = feed_issues(&state.db_connection).await.map_err(e500)?;
+    let entries = feed_entries(&state.issue_links(), &issues, |t| t.to_rfc2822()).map_err(e500)?;
+
+    let reg = Handlebars::new();
+    let xml = reg
+        .render_template(
+            include_str!("rss.xml"),
+            &serde_json::json!({
+                "base_url": state.base_url,
+                "issues": entries,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(([(CONTENT_TYPE, "application/rss+xml; charset=utf-8")], xml).into_response())
+}
+
+pub async fn atom_feed(State(state): State<AppState>) -> Result<Response, Response> {
+    let issues = feed_issues(&state.db_connection).await.map_err(e500)?;
+    let entries = feed_entries(&state.issue_links(), &issues, |t| t.to_rfc3339()).map_err(e500)?;
+    // Atom wants a feed-level timestamp even when there are no entries.
+    let updated = issues
+        .first()
+        .and_then(|issue| issue.published_at)
+        .map(|t| t.to_rfc3339())
+        .unwrap_or_else(|| DateTime::UNIX_EPOCH.to_rfc3339());
+
+    let reg = Handlebars::new();
+    let xml = reg
+        .render_template(
+            include_str!("atom.xml"),
+            &serde_json::json!({
+                "base_url": state.base_url,
+                "updated": updated,
+                "issues": entries,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(([(CONTENT_TYPE, "application/atom+xml; charset=utf-8")], xml).into_response())
+}
+
+async fn feed_issues(
+    db_connection: &DatabaseConnection,
+) -> Result<Vec<newsletter_issues::Model>, sea_orm::DbErr> {
+    visible_issues().limit(FEED_LENGTH).all(db_connection).await
+}
+
+/// The fields both feed formats share. Only the date format differs.
+fn feed_entries(
+    links: &IssueLinks,
+    issues: &[newsletter_issues::Model],
+    format_date: impl Fn(&DateTime<FixedOffset>) -> String,
+) -> Result<Vec<serde_json::Value>, String> {
+    issues
+        .iter()
+        .map(|issue| {
+            // Feed readers do not say which language they are read in.
+            let content = render_public_html(links, issue, Locale::default())?;
+            Ok(serde_json::json!({
+                "id": issue.id,
+                "slug": issue.slug,
+                "title": issue.title,
+                "published_at": issue.published_at.as_ref().map(&format_date),
+                "content": content,
+            }))
+        })
+        .collect()
+}
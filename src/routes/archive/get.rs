@@ -0,0 +1,70 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use entity::entities::newsletter_issues;
+use handlebars::Handlebars;
+use sea_orm::{ColumnTrait, QueryFilter};
+
+use super::visible_issues;
+use crate::{
+    i18n::RequestLocale,
+    routes::{AppState, render_public_html},
+    utils::e500,
+};
+
+pub async fn archive(State(state): State<AppState>) -> Result<Response, Response> {
+    let issues = visible_issues()
+        .all(&state.db_connection)
+        .await
+        .map_err(e500)?
+        .into_iter()
+        .map(|issue| {
+            serde_json::json!({
+                "slug": issue.slug,
+                "title": issue.title,
+                "published_at": issue.published_at.map(|t| t.date_naive().to_string()),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("archive.html"),
+            &serde_json::json!({"issues": issues}),
+        )
+        .map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
+
+#[tracing::instrument(name = "Show an archived issue", skip(state))]
+pub async fn archive_issue(
+    State(state): State<AppState>,
+    RequestLocale(locale): RequestLocale,
+    Path(slug): Path<String>,
+) -> Result<Response, Response> {
+    let Some(issue) = visible_issues()
+        .filter(newsletter_issues::Column::Slug.eq(slug))
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+    else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let content = render_public_html(&state.issue_links(), &issue, locale).map_err(e500)?;
+    let reg = Handlebars::new();
+    let html = reg
+        .render_template(
+            include_str!("issue.html"),
+            &serde_json::json!({
+                "title": issue.title,
+                "published_at": issue.published_at.map(|t| t.date_naive().to_string()),
+                "content": content,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
@@ -0,0 +1,16 @@
+mod feeds;
+mod get;
+
+pub use feeds::{atom_feed, rss_feed};
+pub use get::{archive, archive_issue};
+
+use entity::entities::{newsletter_issues, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Select};
+
+/// Published issues an admin has not hidden, newest first.
+fn visible_issues() -> Select<NewsletterIssues> {
+    NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.eq("published"))
+        .filter(newsletter_issues::Column::Hidden.eq(false))
+        .order_by_desc(newsletter_issues::Column::PublishedAt)
+}
@@ -0,0 +1,39 @@
+use axum::{body::Bytes, http::StatusCode};
+
+/// Browsers report Content-Security-Policy violations here, either in the
+/// `report-uri` format, an object under `csp-report`, or in the Reporting
+/// API one, a list of objects with the details under `body`.
+#[tracing::instrument(name = "Receive a CSP violation report", skip(body))]
+pub async fn csp_report(body: Bytes) -> StatusCode {
+    let Ok(payload) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return StatusCode::BAD_REQUEST;
+    };
+    let reports = match payload {
+        serde_json::Value::Array(reports) => reports
+            .into_iter()
+            .filter_map(|mut r| r.get_mut("body").map(serde_json::Value::take))
+            .collect(),
+        mut report => report
+            .get_mut("csp-report")
+            .map(serde_json::Value::take)
+            .into_iter()
+            .collect::<Vec<_>>(),
+    };
+    for report in reports {
+        let field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| report.get(*name).and_then(|v| v.as_str()))
+                .unwrap_or_default()
+                .to_string()
+        };
+        tracing::warn!(
+            document_uri = %field(&["document-uri", "documentURL"]),
+            violated_directive = %field(&["violated-directive", "effectiveDirective"]),
+            blocked_uri = %field(&["blocked-uri", "blockedURL"]),
+            disposition = %field(&["disposition"]),
+            "Content Security Policy violation"
+        );
+    }
+    StatusCode::NO_CONTENT
+}
@@ -1,13 +1,132 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
 use axum::{
-    extract::Path,
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
     response::{IntoResponse, Response},
 };
+use serde::Serialize;
+use tower_sessions_redis_store::fred::prelude::ClientLike;
+
+use super::AppState;
 
 pub async fn health_check() -> impl IntoResponse {
     let response = Response::new("hello world");
     response.status()
 }
 
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum Status {
+    Up,
+    Down,
+}
+
+#[derive(Serialize)]
+struct ComponentHealth {
+    status: Status,
+    latency_ms: u128,
+    /// `error` or `timeout`. The probe answers anyone, so what went wrong
+    /// is only logged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'static str>,
+    #[serde(skip)]
+    detail: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Readiness {
+    status: Status,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    shutting_down: bool,
+    components: std::collections::BTreeMap<&'static str, ComponentHealth>,
+}
+
+/// The process is up and serving requests. Dependencies are not checked:
+/// restarting the instance would not fix them.
+pub async fn liveness() -> impl IntoResponse {
+    Json(serde_json::json!({ "status": Status::Up }))
+}
+
+/// Whether the instance can serve traffic, with the status of each
+/// dependency. Answers 503 as soon as one of them is down, or once the
+/// instance has started shutting down.
+#[tracing::instrument(name = "Readiness probe", skip(state))]
+pub async fn readiness(State(state): State<AppState>) -> Response {
+    let timeout = state.health.timeout();
+    let (postgres, redis, email_provider) = tokio::join!(
+        check(timeout, state.db_connection.ping()),
+        check(timeout, state.redis_pool.ping::<String>(None)),
+        async {
+            if state.health.check_email_provider {
+                Some(check(timeout, state.email_client.ping()).await)
+            } else {
+                None
+            }
+        },
+    );
+    let mut components =
+        std::collections::BTreeMap::from([("postgres", postgres), ("redis", redis)]);
+    if let Some(email_provider) = email_provider {
+        components.insert("email_provider", email_provider);
+    }
+    let shutting_down = state.shutdown.is_cancelled();
+    let status = if shutting_down {
+        Status::Down
+    } else if components.values().all(|c| c.status == Status::Up) {
+        Status::Up
+    } else {
+        for (name, component) in &components {
+            if let Some(detail) = &component.detail {
+                tracing::warn!(
+                    component = name,
+                    error = detail,
+                    "A dependency is not ready"
+                );
+            }
+        }
+        Status::Down
+    };
+    let code = match status {
+        Status::Up => StatusCode::OK,
+        Status::Down => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    let readiness = Readiness {
+        status,
+        shutting_down,
+        components,
+    };
+    (code, Json(readiness)).into_response()
+}
+
+async fn check<T, E: std::fmt::Display>(
+    timeout: Duration,
+    probe: impl Future<Output = Result<T, E>>,
+) -> ComponentHealth {
+    let start = Instant::now();
+    let outcome = tokio::time::timeout(timeout, probe).await;
+    let (error, detail) = match outcome {
+        Ok(Ok(_)) => (None, None),
+        Ok(Err(e)) => (Some("error"), Some(e.to_string())),
+        Err(_) => (
+            Some("timeout"),
+            Some(format!("No answer within {}ms", timeout.as_millis())),
+        ),
+    };
+    ComponentHealth {
+        status: if error.is_none() {
+            Status::Up
+        } else {
+            Status::Down
+        },
+        latency_ms: start.elapsed().as_millis(),
+        error,
+        detail,
+    }
+}
+
 pub async fn index() -> impl IntoResponse {
     "Rust Rocks!"
 }
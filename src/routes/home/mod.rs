@@ -1,5 +1,29 @@
-use axum::response::{Html, IntoResponse, Response};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse, Response},
+};
 
-pub async fn home() -> Response {
-    Html(include_str!("home.html")).into_response()
+use crate::{
+    bot_protection::HONEYPOT_FIELD, i18n::RequestLocale, routes::AppState,
+    security_headers::CspNonce, utils::e500,
+};
+
+pub async fn home(
+    State(state): State<AppState>,
+    RequestLocale(locale): RequestLocale,
+    nonce: CspNonce,
+) -> Result<Response, Response> {
+    let html = locale
+        .templates()
+        .render_template(
+            include_str!("home.html"),
+            &serde_json::json!({
+                "locale": locale,
+                "form_token": state.bot_protection.form_token(),
+                "honeypot_field": HONEYPOT_FIELD,
+                "csp_nonce": nonce.0,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(Html(html).into_response())
 }
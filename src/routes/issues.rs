@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use entity::entities::{newsletter_issues, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use uuid::Uuid;
+
+use super::AppState;
+use crate::{
+    domain::{IssueTemplate, TemplateVariables},
+    i18n::{Locale, RequestLocale},
+    issue_links::IssueLinks,
+    utils::e500,
+};
+
+/// The browser version of a published issue, linked from every email.
+/// It is shared by all recipients, so nothing personal is filled in.
+#[tracing::instrument(name = "Show the web view of an issue", skip(state))]
+pub async fn issue_web_view(
+    State(state): State<AppState>,
+    RequestLocale(locale): RequestLocale,
+    Path(issue_id): Path<Uuid>,
+) -> Result<Response, Response> {
+    let Some(issue) = NewsletterIssues::find_by_id(issue_id)
+        .filter(newsletter_issues::Column::State.eq("published"))
+        .one(&state.db_connection)
+        .await
+        .map_err(e500)?
+    else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+
+    let html = render_public_html(&state.issue_links(), &issue, locale).map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
+
+/// The HTML body of an issue as anyone but its recipients gets to see it,
+/// in the language of the reader where the issue offers a choice.
+pub fn render_public_html(
+    links: &IssueLinks,
+    issue: &newsletter_issues::Model,
+    locale: Locale,
+) -> Result<String, String> {
+    let template = IssueTemplate::parse(&issue.html_content, &issue.text_content)?;
+    let (html, _) = template.render(&TemplateVariables {
+        name: "",
+        email: "",
+        unsubscribe_url: "",
+        web_view_url: &links.web_view_url(issue.id),
+        locale: locale.as_str(),
+    })?;
+    Ok(html)
+}
@@ -1,21 +1,25 @@
 use axum::http::StatusCode;
 use axum::response::Html;
 use axum_messages::Messages;
-use handlebars::Handlebars;
 use std::fmt::Write;
 
+use crate::i18n::RequestLocale;
+
 #[tracing::instrument(skip(flash))]
-pub async fn login_form(flash: Messages) -> (StatusCode, Html<String>) {
+pub async fn login_form(
+    RequestLocale(locale): RequestLocale,
+    flash: Messages,
+) -> (StatusCode, Html<String>) {
     let mut error_html = String::new();
     for message in flash.into_iter() {
         writeln!(error_html, "<p><i>{}</i></p>", message.message).unwrap();
     }
     let html_template = include_str!("login.html");
-    let reg = Handlebars::new();
+    let reg = locale.templates();
     let login_form = reg
         .render_template(
             html_template,
-            &serde_json::json!({"error_html": error_html}),
+            &serde_json::json!({"error_html": error_html, "locale": locale}),
         )
         .expect("Failed to render login form.");
     tracing::debug!("Login form rendered:{}", login_form);
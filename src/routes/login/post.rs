@@ -2,6 +2,7 @@ use crate::authentication::{AuthError, Credentials, validate_credentials};
 use crate::routes::AppState;
 use crate::routes::error_chain_fmt;
 use crate::session_state::TypedSession;
+use crate::telemetry::Redacted;
 use axum::Form;
 use axum::extract::State;
 use axum::http::StatusCode;
@@ -63,7 +64,10 @@ pub async fn login(
         username: form.username,
         password: form.password,
     };
-    tracing::Span::current().record("username", &tracing::field::display(&credentials.username));
+    tracing::Span::current().record(
+        "username",
+        tracing::field::display(Redacted::name(&credentials.username)),
+    );
 
     match validate_credentials(credentials, &state.db_connection).await {
         Ok(user_id) => {
@@ -72,6 +76,8 @@ pub async fn login(
                 login_redirect(flash.clone(), LoginError::UnexpectedError(e.into()))
             };
             session.cycle_id().await.map_err(&redirect_err)?;
+            // Tokens handed out before logging in are not good afterwards.
+            session.rotate_csrf_token().await.map_err(&redirect_err)?;
             session
                 .insert_iser_id(user_id)
                 .await
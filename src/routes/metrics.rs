@@ -0,0 +1,34 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use secrecy::ExposeSecret;
+
+use super::AppState;
+use crate::utils::constant_time_eq;
+
+/// Metrics in the Prometheus text format, for scrapers presenting the
+/// configured bearer token.
+pub async fn export_metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token, state.metrics_token.expose_secret()));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, r#"Bearer realm="metrics""#)],
+        )
+            .into_response();
+    }
+    state
+        .metrics
+        .observe_pools(&state.db_connection, &state.redis_pool);
+    (
+        [(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)],
+        state.metrics.render(),
+    )
+        .into_response()
+}
@@ -1,13 +1,27 @@
 mod admin;
+mod archive;
+mod csp_reports;
 mod health_check;
 mod home;
+mod issues;
 mod login;
+mod metrics;
 mod subscriptions;
 mod subscriptions_confirm;
+mod tracking;
+mod unsubscribe;
+mod webhooks;
 
 pub use admin::*;
+pub use archive::*;
+pub use csp_reports::*;
 pub use health_check::*;
 pub use home::*;
+pub use issues::*;
 pub use login::*;
+pub use metrics::*;
 pub use subscriptions::*;
 pub use subscriptions_confirm::*;
+pub use tracking::*;
+pub use unsubscribe::*;
+pub use webhooks::*;
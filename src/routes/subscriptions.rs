@@ -1,33 +1,50 @@
 use anyhow::Context;
 use axum::{
     Form,
-    extract::State,
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
 };
 use chrono::Utc;
 use entity::entities::subscription_tokens;
 use entity::entities::subscriptions;
+use entity::entities::{list_memberships, lists, prelude::*};
+use fluent_bundle::FluentArgs;
+use migration::SimpleExpr;
 use rand::distributions::Alphanumeric;
 use rand::{Rng, thread_rng};
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, DatabaseTransaction, Set, TransactionTrait,
-    prelude::DateTimeWithTimeZone,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    EntityTrait, QueryFilter, Set, TransactionTrait, Value, prelude::DateTimeWithTimeZone,
 };
+use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tower_sessions_redis_store::fred::prelude::Pool;
 use unicode_segmentation::UnicodeSegmentation;
 use uuid::Uuid;
 
 use crate::{
-    domain::{NewSubscriber, SubscriberEmail, SubscriberName},
-    email_client::EmailClient,
+    bot_protection::{BlockReason, BotCheckError, BotProtection, Submission},
+    configuration::{HealthSettings, TrackingSettings, WebhookSettings},
+    domain::{EmailPolicy, ListSlug, NewSubscriber, SubscriberEmail, SubscriberName},
+    email_client::{EmailClient, SendEmailError},
+    i18n::{Locale, RequestLocale},
+    issue_links::IssueLinks,
+    metrics::Metrics,
     startup::HmacSecret,
+    suppression::SuppressionList,
+    telemetry::Redacted,
 };
 
 #[derive(thiserror::Error)]
 pub enum SubscribeError {
     #[error("{0}")]
     ValidationError(String),
+    #[error("The request was blocked: {0}.")]
+    Blocked(BlockReason),
     // Transparent delegates both `Display`'s and `source`'s implementation
     // to the type wrapped by `UnexpectedError`.
     #[error(transparent)]
@@ -43,12 +60,30 @@ impl std::fmt::Debug for SubscribeError {
 impl IntoResponse for SubscribeError {
     fn into_response(self) -> Response {
         match self {
-            SubscribeError::ValidationError(_) => StatusCode::BAD_REQUEST.into_response(),
+            // The reason is meant to be shown next to the subscribe form.
+            SubscribeError::ValidationError(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+            // Bots are left to believe they got through.
+            SubscribeError::Blocked(BlockReason::Honeypot) => StatusCode::OK.into_response(),
+            SubscribeError::Blocked(
+                reason @ (BlockReason::IpRateLimited | BlockReason::EmailRateLimited),
+            ) => (StatusCode::TOO_MANY_REQUESTS, reason.message()).into_response(),
+            SubscribeError::Blocked(reason) => {
+                (StatusCode::BAD_REQUEST, reason.message()).into_response()
+            }
             SubscribeError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
         }
     }
 }
 
+impl From<BotCheckError> for SubscribeError {
+    fn from(e: BotCheckError) -> Self {
+        match e {
+            BotCheckError::Blocked(reason) => Self::Blocked(reason),
+            BotCheckError::UnexpectedError(e) => Self::UnexpectedError(e),
+        }
+    }
+}
+
 impl From<String> for SubscribeError {
     fn from(e: String) -> Self {
         Self::ValidationError(e)
@@ -96,6 +131,21 @@ pub fn error_chain_fmt(
 pub struct FormData {
     pub name: String,
     pub email: String,
+    /// Slug of the list to join, the default list when omitted.
+    #[serde(default)]
+    pub list: Option<String>,
+    /// Language of the emails and pages the subscriber gets, taken from
+    /// `Accept-Language` when omitted.
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Hidden from people, left empty unless a bot fills it in.
+    #[serde(default, rename = "website")]
+    pub honeypot: String,
+    /// Signed when the form was served, see [`BotProtection`].
+    #[serde(default)]
+    pub form_token: Option<String>,
+    #[serde(default)]
+    pub challenge_response: Option<String>,
 }
 
 #[derive(Clone)]
@@ -104,6 +154,29 @@ pub struct AppState {
     pub email_client: EmailClient,
     pub base_url: String,
     pub secret: HmacSecret,
+    pub tracking: TrackingSettings,
+    pub webhooks: WebhookSettings,
+    pub email_policy: Arc<EmailPolicy>,
+    pub bot_protection: Arc<BotProtection>,
+    pub metrics: Metrics,
+    pub metrics_token: SecretString,
+    pub redis_pool: Pool,
+    pub health: HealthSettings,
+    /// Cancelled once the instance starts shutting down.
+    pub shutdown: CancellationToken,
+}
+
+impl AppState {
+    pub fn issue_links(&self) -> IssueLinks {
+        IssueLinks::new(
+            self.base_url.clone(),
+            self.secret.clone(),
+            self.tracking.enabled,
+        )
+    }
+    pub fn suppression_list(&self) -> SuppressionList {
+        SuppressionList::new(self.db_connection.clone())
+    }
 }
 
 impl TryFrom<FormData> for NewSubscriber {
@@ -124,30 +197,114 @@ pub fn parse_subscriber(form: FormData) -> Result<NewSubscriber, String> {
 
 #[tracing::instrument(
     name = "Adding a new subscriber",
-    skip(state, form),
+    skip(state, peer, headers, form),
     fields(
-        request_id = %Uuid::new_v4(),
-        subscriber_name = %form.name,
-        subscriber_email = %form.email
+        subscriber_name = %Redacted::name(&form.name),
+        subscriber_email = %Redacted::email(&form.email),
+        list = ?form.list,
+        locale = tracing::field::Empty
     )
 )]
 pub async fn subscribe(
     State(state): State<AppState>,
-    Form(form): Form<FormData>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    RequestLocale(requested_locale): RequestLocale,
+    Form(mut form): Form<FormData>,
 ) -> Result<Response, SubscribeError> {
-    let new_subscriber = form.try_into().map_err(SubscribeError::ValidationError)?;
+    let list_slug = match form.list.take() {
+        Some(slug) => ListSlug::parse(slug)?,
+        None => ListSlug::default(),
+    };
+    let locale = form
+        .locale
+        .take()
+        .and_then(|tag| Locale::parse(&tag))
+        .unwrap_or(requested_locale);
+    tracing::Span::current().record("locale", locale.as_str());
+    let honeypot = std::mem::take(&mut form.honeypot);
+    let form_token = form.form_token.take();
+    let challenge_response = form.challenge_response.take();
+    let new_subscriber: NewSubscriber = form.try_into().map_err(SubscribeError::ValidationError)?;
+    let client_ip = state.bot_protection.client_ip(&headers, peer);
+    let submission = Submission {
+        client_ip,
+        email: new_subscriber.email.as_ref(),
+        honeypot: &honeypot,
+        form_token: form_token.as_deref(),
+        challenge_response: challenge_response.as_deref(),
+    };
+    if let Err(e) = state.bot_protection.check(&submission).await {
+        if let BotCheckError::Blocked(reason) = &e {
+            tracing::warn!(
+                %reason,
+                client_ip = %Redacted::ip(client_ip),
+                "Blocked a subscription request"
+            );
+        }
+        return Err(e.into());
+    }
+    state
+        .email_policy
+        .check(&new_subscriber.email)
+        .map_err(|e| SubscribeError::ValidationError(e.to_string()))?;
+    if state
+        .suppression_list()
+        .is_suppressed(new_subscriber.email.as_ref())
+        .await
+        .context("Failed to check the suppression list.")?
+    {
+        // Answer as if the request went through, so that the suppression
+        // list cannot be probed from the outside.
+        tracing::info!("Ignoring a subscription request from a suppressed address");
+        return Ok(StatusCode::OK.into_response());
+    }
     let mut transaction = state
         .db_connection
         .begin()
         .await
         .context("Failed to acquire a Postgres connection from the pool")?;
-    let subscriber_id = insert_subscriber(&mut transaction, &new_subscriber)
+    let list = get_list_by_slug(&transaction, &list_slug)
         .await
-        .context("Failed to insert new subscriber in the database.")?;
-    let subscription_token = generate_subscription_token();
-    store_token(&mut transaction, &subscription_token, subscriber_id)
+        .context("Failed to look up the requested list.")?
+        .ok_or_else(|| {
+            SubscribeError::ValidationError(format!("{} is not a known list.", list_slug))
+        })?;
+    let subscriber_id = match get_subscriber_id_by_email(&transaction, &new_subscriber.email)
+        .await
+        .context("Failed to look up an existing subscriber.")?
+    {
+        Some(subscriber_id) => {
+            // The latest choice wins.
+            update_subscriber_locale(&transaction, subscriber_id, locale)
+                .await
+                .context("Failed to update the locale of an existing subscriber.")?;
+            subscriber_id
+        }
+        None => insert_subscriber(&mut transaction, &new_subscriber, locale)
+            .await
+            .context("Failed to insert new subscriber in the database.")?,
+    };
+    let is_confirmed = upsert_list_membership(&transaction, list.id, subscriber_id)
         .await
-        .context("Failed to store the confirmation token a new subscriber.")?;
+        .context("Failed to store the list membership of a new subscriber.")?;
+    if is_confirmed {
+        // Nothing to confirm: do not send another email to an existing member.
+        transaction
+            .commit()
+            .await
+            .context("Failed to commit SQL transaction to store a new subscriber.")?;
+        return Ok(StatusCode::OK.into_response());
+    }
+    let subscription_token = generate_subscription_token();
+    store_token(
+        &mut transaction,
+        &subscription_token,
+        subscriber_id,
+        list.id,
+    )
+    .await
+    .context("Failed to store the confirmation token a new subscriber.")?;
 
     transaction
         .commit()
@@ -159,15 +316,99 @@ pub async fn subscribe(
     send_confirmation_email(
         state.email_client,
         new_subscriber,
+        locale,
+        &list,
         state.base_url,
         subscription_token.as_str(),
     )
     .await
     .context("Failed to send a confirmation email")?;
+    state.metrics.subscriptions.inc();
 
     Ok(StatusCode::OK.into_response())
 }
 
+#[tracing::instrument(name = "Get list by slug", skip(db_connection))]
+pub async fn get_list_by_slug(
+    db_connection: &impl ConnectionTrait,
+    slug: &ListSlug,
+) -> Result<Option<lists::Model>, sea_orm::DbErr> {
+    Lists::find()
+        .filter(lists::Column::Slug.eq(slug.as_ref()))
+        .one(db_connection)
+        .await
+}
+
+#[tracing::instrument(name = "Get subscriber ID by email", skip(transaction, email))]
+pub async fn get_subscriber_id_by_email(
+    transaction: &DatabaseTransaction,
+    email: &SubscriberEmail,
+) -> Result<Option<Uuid>, sea_orm::DbErr> {
+    let subscriber = Subscriptions::find()
+        .filter(subscriptions::Column::Email.eq(email.as_ref()))
+        .one(transaction)
+        .await?;
+    Ok(subscriber.map(|s| s.id))
+}
+
+#[tracing::instrument(name = "Update the locale of a subscriber", skip(transaction))]
+pub async fn update_subscriber_locale(
+    transaction: &DatabaseTransaction,
+    subscriber_id: Uuid,
+    locale: Locale,
+) -> Result<(), sea_orm::DbErr> {
+    Subscriptions::update_many()
+        .col_expr(
+            subscriptions::Column::Locale,
+            SimpleExpr::Value(Value::String(Some(Box::new(locale.as_str().to_string())))),
+        )
+        .filter(subscriptions::Column::Id.eq(subscriber_id))
+        .exec(transaction)
+        .await?;
+    Ok(())
+}
+
+/// The language a subscriber chose, the default one if they are unknown.
+#[tracing::instrument(name = "Get the locale of a subscriber", skip(db_connection))]
+pub async fn get_subscriber_locale(
+    db_connection: &impl ConnectionTrait,
+    subscriber_id: Uuid,
+) -> Result<Locale, sea_orm::DbErr> {
+    let subscriber = Subscriptions::find_by_id(subscriber_id)
+        .one(db_connection)
+        .await?;
+    Ok(subscriber
+        .and_then(|s| Locale::parse(&s.locale))
+        .unwrap_or_default())
+}
+
+/// Add the subscriber to the list, pending confirmation, unless they are
+/// already a member. Returns `true` if the membership was already confirmed.
+#[tracing::instrument(name = "Storing list membership in the database", skip(transaction))]
+pub async fn upsert_list_membership(
+    transaction: &DatabaseTransaction,
+    list_id: Uuid,
+    subscriber_id: Uuid,
+) -> Result<bool, sea_orm::DbErr> {
+    let membership = ListMemberships::find_by_id((list_id, subscriber_id))
+        .one(transaction)
+        .await?;
+    match membership {
+        Some(membership) => Ok(membership.status == "confirmed"),
+        None => {
+            list_memberships::ActiveModel {
+                list_id: Set(list_id),
+                subscriber_id: Set(subscriber_id),
+                status: Set("pending_confirmation".to_string()),
+                created_at: Set(DateTimeWithTimeZone::from(Utc::now())),
+            }
+            .insert(transaction)
+            .await?;
+            Ok(false)
+        }
+    }
+}
+
 #[tracing::instrument(
     name = "Saving new subscriber details in the database",
     skip(transaction, new_subscriber)
@@ -175,6 +416,7 @@ pub async fn subscribe(
 pub async fn insert_subscriber(
     transaction: &DatabaseTransaction,
     new_subscriber: &NewSubscriber,
+    locale: Locale,
 ) -> Result<Uuid, sea_orm::DbErr> {
     let subscriber_id = Uuid::new_v4();
     let subscription = subscriptions::ActiveModel {
@@ -183,6 +425,7 @@ pub async fn insert_subscriber(
         email: Set(new_subscriber.email.as_ref().to_string()),
         subscribed_at: Set(DateTimeWithTimeZone::from(Utc::now())),
         status: Set("pending_confirmation".to_string()),
+        locale: Set(locale.as_str().to_string()),
     };
 
     subscription.insert(transaction).await.map_err(|e| {
@@ -195,44 +438,50 @@ pub async fn insert_subscriber(
 
 #[tracing::instrument(
     name = "Send a confirmation email to a new subscriber",
-    skip(email_client, new_subscriber, base_url, subscription_token)
+    skip(email_client, new_subscriber, list, base_url, subscription_token)
 )]
 pub async fn send_confirmation_email(
     email_client: EmailClient,
     new_subscriber: NewSubscriber,
+    locale: Locale,
+    list: &lists::Model,
     base_url: String,
     subscription_token: &str,
-) -> Result<(), reqwest::Error> {
+) -> Result<(), SendEmailError> {
     let confirmation_link = format!(
         "{}/subscriptions/confirm?subscription_token={}",
         base_url, subscription_token
     );
-    let palin_body = format!(
-        "Welcome to our newsletter!\nVisit {} to confirm your subscription.",
-        confirmation_link
-    );
-    let html_body = format!(
-        "Welcome to our newsletter!<br />\
-        Click <a href=\"{}\">here</a> to confirm your subscription.",
-        confirmation_link
-    );
+    let mut args = FluentArgs::new();
+    args.set("list", list.name.as_str());
+    args.set("link", confirmation_link.as_str());
+    let plain_body = locale.translate("confirmation-text", Some(&args));
+    args.set("list", htmlescape::encode_minimal(&list.name));
+    let html_body = locale.translate("confirmation-html", Some(&args));
     email_client
-        .send_email(&new_subscriber.email, "Welcome!", &html_body, &palin_body)
+        .send_email(
+            &new_subscriber.email,
+            &locale.translate("confirmation-subject", None),
+            &html_body,
+            &plain_body,
+        )
         .await
 }
 
 #[tracing::instrument(
     name = "Storing subscription token in the database",
-    skip(transaction, subscription_token, subscriber_id)
+    skip(transaction, subscription_token, subscriber_id, list_id)
 )]
 pub async fn store_token(
     transaction: &DatabaseTransaction,
     subscription_token: &str,
     subscriber_id: Uuid,
+    list_id: Uuid,
 ) -> Result<(), StoreTokenError> {
     let token = subscription_tokens::ActiveModel {
         subscription_token: Set(subscription_token.to_string()),
         subscriber_id: Set(subscriber_id),
+        list_id: Set(list_id),
     };
 
     token.insert(transaction).await.map_err(|e| {
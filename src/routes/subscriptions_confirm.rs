@@ -1,14 +1,15 @@
 use axum::{
     extract::{Query, State},
     http::StatusCode,
-    response::{IntoResponse, Response},
+    response::{Html, IntoResponse, Response},
 };
-use entity::entities::{prelude::*, subscription_tokens, subscriptions};
+use entity::entities::{list_memberships, prelude::*, subscription_tokens, subscriptions};
 use migration::SimpleExpr;
-use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Value};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, TransactionTrait, Value};
 use uuid::Uuid;
 
-use super::AppState;
+use super::{AppState, get_subscriber_locale};
+use crate::utils::e500;
 
 #[derive(Debug, serde::Deserialize)]
 pub struct Parameters {
@@ -17,50 +18,88 @@ pub struct Parameters {
 
 #[tracing::instrument(name = "Confirm a pending subscriber", skip(parameters, state))]
 pub async fn confirm(parameters: Query<Parameters>, state: State<AppState>) -> Response {
-    let id =
-        match get_subscriber_id_from_token(&parameters.subscription_token, &state.db_connection)
+    let ids =
+        match get_subscription_from_token(&parameters.subscription_token, &state.db_connection)
             .await
         {
-            Ok(id) => id,
+            Ok(ids) => ids,
             Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
         };
 
-    match id {
+    match ids {
         // Non-existing token!
         None => StatusCode::UNAUTHORIZED.into_response(),
-        Some(subscriber_id) => {
-            if confirm_subscriber(subscriber_id, &state.db_connection)
+        Some((subscriber_id, list_id)) => {
+            if confirm_subscriber(subscriber_id, list_id, &state.db_connection)
                 .await
                 .is_err()
             {
                 return StatusCode::INTERNAL_SERVER_ERROR.into_response();
             }
-            StatusCode::OK.into_response()
+            state.metrics.confirmations.inc();
+            confirmation_page(&state.db_connection, subscriber_id, list_id)
+                .await
+                .unwrap_or_else(|e| e)
         }
     }
 }
 
+/// Thank the subscriber in the language they subscribed in.
+async fn confirmation_page(
+    db_connection: &DatabaseConnection,
+    subscriber_id: Uuid,
+    list_id: Uuid,
+) -> Result<Response, Response> {
+    let locale = get_subscriber_locale(db_connection, subscriber_id)
+        .await
+        .map_err(e500)?;
+    let list = Lists::find_by_id(list_id)
+        .one(db_connection)
+        .await
+        .map_err(e500)?
+        .ok_or_else(|| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
+    let html = locale
+        .templates()
+        .render_template(
+            include_str!("subscriptions_confirm.html"),
+            &serde_json::json!({ "locale": locale, "list": list.name }),
+        )
+        .map_err(e500)?;
+    Ok(Html(html).into_response())
+}
+
+/// Confirm the subscriber's membership of the list the token was issued for.
+/// The subscriber itself is marked as confirmed as soon as they have
+/// confirmed one list, since that proves they own the address.
 pub async fn confirm_subscriber(
     subscriber_id: Uuid,
+    list_id: Uuid,
     pool: &DatabaseConnection,
 ) -> Result<(), sea_orm::DbErr> {
+    let confirmed = || SimpleExpr::Value(Value::String(Some(Box::new("confirmed".to_string()))));
+    let transaction = pool.begin().await?;
+
+    ListMemberships::update_many()
+        .col_expr(list_memberships::Column::Status, confirmed())
+        .filter(list_memberships::Column::ListId.eq(list_id))
+        .filter(list_memberships::Column::SubscriberId.eq(subscriber_id))
+        .exec(&transaction)
+        .await?;
     Subscriptions::update_many()
-        .col_expr(
-            subscriptions::Column::Status,
-            SimpleExpr::Value(Value::String(Some(Box::new("confirmed".to_string())))),
-        )
+        .col_expr(subscriptions::Column::Status, confirmed())
         .filter(subscriptions::Column::Id.eq(subscriber_id))
-        .exec(pool)
+        .exec(&transaction)
         .await?;
 
-    Ok(())
+    transaction.commit().await
 }
 
-#[tracing::instrument(name = "Get subscriber ID from token", skip(subscription_token, pool))]
-pub async fn get_subscriber_id_from_token(
+/// Returns the subscriber and the list a confirmation token was issued for.
+#[tracing::instrument(name = "Get subscription from token", skip(subscription_token, pool))]
+pub async fn get_subscription_from_token(
     subscription_token: &str,
     pool: &DatabaseConnection,
-) -> Result<Option<Uuid>, sea_orm::DbErr> {
+) -> Result<Option<(Uuid, Uuid)>, sea_orm::DbErr> {
     let token = subscription_tokens::Entity::find()
         .filter(subscription_tokens::Column::SubscriptionToken.eq(subscription_token))
         .one(pool)
@@ -70,8 +109,5 @@ pub async fn get_subscriber_id_from_token(
             e
         })?;
 
-    match token {
-        Some(token) => Ok(Some(token.subscriber_id)),
-        None => Ok(None),
-    }
+    Ok(token.map(|token| (token.subscriber_id, token.list_id)))
 }
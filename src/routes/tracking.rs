@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, State},
+    http::{
+        StatusCode,
+        header::{CACHE_CONTROL, CONTENT_TYPE},
+    },
+    response::{IntoResponse, Redirect, Response},
+};
+use chrono::Utc;
+use entity::entities::tracking_events;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, Set, prelude::DateTimeWithTimeZone};
+use uuid::Uuid;
+
+use super::AppState;
+use crate::issue_links::TrackingToken;
+
+/// The smallest transparent GIF there is.
+const PIXEL: &[u8] = &[
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+/// Record a click and send the reader on to the original link.
+#[tracing::instrument(name = "Track a click", skip(state, token))]
+pub async fn track_click(State(state): State<AppState>, Path(token): Path<String>) -> Response {
+    let Some(tracked) = state.issue_links().verify_click(&token) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if state.tracking.enabled {
+        record_event(&state.db_connection, &tracked, "click").await;
+    }
+    match tracked.url {
+        Some(url) => Redirect::to(&url).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// Record an open, answering with an invisible image.
+#[tracing::instrument(name = "Track an open", skip(state, token))]
+pub async fn track_open(State(state): State<AppState>, Path(token): Path<String>) -> Response {
+    let Some(tracked) = state.issue_links().verify_open(&token) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    if state.tracking.enabled {
+        record_event(&state.db_connection, &tracked, "open").await;
+    }
+    (
+        [(CONTENT_TYPE, "image/gif"), (CACHE_CONTROL, "no-store")],
+        PIXEL,
+    )
+        .into_response()
+}
+
+/// Failing to record an event must not break the reader's link,
+/// so errors are only logged.
+async fn record_event(db_connection: &DatabaseConnection, tracked: &TrackingToken, kind: &str) {
+    let event = tracking_events::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        issue_id: Set(tracked.issue_id),
+        subscriber_id: Set(tracked.subscriber_id),
+        kind: Set(kind.to_string()),
+        url: Set(tracked.url.clone()),
+        created_at: Set(DateTimeWithTimeZone::from(Utc::now())),
+    };
+    if let Err(e) = event.insert(db_connection).await {
+        tracing::warn!(
+            error.cause_chain = ?e,
+            issue_id = %tracked.issue_id,
+            "Failed to record a tracking event",
+        );
+    }
+}
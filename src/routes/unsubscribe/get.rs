@@ -0,0 +1,43 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+
+use super::UnsubscribeParameters;
+use crate::{
+    routes::{AppState, get_subscriber_locale},
+    utils::e500,
+};
+
+/// Ask for confirmation first: mail scanners follow links in emails,
+/// only a person submits the form.
+pub async fn unsubscribe_form(
+    State(state): State<AppState>,
+    Query(parameters): Query<UnsubscribeParameters>,
+) -> Result<Response, Response> {
+    if !state.issue_links().verify_unsubscribe(
+        parameters.subscriber_id,
+        parameters.issue_id,
+        &parameters.tag,
+    ) {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    let locale = get_subscriber_locale(&state.db_connection, parameters.subscriber_id)
+        .await
+        .map_err(e500)?;
+    let html = locale
+        .templates()
+        .render_template(
+            include_str!("get.html"),
+            &serde_json::json!({
+                "locale": locale,
+                "subscriber_id": parameters.subscriber_id,
+                "issue_id": parameters.issue_id,
+                "tag": parameters.tag,
+            }),
+        )
+        .map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
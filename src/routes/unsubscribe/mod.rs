@@ -0,0 +1,12 @@
+mod get;
+mod post;
+
+pub use get::unsubscribe_form;
+pub use post::unsubscribe;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct UnsubscribeParameters {
+    subscriber_id: uuid::Uuid,
+    issue_id: uuid::Uuid,
+    tag: String,
+}
@@ -0,0 +1,83 @@
+use axum::{
+    Form,
+    extract::State,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+};
+use entity::entities::{list_memberships, newsletter_issue_lists, prelude::*};
+use migration::SimpleExpr;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Value};
+use uuid::Uuid;
+
+use super::UnsubscribeParameters;
+use crate::{
+    routes::{AppState, get_subscriber_locale},
+    telemetry::Redacted,
+    utils::e500,
+};
+
+#[tracing::instrument(
+    name = "Unsubscribe a subscriber",
+    skip(state, parameters),
+    fields(
+        subscriber_id = %parameters.subscriber_id,
+        issue_id = %parameters.issue_id,
+        tag = %Redacted::token(&parameters.tag)
+    )
+)]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Form(parameters): Form<UnsubscribeParameters>,
+) -> Result<Response, Response> {
+    if !state.issue_links().verify_unsubscribe(
+        parameters.subscriber_id,
+        parameters.issue_id,
+        &parameters.tag,
+    ) {
+        return Ok(StatusCode::UNAUTHORIZED.into_response());
+    }
+
+    unsubscribe_from_issue_lists(
+        &state.db_connection,
+        parameters.subscriber_id,
+        parameters.issue_id,
+    )
+    .await
+    .map_err(e500)?;
+    let locale = get_subscriber_locale(&state.db_connection, parameters.subscriber_id)
+        .await
+        .map_err(e500)?;
+    let html = locale
+        .templates()
+        .render_template(
+            include_str!("post.html"),
+            &serde_json::json!({ "locale": locale }),
+        )
+        .map_err(e500)?;
+    Ok(Html::from(html).into_response())
+}
+
+/// Leave every list the issue was sent to.
+/// Memberships of other lists are left alone.
+async fn unsubscribe_from_issue_lists(
+    db_connection: &DatabaseConnection,
+    subscriber_id: Uuid,
+    issue_id: Uuid,
+) -> Result<(), sea_orm::DbErr> {
+    let list_ids = NewsletterIssueLists::find()
+        .filter(newsletter_issue_lists::Column::IssueId.eq(issue_id))
+        .all(db_connection)
+        .await?
+        .into_iter()
+        .map(|l| l.list_id);
+    ListMemberships::update_many()
+        .col_expr(
+            list_memberships::Column::Status,
+            SimpleExpr::Value(Value::String(Some(Box::new("unsubscribed".to_string())))),
+        )
+        .filter(list_memberships::Column::SubscriberId.eq(subscriber_id))
+        .filter(list_memberships::Column::ListId.is_in(list_ids))
+        .exec(db_connection)
+        .await?;
+    Ok(())
+}
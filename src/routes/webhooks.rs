@@ -0,0 +1,146 @@
+use anyhow::Context;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use secrecy::{ExposeSecret, SecretString};
+
+use super::{AppState, error_chain_fmt};
+use crate::domain::SuppressionEntry;
+use crate::suppression::SuppressionReason;
+
+/// Header carrying the hex-encoded HMAC-SHA256 of the request body.
+const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+#[derive(thiserror::Error)]
+pub enum WebhookError {
+    #[error("Invalid webhook credentials.")]
+    AuthError,
+    #[error("{0}")]
+    ValidationError(String),
+    #[error(transparent)]
+    UnexpectedError(#[from] anyhow::Error),
+}
+
+impl std::fmt::Debug for WebhookError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        error_chain_fmt(self, f)
+    }
+}
+
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        match self {
+            WebhookError::AuthError => StatusCode::UNAUTHORIZED.into_response(),
+            WebhookError::ValidationError(_) => StatusCode::BAD_REQUEST.into_response(),
+            WebhookError::UnexpectedError(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
+
+/// The subset of Postmark's webhook payloads we act upon.
+#[derive(Debug, serde::Deserialize)]
+#[serde(tag = "RecordType")]
+enum EmailEvent {
+    Bounce {
+        #[serde(rename = "Type")]
+        kind: String,
+        #[serde(rename = "Email")]
+        email: String,
+    },
+    SpamComplaint {
+        #[serde(rename = "Email")]
+        email: String,
+    },
+    /// Deliveries, opens, clicks, ...
+    #[serde(other)]
+    Other,
+}
+
+impl EmailEvent {
+    /// The address to suppress, if any. Soft bounces are transient and
+    /// do not warrant dropping the address.
+    fn suppression(&self) -> Option<(&str, SuppressionReason)> {
+        match self {
+            EmailEvent::Bounce { kind, email }
+                if kind == "HardBounce" || kind == "BadEmailAddress" =>
+            {
+                Some((email, SuppressionReason::HardBounce))
+            }
+            EmailEvent::SpamComplaint { email } => Some((email, SuppressionReason::SpamComplaint)),
+            _ => None,
+        }
+    }
+}
+
+/// Receive bounce and spam-complaint notifications from the email provider.
+#[tracing::instrument(name = "Receive an email event", skip_all)]
+pub async fn email_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, WebhookError> {
+    if !is_authentic(&headers, &body, &state.webhooks.secret) {
+        return Err(WebhookError::AuthError);
+    }
+    let event: EmailEvent =
+        serde_json::from_slice(&body).map_err(|e| WebhookError::ValidationError(e.to_string()))?;
+    if let Some((email, reason)) = event.suppression() {
+        let entry = SuppressionEntry::parse_email(email).map_err(WebhookError::ValidationError)?;
+        state
+            .suppression_list()
+            .add(&entry, reason.as_str(), None)
+            .await
+            .context("Failed to suppress an email address.")?;
+        tracing::info!(reason = reason.as_str(), "Suppressed an email address");
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Postmark can send the secret as the password of HTTP Basic auth;
+/// other senders may sign the body with it instead.
+fn is_authentic(headers: &HeaderMap, body: &[u8], secret: &SecretString) -> bool {
+    if let Some(signature) = headers
+        .get(SIGNATURE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| hex::decode(h).ok())
+    {
+        return mac(secret, body).verify_slice(&signature).is_ok();
+    }
+    match basic_auth_password(headers) {
+        // Compare MACs rather than the raw strings to keep the check
+        // constant-time.
+        Some(password) => mac(secret, password.as_bytes())
+            .verify_slice(
+                &mac(secret, secret.expose_secret().as_bytes())
+                    .finalize()
+                    .into_bytes(),
+            )
+            .is_ok(),
+        None => false,
+    }
+}
+
+fn basic_auth_password(headers: &HeaderMap) -> Option<String> {
+    let encoded = headers
+        .get("Authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Basic ")?;
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (_username, password) = decoded.split_once(':')?;
+    Some(password.to_string())
+}
+
+fn mac(secret: &SecretString, message: &[u8]) -> Hmac<sha2::Sha256> {
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.expose_secret().as_bytes()).unwrap();
+    mac.update(message);
+    mac
+}
@@ -0,0 +1,208 @@
+use anyhow::Context;
+use chrono::Utc;
+use entity::entities::{newsletter_issues, prelude::*};
+use migration::SimpleExpr;
+use sea_orm::prelude::*;
+use sea_orm::{DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, TransactionTrait, Value};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+use crate::{
+    configuration::Settings,
+    email_client::EmailClient,
+    issue_delivery::{deliver_and_publish, enqueue_delivery},
+    issue_links::IssueLinks,
+    metrics::Metrics,
+    shutdown::drain,
+    startup::{HmacSecret, get_db_connection},
+    suppression::SuppressionList,
+    telemetry::TraceContext,
+};
+
+/// Deliver scheduled issues until `shutdown` is cancelled. An issue being
+/// delivered at that point gets the shutdown grace period to finish.
+pub async fn run_scheduler_until_stopped(
+    configuration: Settings,
+    metrics: Metrics,
+    shutdown: CancellationToken,
+) -> Result<(), anyhow::Error> {
+    let db_connection = get_db_connection(&configuration.database);
+    let email_client = configuration
+        .email_client
+        .client()
+        .with_suppression_list(SuppressionList::new(db_connection.clone()))
+        .with_metrics(metrics);
+    let links = IssueLinks::new(
+        configuration.application.public_url(),
+        HmacSecret(configuration.application.hmac_secret),
+        configuration.tracking.enabled,
+    );
+    let scheduler = scheduler_loop(
+        db_connection,
+        email_client,
+        links,
+        configuration.scheduler.poll_interval(),
+        &shutdown,
+    );
+    match drain(scheduler, &shutdown, configuration.shutdown.grace_period()).await {
+        Some(outcome) => outcome,
+        None => {
+            tracing::warn!(
+                "Newsletter delivery still in progress after the grace period was dropped"
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn scheduler_loop(
+    db_connection: DatabaseConnection,
+    email_client: EmailClient,
+    links: IssueLinks,
+    poll_interval: Duration,
+    shutdown: &CancellationToken,
+) -> Result<(), anyhow::Error> {
+    while !shutdown.is_cancelled() {
+        if let Err(e) = promote_due_issues(&db_connection, &email_client, &links, shutdown).await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to promote due newsletter issues",
+            );
+        }
+        if let Err(e) = resume_deliveries(&db_connection, &email_client, &links, shutdown).await {
+            tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                "Failed to resume newsletter deliveries",
+            );
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(poll_interval) => {}
+            _ = shutdown.cancelled() => {}
+        }
+    }
+    Ok(())
+}
+
+/// Deliver every scheduled issue whose send time has passed, stopping
+/// between issues once `shutdown` is cancelled.
+/// Returns the number of issues that were delivered successfully.
+#[tracing::instrument(name = "Promote due newsletter issues", skip_all)]
+pub async fn promote_due_issues(
+    db_connection: &DatabaseConnection,
+    email_client: &EmailClient,
+    links: &IssueLinks,
+    shutdown: &CancellationToken,
+) -> Result<usize, anyhow::Error> {
+    let due = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.eq("scheduled"))
+        .filter(newsletter_issues::Column::SendAt.lte(Utc::now()))
+        .order_by_asc(newsletter_issues::Column::SendAt)
+        .all(db_connection)
+        .await
+        .context("Failed to fetch due newsletter issues.")?;
+
+    let mut delivered = 0;
+    for issue in due {
+        if shutdown.is_cancelled() {
+            // Left scheduled, for the next instance to pick up.
+            break;
+        }
+        if !claim_issue(db_connection, issue.id).await? {
+            // Another instance got there first, or it was cancelled meanwhile.
+            continue;
+        }
+        // Carry on with the trace the issue was scheduled in.
+        let delivery =
+            tracing::info_span!("Deliver a scheduled newsletter issue", issue_id = %issue.id);
+        TraceContext::decode(issue.trace_context.as_deref()).attach(&delivery);
+        match deliver_and_publish(db_connection, email_client, links, &issue)
+            .instrument(delivery)
+            .await
+        {
+            Ok(()) => delivered += 1,
+            Err(e) => tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                issue_id = %issue.id,
+                "Failed to deliver a scheduled newsletter issue",
+            ),
+        }
+    }
+    Ok(delivered)
+}
+
+/// Resume the delivery of every issue that failed or was interrupted,
+/// stopping between issues once `shutdown` is cancelled. Only the emails
+/// still queued are sent.
+/// Returns the number of issues that were delivered successfully.
+#[tracing::instrument(name = "Resume newsletter deliveries", skip_all)]
+pub async fn resume_deliveries(
+    db_connection: &DatabaseConnection,
+    email_client: &EmailClient,
+    links: &IssueLinks,
+    shutdown: &CancellationToken,
+) -> Result<usize, anyhow::Error> {
+    let undelivered = NewsletterIssues::find()
+        .filter(newsletter_issues::Column::State.is_in(["sending", "failed"]))
+        .order_by_asc(newsletter_issues::Column::CreatedAt)
+        .all(db_connection)
+        .await
+        .context("Failed to fetch undelivered newsletter issues.")?;
+
+    let mut delivered = 0;
+    for issue in undelivered {
+        if shutdown.is_cancelled() {
+            break;
+        }
+        let delivery =
+            tracing::info_span!("Resume the delivery of a newsletter issue", issue_id = %issue.id);
+        TraceContext::decode(issue.trace_context.as_deref()).attach(&delivery);
+        match deliver_and_publish(db_connection, email_client, links, &issue)
+            .instrument(delivery)
+            .await
+        {
+            Ok(()) => delivered += 1,
+            Err(e) => tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                issue_id = %issue.id,
+                "Failed to resume the delivery of a newsletter issue",
+            ),
+        }
+    }
+    Ok(delivered)
+}
+
+/// Move a scheduled issue into the `sending` state and queue its emails.
+/// Returns `false` if the issue was no longer scheduled.
+async fn claim_issue(
+    db_connection: &DatabaseConnection,
+    issue_id: Uuid,
+) -> Result<bool, anyhow::Error> {
+    let transaction = db_connection
+        .begin()
+        .await
+        .context("Failed to start a transaction.")?;
+    let result = NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::State,
+            SimpleExpr::Value(Value::String(Some(Box::new("sending".to_string())))),
+        )
+        .filter(newsletter_issues::Column::Id.eq(issue_id))
+        .filter(newsletter_issues::Column::State.eq("scheduled"))
+        .exec(&transaction)
+        .await
+        .context("Failed to claim a scheduled newsletter issue.")?;
+    if result.rows_affected != 1 {
+        return Ok(false);
+    }
+    enqueue_delivery(&transaction, issue_id).await?;
+    transaction
+        .commit()
+        .await
+        .context("Failed to claim a scheduled newsletter issue.")?;
+    Ok(true)
+}
@@ -0,0 +1,228 @@
+//! Security headers added to every response of the application.
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{HeaderMap, HeaderName, HeaderValue, header, request::Parts},
+    response::Response,
+};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use rand::{Rng, thread_rng};
+use tower::{Layer, Service};
+
+use crate::configuration::SecurityHeadersSettings;
+
+/// Where browsers send Content-Security-Policy violation reports.
+pub const CSP_REPORT_PATH: &str = "/csp-reports";
+const NONCE_PLACEHOLDER: &str = "{nonce}";
+
+/// A random value generated for each request, safe to put in HTML as it
+/// is. Inline `<script>` and `<style>` elements carrying it are allowed by
+/// the policy.
+#[derive(Debug, Clone)]
+pub struct CspNonce(pub String);
+
+impl CspNonce {
+    fn generate() -> Self {
+        let bytes: [u8; 16] = thread_rng().r#gen();
+        Self(URL_SAFE_NO_PAD.encode(bytes))
+    }
+}
+
+impl std::fmt::Display for CspNonce {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<S> FromRequestParts<S> for CspNonce
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    /// Outside of [`SecurityHeadersLayer`] no policy is sent, any nonce
+    /// will do.
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<CspNonce>()
+            .cloned()
+            .unwrap_or_else(CspNonce::generate))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SecurityHeadersLayer {
+    csp_header: HeaderName,
+    /// Split around the nonce placeholders.
+    csp_parts: Vec<String>,
+    headers: HeaderMap,
+}
+
+impl SecurityHeadersLayer {
+    /// Fails if a configured value cannot be sent as a header.
+    pub fn new(settings: &SecurityHeadersSettings) -> Result<Self, anyhow::Error> {
+        let csp_header = if settings.csp_report_only {
+            header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+        } else {
+            header::CONTENT_SECURITY_POLICY
+        };
+        let policy = format!(
+            "{}; report-uri {}",
+            settings
+                .content_security_policy
+                .trim()
+                .trim_end_matches(';'),
+            CSP_REPORT_PATH
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        );
+        headers.insert(
+            header::X_FRAME_OPTIONS,
+            HeaderValue::try_from(&settings.frame_options)?,
+        );
+        headers.insert(
+            header::REFERRER_POLICY,
+            HeaderValue::try_from(&settings.referrer_policy)?,
+        );
+        headers.insert(
+            HeaderName::from_static("permissions-policy"),
+            HeaderValue::try_from(&settings.permissions_policy)?,
+        );
+        if let Some(max_age) = settings.hsts_max_age_seconds {
+            headers.insert(
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::try_from(format!("max-age={}; includeSubDomains", max_age))?,
+            );
+        }
+        let layer = Self {
+            csp_header,
+            csp_parts: policy.split(NONCE_PLACEHOLDER).map(str::to_owned).collect(),
+            headers,
+        };
+        layer.policy(&CspNonce::generate())?;
+        Ok(layer)
+    }
+
+    fn policy(&self, nonce: &CspNonce) -> Result<HeaderValue, header::InvalidHeaderValue> {
+        HeaderValue::try_from(self.csp_parts.join(&nonce.0))
+    }
+
+    /// Headers set by a handler take precedence.
+    fn apply(&self, response: &mut Response, nonce: &CspNonce) {
+        let response_headers = response.headers_mut();
+        for (name, value) in &self.headers {
+            response_headers
+                .entry(name)
+                .or_insert_with(|| value.clone());
+        }
+        if let Ok(policy) = self.policy(nonce) {
+            response_headers.entry(&self.csp_header).or_insert(policy);
+        }
+    }
+}
+
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeaders<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeaders {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SecurityHeaders<S> {
+    inner: S,
+    layer: SecurityHeadersLayer,
+}
+
+impl<S> Service<Request> for SecurityHeaders<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let nonce = CspNonce::generate();
+        request.extensions_mut().insert(nonce.clone());
+        // The clone may not be ready, use the service that was polled.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let layer = self.layer.clone();
+        Box::pin(async move {
+            let mut response = inner.call(request).await?;
+            layer.apply(&mut response, &nonce);
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> SecurityHeadersSettings {
+        SecurityHeadersSettings {
+            content_security_policy: "script-src 'nonce-{nonce}'; style-src 'nonce-{nonce}';"
+                .into(),
+            csp_report_only: false,
+            hsts_max_age_seconds: None,
+            frame_options: "DENY".into(),
+            referrer_policy: "no-referrer".into(),
+            permissions_policy: "camera=()".into(),
+        }
+    }
+
+    #[test]
+    fn the_nonce_is_placed_in_the_policy() {
+        let layer = SecurityHeadersLayer::new(&settings()).unwrap();
+        let policy = layer.policy(&CspNonce("abc".into())).unwrap();
+        assert_eq!(
+            policy,
+            "script-src 'nonce-abc'; style-src 'nonce-abc'; report-uri /csp-reports"
+        );
+    }
+
+    #[test]
+    fn headers_set_by_handlers_are_kept() {
+        let layer = SecurityHeadersLayer::new(&settings()).unwrap();
+        let mut response = Response::new(axum::body::Body::empty());
+        response.headers_mut().insert(
+            header::X_FRAME_OPTIONS,
+            HeaderValue::from_static("SAMEORIGIN"),
+        );
+        layer.apply(&mut response, &CspNonce("abc".into()));
+        assert_eq!(response.headers()[header::X_FRAME_OPTIONS], "SAMEORIGIN");
+        assert_eq!(response.headers()[header::REFERRER_POLICY], "no-referrer");
+        assert!(
+            response
+                .headers()
+                .get(header::STRICT_TRANSPORT_SECURITY)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_rejected_upfront() {
+        let mut settings = settings();
+        settings.referrer_policy = "no-referrer\n".into();
+        assert!(SecurityHeadersLayer::new(&settings).is_err());
+    }
+}
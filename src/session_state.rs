@@ -1,4 +1,6 @@
 use axum::{extract::FromRequestParts, http::request::Parts};
+use rand::distributions::Alphanumeric;
+use rand::{Rng, thread_rng};
 use reqwest::StatusCode;
 use tower_sessions::Session;
 use uuid::Uuid;
@@ -8,6 +10,7 @@ pub struct TypedSession(Session);
 
 impl TypedSession {
     const USER_ID_KEY: &'static str = "user_id";
+    const CSRF_TOKEN_KEY: &'static str = "csrf_token";
 
     pub async fn insert_iser_id(
         &self,
@@ -20,6 +23,24 @@ impl TypedSession {
         self.0.get(Self::USER_ID_KEY).await
     }
 
+    /// The synchronizer token forms must send back, created on first use.
+    pub async fn csrf_token(&self) -> Result<String, tower_sessions::session::Error> {
+        match self.0.get(Self::CSRF_TOKEN_KEY).await? {
+            Some(token) => Ok(token),
+            None => self.rotate_csrf_token().await,
+        }
+    }
+
+    pub async fn rotate_csrf_token(&self) -> Result<String, tower_sessions::session::Error> {
+        let token: String = thread_rng()
+            .sample_iter(Alphanumeric)
+            .map(char::from)
+            .take(32)
+            .collect();
+        self.0.insert(Self::CSRF_TOKEN_KEY, &token).await?;
+        Ok(token)
+    }
+
     pub async fn cycle_id(&self) -> Result<(), tower_sessions::session::Error> {
         self.0.cycle_id().await
     }
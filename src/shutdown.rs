@@ -0,0 +1,89 @@
+//! Stopping the API and the background tasks without cutting requests and
+//! email sends in half.
+use std::future::Future;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+/// Resolve once the process is asked to stop, with SIGTERM (what
+/// orchestrators send) or SIGINT (Ctrl+C).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for SIGINT.");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM.")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
+
+/// Run `task` to completion, unless it is still going `grace_period` after
+/// `shutdown` was cancelled. Returns `None` when the task was dropped.
+pub async fn drain<F: Future>(
+    task: F,
+    shutdown: &CancellationToken,
+    grace_period: Duration,
+) -> Option<F::Output> {
+    let deadline = async {
+        shutdown.cancelled().await;
+        tokio::time::sleep(grace_period).await;
+    };
+    tokio::select! {
+        output = task => Some(output),
+        _ = deadline => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::drain;
+    use std::time::Duration;
+    use tokio_util::sync::CancellationToken;
+
+    #[tokio::test]
+    async fn a_task_finishing_within_the_grace_period_completes() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let task = async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            42
+        };
+        assert_eq!(
+            drain(task, &shutdown, Duration::from_secs(5)).await,
+            Some(42)
+        );
+    }
+
+    #[tokio::test]
+    async fn a_task_outliving_the_grace_period_is_dropped() {
+        let shutdown = CancellationToken::new();
+        shutdown.cancel();
+        let task = std::future::pending::<()>();
+        assert_eq!(
+            drain(task, &shutdown, Duration::from_millis(10)).await,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn the_grace_period_only_starts_with_the_shutdown() {
+        let shutdown = CancellationToken::new();
+        let task = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            "done"
+        };
+        assert_eq!(drain(task, &shutdown, Duration::ZERO).await, Some("done"));
+    }
+}
@@ -1,28 +1,74 @@
 use crate::{
-    authentication::reject_anonymous_users,
-    configuration::{DatabaseSettings, Settings, get_configuration},
+    authentication::{csrf_protection, reject_anonymous_users, reject_non_owners},
+    bot_protection::BotProtection,
+    configuration::{DatabaseSettings, Settings, ShutdownSettings, get_configuration},
     email_client::EmailClient,
+    metrics::{Metrics, track_http_requests},
+    migrations::migrate_on_start,
+    request_id::{RequestIdLayer, access_log},
     routes::{
-        AppState, admin_dashboard, change_password, change_password_form, confirm, greet,
-        health_check, home, index, log_out, login, login_form, publish_newsletter,
-        publish_newsletter_form, subscribe,
+        AppState, add_suppression, admin_dashboard, archive, archive_issue, atom_feed,
+        cancel_newsletter, change_email, change_email_form, change_log_level, change_password,
+        change_password_form, confirm, create_draft, create_list, csp_report, edit_draft_form,
+        email_events, export_metrics, export_suppressions, greet, health_check, hide_newsletter,
+        home, import_suppressions, index, issue_web_view, liveness, log_level_form, log_out, login,
+        login_form, manage_lists_form, newsletter_drafts, newsletter_report, preview_draft,
+        publish_draft, publish_newsletter, publish_newsletter_form, published_newsletters,
+        readiness, remove_suppression, reschedule_newsletter, rss_feed, scheduled_newsletters,
+        send_test_email, show_newsletter, subscribe, suppression_list_form, track_click,
+        track_open, unsubscribe, unsubscribe_form, update_draft,
     },
+    security_headers::{CSP_REPORT_PATH, SecurityHeadersLayer},
+    shutdown::drain,
+    suppression::SuppressionList,
+    telemetry::{redact_query_for_span, restore_query},
+    tls::{CertificateStore, TlsListener, redirect_to_https, watch_certificate},
 };
+use anyhow::Context;
 use axum::{
     Router,
-    routing::{IntoMakeService, get, post},
-    serve::Serve,
+    extract::{ConnectInfo, connect_info::IntoMakeServiceWithConnectInfo},
+    middleware::AddExtension,
+    routing::{get, post},
+    serve::{ListenerExt, Serve, TapIo},
 };
 use axum_messages::MessagesManagerLayer;
 use axum_tracing_opentelemetry::middleware::OtelAxumLayer;
-use sea_orm::{DatabaseConnection, SqlxPostgresConnector, sqlx::postgres::PgPoolOptions};
+use sea_orm::{DatabaseConnection, SqlxPostgresConnector};
 use secrecy::{ExposeSecret, SecretString};
+use std::net::SocketAddr;
+use std::sync::Arc;
 use time::Duration;
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{TlsAcceptor, server::TlsStream};
+use tokio_util::sync::CancellationToken;
 use tower_sessions::{Expiry, SessionManagerLayer};
 use tower_sessions_redis_store::{RedisStore, fred::prelude::*};
 
-type Server = Serve<TcpListener, IntoMakeService<Router>, Router>;
+/// Backing off further than this would only delay the recovery.
+const MAX_CONNECT_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+type Service = IntoMakeServiceWithConnectInfo<Router, SocketAddr>;
+type ConnectedRouter = AddExtension<Router, ConnectInfo<SocketAddr>>;
+type TlsConnections = TapIo<TlsListener, fn(&mut TlsStream<TcpStream>)>;
+
+/// The API, over plain HTTP or over TLS.
+pub enum Server {
+    Http(Serve<TcpListener, Service, ConnectedRouter>),
+    Https(Serve<TlsConnections, Service, ConnectedRouter>),
+}
+
+impl Server {
+    async fn run_until(
+        self,
+        signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(), std::io::Error> {
+        match self {
+            Server::Http(server) => server.with_graceful_shutdown(signal).await,
+            Server::Https(server) => server.with_graceful_shutdown(signal).await,
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct HmacSecret(pub SecretString);
@@ -30,23 +76,26 @@ pub struct HmacSecret(pub SecretString);
 pub struct Application {
     port: u16,
     server: Server,
+    metrics: Metrics,
+    shutdown: CancellationToken,
+    shutdown_settings: ShutdownSettings,
+    certificates: Option<Arc<CertificateStore>>,
+    redirect_listener: Option<TcpListener>,
 }
 
 impl Application {
     pub async fn build(configuration: Settings) -> anyhow::Result<Self, anyhow::Error> {
-        let db_connection = get_db_connection(&configuration.database);
-
-        let sender_email = configuration
+        let db_connection = connect_with_retry(&configuration.database).await?;
+        if configuration.database.migrate_on_start {
+            migrate_on_start(&configuration.database).await?;
+        }
+        let metrics = Metrics::new();
+        let email_client = configuration
             .email_client
-            .sender()
-            .expect("Invalid sender email address.");
-        let timeout = configuration.email_client.timeout();
-        let email_client = EmailClient::new(
-            configuration.email_client.base_url,
-            sender_email,
-            configuration.email_client.authorization_token,
-            timeout,
-        );
+            .clone()
+            .client()
+            .with_suppression_list(SuppressionList::new(db_connection.clone()))
+            .with_metrics(metrics.clone());
         let address = format!(
             "{}:{}",
             configuration.application.host, configuration.application.port
@@ -57,30 +106,97 @@ impl Application {
 
         let port = listener.local_addr()?.port();
 
+        let certificates = load_certificates(&configuration)?;
+        let redirect_port = configuration
+            .application
+            .tls
+            .as_ref()
+            .and_then(|tls| tls.redirect_http_port);
+        let redirect_listener = match redirect_port {
+            Some(redirect_port) => {
+                let address = format!("{}:{}", configuration.application.host, redirect_port);
+                Some(TcpListener::bind(address).await?)
+            }
+            None => None,
+        };
+        let shutdown = CancellationToken::new();
+        let shutdown_settings = configuration.shutdown.clone();
         let server = run(
             listener,
             db_connection,
             email_client,
-            format!(
-                "{}:{}",
-                configuration.application.base_url, configuration.application.port
-            ),
-            HmacSecret(configuration.application.hmac_secret),
-            configuration.redis_uri,
+            metrics.clone(),
+            shutdown.clone(),
+            certificates
+                .as_ref()
+                .map(|certificates| certificates.acceptor()),
+            configuration,
         )
         .await?;
 
-        Ok(Self { port, server })
+        Ok(Self {
+            port,
+            server,
+            metrics,
+            shutdown,
+            shutdown_settings,
+            certificates,
+            redirect_listener,
+        })
     }
 
     pub fn port(&self) -> u16 {
         self.port
     }
 
+    /// The port of the listener redirecting plain HTTP to HTTPS, if any.
+    pub fn redirect_port(&self) -> Option<u16> {
+        self.redirect_listener
+            .as_ref()
+            .and_then(|listener| listener.local_addr().ok())
+            .map(|address| address.port())
+    }
+
+    /// For the background tasks running next to the API to report to.
+    pub fn metrics(&self) -> Metrics {
+        self.metrics.clone()
+    }
+
+    /// Cancelling it starts the shutdown of the API, and of every
+    /// background task given a clone.
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
     // A more expressive name that makes it clear that
     // this function only returns when the application is stopped.
     pub async fn run_until_stopped(self) -> Result<(), std::io::Error> {
-        self.server.await
+        if let Some(certificates) = self.certificates {
+            tokio::spawn(watch_certificate(certificates, self.shutdown.clone()));
+        }
+        if let Some(listener) = self.redirect_listener {
+            let redirect = axum::serve(listener, redirect_to_https(self.port))
+                .with_graceful_shutdown(self.shutdown.clone().cancelled_owned());
+            tokio::spawn(redirect.into_future());
+        }
+        let drain_delay = self.shutdown_settings.drain_delay();
+        let shutdown = self.shutdown.clone();
+        // Readiness is down from the moment the token is cancelled, new
+        // connections are still accepted until the load balancer notices.
+        let stop_accepting = async move {
+            shutdown.cancelled().await;
+            tokio::time::sleep(drain_delay).await;
+            tracing::info!("No longer accepting connections");
+        };
+        let server = self.server.run_until(stop_accepting);
+        let grace_period = drain_delay + self.shutdown_settings.grace_period();
+        match drain(server, &self.shutdown, grace_period).await {
+            Some(outcome) => outcome,
+            None => {
+                tracing::warn!("Requests still in flight after the grace period were dropped");
+                Ok(())
+            }
+        }
     }
 }
 
@@ -88,16 +204,21 @@ pub async fn run(
     listener: std::net::TcpListener,
     db_connection: DatabaseConnection,
     email_client: EmailClient,
-    base_url: String,
-    secret: HmacSecret,
-    redis_uri: SecretString,
+    metrics: Metrics,
+    shutdown: CancellationToken,
+    tls: Option<TlsAcceptor>,
+    configuration: Settings,
 ) -> Result<Server, anyhow::Error> {
-    let app_state = AppState {
-        db_connection,
-        email_client,
-        base_url,
-        secret,
-    };
+    let email_policy = configuration
+        .email_policy
+        .policy()
+        .context("Failed to load the email policy.")?;
+    let security_headers = SecurityHeadersLayer::new(&configuration.application.security_headers)
+        .context("Invalid security header settings.")?;
+    let redis_uri = configuration.redis_uri;
+    let base_url = configuration.application.public_url();
+    let secret = HmacSecret(configuration.application.hmac_secret);
+    let bot_protection = BotProtection::new(&configuration.bot_protection, secret.clone());
 
     let redis_pool = Pool::new(
         Config::from_url(redis_uri.expose_secret())?,
@@ -109,17 +230,54 @@ pub async fn run(
     .unwrap();
     let _redis_conn = redis_pool.connect();
     redis_pool.wait_for_connect().await?;
-    let redis_store = RedisStore::new(redis_pool);
+    let redis_store = RedisStore::new(redis_pool.clone());
+
+    let app_state = AppState {
+        db_connection,
+        email_client,
+        base_url,
+        secret,
+        tracking: configuration.tracking,
+        webhooks: configuration.webhooks,
+        email_policy: Arc::new(email_policy),
+        bot_protection: Arc::new(bot_protection),
+        metrics: metrics.clone(),
+        metrics_token: configuration.metrics.token,
+        redis_pool: redis_pool.clone(),
+        health: configuration.health,
+        shutdown,
+    };
     let session_layer = SessionManagerLayer::new(redis_store)
-        .with_secure(false)
+        .with_secure(tls.is_some())
         .with_expiry(Expiry::OnInactivity(Duration::seconds(10)));
 
     let app = Router::new()
         .route("/health_check", get(health_check))
+        .route("/health/live", get(liveness))
+        .route("/health/ready", get(readiness))
+        .route("/metrics", get(export_metrics))
         .route("/subscriptions", post(subscribe))
         .route("/subscriptions/confirm", get(confirm))
+        .route(
+            "/subscriptions/unsubscribe",
+            get(unsubscribe_form).post(unsubscribe),
+        )
+        .route("/issues/{issue_id}", get(issue_web_view))
+        .route("/t/c/{token}", get(track_click))
+        .route("/t/o/{token}", get(track_open))
+        .route("/webhooks/email-events", post(email_events))
+        .route(CSP_REPORT_PATH, post(csp_report))
+        .route("/archive", get(archive))
+        .route("/archive/{slug}", get(archive_issue))
+        .route("/feed.rss", get(rss_feed))
+        .route("/feed.atom", get(atom_feed))
         .route("/", get(home))
-        .route("/login", get(login_form).post(login))
+        .route(
+            "/login",
+            get(login_form)
+                .post(login)
+                .layer(axum::middleware::from_fn(csrf_protection)),
+        )
         .route("/index", get(index))
         .route("/{name}", get(greet))
         .nest(
@@ -127,28 +285,118 @@ pub async fn run(
             Router::new()
                 .route("/newsletters", post(publish_newsletter))
                 .route("/newsletters", get(publish_newsletter_form))
+                .route("/newsletters/scheduled", get(scheduled_newsletters))
+                .route(
+                    "/newsletters/scheduled/{issue_id}/reschedule",
+                    post(reschedule_newsletter),
+                )
+                .route(
+                    "/newsletters/scheduled/{issue_id}/cancel",
+                    post(cancel_newsletter),
+                )
+                .route(
+                    "/newsletters/drafts",
+                    get(newsletter_drafts).post(create_draft),
+                )
+                .route(
+                    "/newsletters/drafts/{draft_id}",
+                    get(edit_draft_form).post(update_draft),
+                )
+                .route("/newsletters/drafts/{draft_id}/preview", get(preview_draft))
+                .route("/newsletters/drafts/{draft_id}/test", post(send_test_email))
+                .route(
+                    "/newsletters/drafts/{draft_id}/publish",
+                    post(publish_draft),
+                )
+                .route("/newsletters/published", get(published_newsletters))
+                .route(
+                    "/newsletters/published/{issue_id}/report",
+                    get(newsletter_report),
+                )
+                .route(
+                    "/newsletters/published/{issue_id}/hide",
+                    post(hide_newsletter),
+                )
+                .route(
+                    "/newsletters/published/{issue_id}/show",
+                    post(show_newsletter),
+                )
                 .route("/dashboard", get(admin_dashboard))
+                .route("/lists", get(manage_lists_form).post(create_list))
+                .route("/email", get(change_email_form).post(change_email))
+                .route(
+                    "/log-level",
+                    get(log_level_form).post(change_log_level).layer(
+                        axum::middleware::from_fn_with_state(app_state.clone(), reject_non_owners),
+                    ),
+                )
+                .route(
+                    "/suppressions",
+                    get(suppression_list_form).post(add_suppression),
+                )
+                .route("/suppressions/import", post(import_suppressions))
+                .route("/suppressions/export", get(export_suppressions))
+                .route(
+                    "/suppressions/{suppression_id}/delete",
+                    post(remove_suppression),
+                )
                 .route("/password", get(change_password_form))
                 .route("/password", post(change_password))
                 .route("/logout", post(log_out))
+                .layer(axum::middleware::from_fn(csrf_protection))
                 .layer(axum::middleware::from_fn(reject_anonymous_users)),
         )
+        .route_layer(axum::middleware::from_fn_with_state(
+            metrics,
+            track_http_requests,
+        ))
+        .layer(axum::middleware::from_fn(access_log))
+        .layer(axum::middleware::from_fn(restore_query))
+        .layer(RequestIdLayer)
         //start OpenTelemetry trace on incoming request
         .layer(OtelAxumLayer::default())
+        .layer(axum::middleware::from_fn(redact_query_for_span))
         .layer(MessagesManagerLayer)
         .layer(session_layer)
+        .layer(security_headers)
         .with_state(app_state.clone());
 
     let listener = TcpListener::from_std(listener)?;
     println!(
-        "Listening on http://{:?}",
+        "Listening on {}://{:?}",
+        if tls.is_some() { "https" } else { "http" },
         listener.local_addr().expect("network error")
     );
 
-    let server = axum::serve(listener, app.into_make_service());
+    // The subscribe endpoint rate-limits by client address.
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+    let server = match tls {
+        Some(acceptor) => {
+            // Tapping the connections is what gives them a `ConnectInfo`.
+            let listener = TlsListener::new(listener, acceptor)?.tap_io(
+                (|stream| {
+                    let _ = stream.get_ref().0.set_nodelay(true);
+                }) as fn(&mut TlsStream<TcpStream>),
+            );
+            Server::Https(axum::serve(listener, service))
+        }
+        None => Server::Http(axum::serve(listener, service)),
+    };
     Ok(server)
 }
 
+/// The certificate to serve, when TLS is configured.
+fn load_certificates(
+    configuration: &Settings,
+) -> Result<Option<Arc<CertificateStore>>, anyhow::Error> {
+    let Some(tls) = &configuration.application.tls else {
+        return Ok(None);
+    };
+    let certificates =
+        CertificateStore::load(tls).context("Failed to load the TLS certificate.")?;
+    Ok(Some(Arc::new(certificates)))
+}
+
 pub async fn build(configuration: Settings) -> Result<Server, anyhow::Error> {
     let address = format!(
         "{}:{}",
@@ -160,30 +408,64 @@ pub async fn build(configuration: Settings) -> Result<Server, anyhow::Error> {
     let configuration = get_configuration().expect("Failed to read configuration");
     let db_connection = get_db_connection(&configuration.database);
 
-    let sender_email = configuration
+    let metrics = Metrics::new();
+    let email_client = configuration
         .email_client
-        .sender()
-        .expect("Invalid sender email address.");
-    let timeout = configuration.email_client.timeout();
-    let email_client = EmailClient::new(
-        configuration.email_client.base_url,
-        sender_email,
-        configuration.email_client.authorization_token,
-        timeout,
-    );
+        .clone()
+        .client()
+        .with_suppression_list(SuppressionList::new(db_connection.clone()))
+        .with_metrics(metrics.clone());
+    let certificates = load_certificates(&configuration)?;
 
     run(
         listener,
         db_connection,
         email_client,
-        configuration.application.base_url,
-        HmacSecret(configuration.application.hmac_secret),
-        configuration.redis_uri,
+        metrics,
+        CancellationToken::new(),
+        certificates
+            .as_ref()
+            .map(|certificates| certificates.acceptor()),
+        configuration,
     )
     .await
 }
 
 pub fn get_db_connection(configuration: &DatabaseSettings) -> DatabaseConnection {
-    let db_pool = PgPoolOptions::new().connect_lazy_with(configuration.with_db());
+    let db_pool = configuration
+        .pool_options()
+        .connect_lazy_with(configuration.with_db());
     SqlxPostgresConnector::from_sqlx_postgres_pool(db_pool)
 }
+
+/// Wait for Postgres to accept connections, backing off between attempts,
+/// so that an instance starting next to its database does not fail its
+/// first requests.
+pub async fn connect_with_retry(
+    configuration: &DatabaseSettings,
+) -> Result<DatabaseConnection, anyhow::Error> {
+    let db_connection = get_db_connection(configuration);
+    let mut backoff = configuration.connect_backoff();
+    let mut attempt = 1;
+    loop {
+        match db_connection.ping().await {
+            Ok(()) => return Ok(db_connection),
+            Err(e) if attempt < configuration.connect_attempts => {
+                tracing::warn!(
+                    error = %e,
+                    attempt,
+                    retry_in_ms = backoff.as_millis() as u64,
+                    "Postgres is not reachable yet"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                attempt += 1;
+            }
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to reach Postgres after {} attempts.", attempt)
+                });
+            }
+        }
+    }
+}
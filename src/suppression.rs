@@ -0,0 +1,134 @@
+use anyhow::Context;
+use chrono::Utc;
+use entity::entities::{prelude::*, subscriptions, suppressions};
+use sea_orm::sea_query::{Alias, Expr, Func, OnConflict, Query, SimpleExpr};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    prelude::DateTimeWithTimeZone,
+};
+use uuid::Uuid;
+
+use crate::domain::SuppressionEntry;
+
+/// Why the provider told us to stop emailing an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuppressionReason {
+    /// The provider reported the address as permanently undeliverable.
+    HardBounce,
+    /// The recipient marked one of our emails as spam.
+    SpamComplaint,
+}
+
+impl SuppressionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuppressionReason::HardBounce => "hard_bounce",
+            SuppressionReason::SpamComplaint => "spam_complaint",
+        }
+    }
+}
+
+/// Addresses and domains that must never be emailed, whether the provider
+/// reported them or staff blocked them by hand.
+#[derive(Debug, Clone)]
+pub struct SuppressionList {
+    db_connection: DatabaseConnection,
+}
+
+impl SuppressionList {
+    pub fn new(db_connection: DatabaseConnection) -> Self {
+        Self { db_connection }
+    }
+
+    /// Whether the address, or its domain, is on the list.
+    #[tracing::instrument(name = "Check the suppression list", skip_all)]
+    pub async fn is_suppressed(&self, email: &str) -> Result<bool, sea_orm::DbErr> {
+        let email = email.trim().to_lowercase();
+        let domain = email.rsplit_once('@').map(|(_, d)| d).unwrap_or_default();
+        let entry = Suppressions::find()
+            .filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(suppressions::Column::Kind.eq("email"))
+                            .add(suppressions::Column::Entry.eq(email.as_str())),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(suppressions::Column::Kind.eq("domain"))
+                            .add(suppressions::Column::Entry.eq(domain)),
+                    ),
+            )
+            .one(&self.db_connection)
+            .await?;
+        Ok(entry.is_some())
+    }
+
+    /// Add an entry. `added_by` is the user who did so, `None` for entries
+    /// coming from the email provider.
+    /// Returns `false` if the entry was already on the list, in which case
+    /// the original record is kept.
+    #[tracing::instrument(name = "Add a suppression list entry", skip(self, entry))]
+    pub async fn add(
+        &self,
+        entry: &SuppressionEntry,
+        reason: &str,
+        added_by: Option<Uuid>,
+    ) -> Result<bool, anyhow::Error> {
+        let suppression = suppressions::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            entry: Set(entry.as_ref().to_string()),
+            kind: Set(entry.kind().to_string()),
+            reason: Set(reason.to_string()),
+            added_by: Set(added_by),
+            created_at: Set(DateTimeWithTimeZone::from(Utc::now())),
+        };
+        let result = Suppressions::insert(suppression)
+            .on_conflict(
+                OnConflict::column(suppressions::Column::Entry)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db_connection)
+            .await
+            .context("Failed to store a suppression list entry.")?;
+        Ok(matches!(result, sea_orm::TryInsertResult::Inserted(_)))
+    }
+
+    #[tracing::instrument(name = "Remove a suppression list entry", skip(self))]
+    pub async fn remove(&self, suppression_id: Uuid) -> Result<(), sea_orm::DbErr> {
+        Suppressions::delete_by_id(suppression_id)
+            .exec(&self.db_connection)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A condition on `subscriptions` matching the subscribers whose address
+/// is not suppressed, for queries that cannot afford a lookup per row.
+pub fn not_suppressed() -> Condition {
+    let email = || {
+        SimpleExpr::from(Func::lower(Expr::col((
+            Subscriptions,
+            subscriptions::Column::Email,
+        ))))
+    };
+    let entries = |kind: &str| {
+        Query::select()
+            .column(suppressions::Column::Entry)
+            .from(Suppressions)
+            .and_where(suppressions::Column::Kind.eq(kind))
+            .to_owned()
+    };
+    Condition::all()
+        .add(Expr::expr(email()).not_in_subquery(entries("email")))
+        .add(
+            Expr::expr(Func::cust(Alias::new("split_part")).args([
+                email(),
+                Expr::val("@").into(),
+                Expr::val(2).into(),
+            ]))
+            .not_in_subquery(entries("domain")),
+        )
+}
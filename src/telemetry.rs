@@ -1,12 +1,83 @@
+use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::net::IpAddr;
+use std::sync::OnceLock;
+
+use anyhow::Context;
+use axum::extract::Request;
+use axum::http::Uri;
+use axum::middleware::Next;
+use axum::response::Response;
+use hmac::{Hmac, Mac};
+use opentelemetry::logs::{AnyValue, LogRecord, Logger, LoggerProvider as _, Severity};
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry::{Key, KeyValue};
+use opentelemetry_otlp::{LogExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::logs::LoggerProvider;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, TracerProvider};
+use opentelemetry_sdk::{Resource, runtime};
+use secrecy::{ExposeSecret, SecretString};
 use tokio::task::JoinHandle;
-use tracing::Subscriber;
+use tracing::field::{Field, Visit};
 use tracing::subscriber::set_global_default;
+use tracing::{Event, Level, Span, Subscriber};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
+use tracing_opentelemetry::{OpenTelemetrySpanExt, OtelData};
 use tracing_subscriber::fmt::time::ChronoLocal;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::fmt::{self, MakeWriter};
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::{EnvFilter, Registry};
+use tracing_subscriber::layer::{Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+use crate::configuration::TelemetrySettings;
+
+/// How log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Multi-line and coloured, for a terminal.
+    Pretty,
+    /// One plain line per event.
+    Compact,
+    /// Bunyan's JSON, with `[SPAN - START]` and `[SPAN - END]` lines.
+    Bunyan,
+    /// One JSON object per event, with the spans it happened in.
+    Json,
+}
+
+/// Where log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogDestination {
+    Stdout,
+    Stderr,
+    /// Files in a directory, a new one every rotation period.
+    File,
+}
+
+impl LogDestination {
+    /// Whether lines end up in front of a person, and may be coloured.
+    pub fn is_terminal(self) -> bool {
+        match self {
+            Self::Stdout => std::io::stdout().is_terminal(),
+            Self::Stderr => std::io::stderr().is_terminal(),
+            Self::File => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
 
 // We are using `impl Subscriber` as return type to avoid having to
 /// spell out the actual type of the returned subscriber, which is
@@ -14,11 +85,17 @@ use tracing_subscriber::{EnvFilter, Registry};
 /// We need to explicitly call out that the returned subscriber is
 /// `Send` and `Sync` to make it possible to pass it to `init_subscriber`
 /// later on.
+/// `RUST_LOG`, when set, takes precedence over `env_filter`. Either can be
+/// changed later on through the returned [`LogFilter`].
+/// `ansi` colours the pretty format, for sinks that are a terminal.
 pub fn get_subscriber<Sink>(
     name: String,
     env_filter: String,
+    format: LogFormat,
+    ansi: bool,
     sink: Sink,
-) -> impl Subscriber + Send + Sync
+    otlp: Option<&OtlpExporter>,
+) -> (impl Subscriber + Send + Sync, LogFilter)
 where
     // This "weired" syntax is a higher-ranked trait bound(HRTB)
     // It basically means that Sink implements the `MakerWriter`
@@ -29,22 +106,515 @@ where
 {
     let env_filter =
         EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(env_filter));
-    let formatting_layer = BunyanFormattingLayer::new(name, sink);
-    let time_fmt_layer = fmt::Layer::default()
-        .with_target(false)
-        .with_timer(ChronoLocal::new("%Y-%m-%d %H:%M:%S".to_string()));
-    Registry::default()
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let subscriber = Registry::default()
         .with(env_filter)
-        .with(time_fmt_layer)
-        .with(JsonStorageLayer)
-        .with(formatting_layer)
+        .with(otlp.map(|otlp| otlp.layer()))
+        .with(output_layer(name, format, ansi, sink));
+    (subscriber, LogFilter(handle))
+}
+
+fn output_layer<S, Sink>(
+    name: String,
+    format: LogFormat,
+    ansi: bool,
+    sink: Sink,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    Sink: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let timer = ChronoLocal::new("%Y-%m-%d %H:%M:%S".to_string());
+    match format {
+        LogFormat::Pretty => fmt::layer()
+            .pretty()
+            .with_ansi(ansi)
+            .with_timer(timer)
+            .with_writer(sink)
+            .boxed(),
+        LogFormat::Compact => fmt::layer()
+            .compact()
+            .with_ansi(false)
+            .with_timer(timer)
+            .with_writer(sink)
+            .boxed(),
+        LogFormat::Bunyan => JsonStorageLayer
+            .and_then(BunyanFormattingLayer::new(name, sink))
+            .boxed(),
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_span_list(true)
+            .with_writer(sink)
+            .boxed(),
+    }
+}
+
+/// The writer for `destination`. Files are named after `file_prefix`
+/// and the period they cover, e.g. `zero2prod.2025-10-19.log`.
+pub fn log_writer(
+    destination: LogDestination,
+    directory: &str,
+    file_prefix: &str,
+    rotation: LogRotation,
+) -> Result<BoxMakeWriter, anyhow::Error> {
+    Ok(match destination {
+        LogDestination::Stdout => BoxMakeWriter::new(std::io::stdout),
+        LogDestination::Stderr => BoxMakeWriter::new(std::io::stderr),
+        LogDestination::File => {
+            let rotation = match rotation {
+                LogRotation::Hourly => Rotation::HOURLY,
+                LogRotation::Daily => Rotation::DAILY,
+                LogRotation::Never => Rotation::NEVER,
+            };
+            let appender = RollingFileAppender::builder()
+                .rotation(rotation)
+                .filename_prefix(file_prefix)
+                .filename_suffix("log")
+                .build(directory)
+                .with_context(|| format!("Failed to open a log file in {}.", directory))?;
+            BoxMakeWriter::new(appender)
+        }
+    })
+}
+
+static LOG_FILTER: OnceLock<LogFilter> = OnceLock::new();
+
+/// Changes what is logged while the application runs.
+#[derive(Clone)]
+pub struct LogFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter {
+    /// The filter of the global subscriber, once `init_subscriber` ran.
+    pub fn global() -> Option<&'static Self> {
+        LOG_FILTER.get()
+    }
+
+    /// The directives in use, e.g. `info,sqlx=warn`.
+    pub fn current(&self) -> Result<String, anyhow::Error> {
+        self.0
+            .with_current(|filter| filter.to_string())
+            .context("The subscriber is gone.")
+    }
+
+    /// Replace the directives. Nothing changes when they are invalid.
+    pub fn set(&self, directives: &str) -> Result<(), anyhow::Error> {
+        let filter = EnvFilter::try_new(directives)
+            .with_context(|| format!("`{}` are not valid filter directives.", directives))?;
+        self.0.reload(filter).context("The subscriber is gone.")
+    }
 }
 
 /// Register a subscriber as global default to process span data.
 /// It should only be called once!
-pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync) {
+pub fn init_subscriber(subscriber: impl Subscriber + Send + Sync, log_filter: LogFilter) {
     LogTracer::init().expect("Failed to set logger");
     set_global_default(subscriber).expect("Failed to set subscriber");
+    let _ = LOG_FILTER.set(log_filter);
+    // Read and write W3C `traceparent` headers.
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+}
+
+/// Sends spans, and the events logged while they are entered, to an
+/// OpenTelemetry collector over OTLP/HTTP.
+pub struct OtlpExporter {
+    tracer_provider: TracerProvider,
+    logger_provider: LoggerProvider,
+}
+
+impl OtlpExporter {
+    /// `None` when no collector is configured. Batches are exported from
+    /// a Tokio task, so this has to be called within the runtime.
+    pub fn new(settings: &TelemetrySettings) -> Result<Option<Self>, anyhow::Error> {
+        let Some(endpoint) = &settings.otlp_endpoint else {
+            return Ok(None);
+        };
+        let endpoint = endpoint.trim_end_matches('/');
+        let resource = Resource::new_with_defaults(
+            std::iter::once(KeyValue::new("service.name", settings.service_name.clone())).chain(
+                settings
+                    .resource_attributes
+                    .iter()
+                    .map(|(key, value)| KeyValue::new(key.clone(), value.clone())),
+            ),
+        );
+
+        let span_exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .build()
+            .context("Failed to build the OTLP span exporter.")?;
+        let tracer_provider = TracerProvider::builder()
+            .with_batch_exporter(span_exporter, runtime::Tokio)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                settings.sampling_ratio,
+            ))))
+            .with_resource(resource.clone())
+            .build();
+
+        let log_exporter = LogExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/logs", endpoint))
+            .build()
+            .context("Failed to build the OTLP log exporter.")?;
+        let logger_provider = LoggerProvider::builder()
+            .with_batch_exporter(log_exporter, runtime::Tokio)
+            .with_resource(resource)
+            .build();
+
+        Ok(Some(Self {
+            tracer_provider,
+            logger_provider,
+        }))
+    }
+
+    pub fn layer<S>(&self) -> impl Layer<S> + use<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let tracer = self.tracer_provider.tracer("zero2prod");
+        let logger = self.logger_provider.logger("zero2prod");
+        tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .and_then(LogLayer { logger })
+    }
+
+    /// Export what is still buffered. Blocks until the collector answered.
+    pub fn shutdown(&self) {
+        if let Err(e) = self.tracer_provider.shutdown() {
+            eprintln!("Failed to export the remaining spans: {}", e);
+        }
+        if let Err(e) = self.logger_provider.shutdown() {
+            eprintln!("Failed to export the remaining logs: {}", e);
+        }
+    }
+}
+
+/// Turns events into OpenTelemetry log records, tied to the trace of the
+/// span they were emitted in.
+struct LogLayer<L> {
+    logger: L,
+}
+
+impl<S, L> Layer<S> for LogLayer<L>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    L: Logger + Send + Sync + 'static,
+{
+    fn on_event(&self, event: &Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let metadata = event.metadata();
+        // What the exporter logs about itself would be exported in turn.
+        if ["opentelemetry", "hyper", "reqwest", "h2"]
+            .iter()
+            .any(|prefix| metadata.target().starts_with(prefix))
+        {
+            return;
+        }
+        let mut record = self.logger.create_log_record();
+        record.set_target(metadata.target().to_owned());
+        record.set_severity_number(severity(metadata.level()));
+        record.set_severity_text(metadata.level().as_str());
+        record.set_observed_timestamp(std::time::SystemTime::now());
+        event.record(&mut LogRecordVisitor(&mut record));
+        if let Some(span) = ctx.event_span(event)
+            && let Some(otel_data) = span.extensions().get::<OtelData>()
+            && let Some(span_id) = otel_data.builder.span_id
+        {
+            let trace_id = otel_data
+                .builder
+                .trace_id
+                .unwrap_or_else(|| otel_data.parent_cx.span().span_context().trace_id());
+            record.set_trace_context(trace_id, span_id, None);
+        }
+        self.logger.emit(record);
+    }
+}
+
+fn severity(level: &Level) -> Severity {
+    match *level {
+        Level::TRACE => Severity::Trace,
+        Level::DEBUG => Severity::Debug,
+        Level::INFO => Severity::Info,
+        Level::WARN => Severity::Warn,
+        Level::ERROR => Severity::Error,
+    }
+}
+
+/// The `message` becomes the body, other fields become attributes.
+struct LogRecordVisitor<'a, R>(&'a mut R);
+
+impl<R: LogRecord> Visit for LogRecordVisitor<'_, R> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.0.set_body(AnyValue::from(format!("{:?}", value)));
+        } else {
+            self.0
+                .add_attribute(Key::from(field.name()), format!("{:?}", value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.0.set_body(AnyValue::from(value.to_owned()));
+        } else {
+            self.0
+                .add_attribute(Key::from(field.name()), value.to_owned());
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.add_attribute(Key::from(field.name()), value);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        match i64::try_from(value) {
+            Ok(value) => self.0.add_attribute(Key::from(field.name()), value),
+            Err(_) => self
+                .0
+                .add_attribute(Key::from(field.name()), value.to_string()),
+        }
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.add_attribute(Key::from(field.name()), value);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.add_attribute(Key::from(field.name()), value);
+    }
+}
+
+/// Continue the trace of `span` in an outgoing request, with W3C
+/// `traceparent` and `tracestate` headers.
+pub fn inject_trace_context(span: &Span, headers: &mut reqwest::header::HeaderMap) {
+    let context = span.context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut HeaderInjector(headers))
+    });
+}
+
+struct HeaderInjector<'a>(&'a mut reqwest::header::HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let Ok(name) = reqwest::header::HeaderName::from_bytes(key.as_bytes())
+            && let Ok(value) = reqwest::header::HeaderValue::from_str(&value)
+        {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+/// The trace some work was queued in, stored along with the work so that
+/// the worker picking it up carries on with the same trace.
+#[derive(Debug, Default, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct TraceContext(HashMap<String, String>);
+
+impl TraceContext {
+    /// The trace of the current span, empty when it is not traced.
+    pub fn current() -> Self {
+        let mut carrier = HashMap::new();
+        let context = Span::current().context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut carrier)
+        });
+        Self(carrier)
+    }
+
+    /// Make `span` part of the captured trace, as if it was a child of the
+    /// span that queued the work.
+    pub fn attach(&self, span: &Span) {
+        if self.0.is_empty() {
+            return;
+        }
+        let context = opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.extract(&self.0)
+        });
+        span.set_parent(context);
+    }
+
+    /// `None` when there is no trace to store.
+    pub fn encode(&self) -> Option<String> {
+        if self.0.is_empty() {
+            return None;
+        }
+        serde_json::to_string(&self.0).ok()
+    }
+
+    /// Read back what `encode` stored. Anything unreadable starts a new
+    /// trace rather than failing the work.
+    pub fn decode(encoded: Option<&str>) -> Self {
+        encoded
+            .and_then(|encoded| serde_json::from_str(encoded).ok())
+            .unwrap_or_default()
+    }
+}
+
+/// How personal data is written to logs and spans.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Redaction {
+    /// As it is, for local debugging.
+    Off,
+    /// Partly hidden, e.g. `u***@example.com`, enough to tell entries
+    /// apart by eye.
+    #[default]
+    Mask,
+    /// Replaced by a keyed hash, so that the lines about one person can
+    /// be found without saying who they are.
+    Hash,
+}
+
+/// The kinds of personal data [`Redacted`] knows how to hide.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pii {
+    Email,
+    Name,
+    Token,
+    Ip,
+}
+
+pub struct Redactor {
+    redaction: Redaction,
+    key: SecretString,
+}
+
+static REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+impl Redactor {
+    pub fn new(redaction: Redaction, key: SecretString) -> Self {
+        Self { redaction, key }
+    }
+
+    /// Used by every [`Redacted`] value. Should be called once, before
+    /// anything is logged; until then values are masked.
+    pub fn init(self) {
+        if REDACTOR.set(self).is_err() {
+            tracing::warn!("The redaction of personal data was already set up");
+        }
+    }
+
+    fn global() -> &'static Self {
+        REDACTOR.get_or_init(|| Self::new(Redaction::Mask, SecretString::from(String::new())))
+    }
+
+    pub fn redact(&self, pii: Pii, value: &str) -> String {
+        match self.redaction {
+            Redaction::Off => value.to_owned(),
+            Redaction::Mask => mask(pii, value),
+            Redaction::Hash => {
+                let mut mac =
+                    Hmac::<sha2::Sha256>::new_from_slice(self.key.expose_secret().as_bytes())
+                        .expect("HMAC accepts keys of any length.");
+                mac.update(value.as_bytes());
+                let digest = mac.finalize().into_bytes();
+                format!("hash:{}", hex::encode(&digest[..8]))
+            }
+        }
+    }
+}
+
+fn mask(pii: Pii, value: &str) -> String {
+    let initial = |value: &str| value.chars().next().map(String::from).unwrap_or_default();
+    match pii {
+        Pii::Email => match value.rsplit_once('@') {
+            Some((local, domain)) => format!("{}***@{}", initial(local), domain),
+            None => "***".into(),
+        },
+        Pii::Name => format!("{}***", initial(value)),
+        Pii::Token => "***".into(),
+        // The network is kept, the host within it is not.
+        Pii::Ip => match value.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                let [a, b, c, _] = ip.octets();
+                format!("{}.{}.{}.0", a, b, c)
+            }
+            Ok(IpAddr::V6(ip)) => {
+                let [a, b, c, ..] = ip.segments();
+                format!("{:x}:{:x}:{:x}::", a, b, c)
+            }
+            Err(_) => "***".into(),
+        },
+    }
+}
+
+/// Personal data, hidden as configured when displayed, e.g. as a span
+/// field: `subscriber_email = %Redacted::email(&form.email)`.
+pub struct Redacted<T> {
+    value: T,
+    pii: Pii,
+}
+
+impl<T: std::fmt::Display> Redacted<T> {
+    pub fn new(pii: Pii, value: T) -> Self {
+        Self { value, pii }
+    }
+
+    pub fn email(value: T) -> Self {
+        Self::new(Pii::Email, value)
+    }
+
+    pub fn name(value: T) -> Self {
+        Self::new(Pii::Name, value)
+    }
+
+    pub fn token(value: T) -> Self {
+        Self::new(Pii::Token, value)
+    }
+
+    pub fn ip(value: T) -> Self {
+        Self::new(Pii::Ip, value)
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Display for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Redactor::global().redact(self.pii, &self.value.to_string()))
+    }
+}
+
+impl<T: std::fmt::Display> std::fmt::Debug for Redacted<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+/// The query string of a request, with every value taken for a token.
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => format!("{}={}", name, Redacted::token(value)),
+            None => pair.to_owned(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The request span records the query string as it is, and some carry
+/// tokens. Goes around the layer creating that span, with
+/// [`restore_query`] inside of it.
+pub async fn redact_query_for_span(mut request: Request, next: Next) -> Response {
+    if let Some(query) = request.uri().query() {
+        let redacted = format!("{}?{}", request.uri().path(), redact_query(query));
+        if let Ok(redacted) = redacted.parse::<Uri>() {
+            let uri = std::mem::replace(request.uri_mut(), redacted);
+            request.extensions_mut().insert(UnredactedUri(uri));
+        }
+    }
+    next.run(request).await
+}
+
+#[derive(Clone)]
+struct UnredactedUri(Uri);
+
+/// Hand the handlers the query string [`redact_query_for_span`] hid.
+pub async fn restore_query(mut request: Request, next: Next) -> Response {
+    if let Some(UnredactedUri(uri)) = request.extensions_mut().remove() {
+        *request.uri_mut() = uri;
+    }
+    next.run(request).await
 }
 
 pub fn spawn_blocking_with_tracing<F, R>(f: F) -> JoinHandle<R>
@@ -55,3 +625,54 @@ where
     let current_span = tracing::Span::current();
     tokio::task::spawn_blocking(move || current_span.in_scope(f))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Pii, Redaction, Redactor, mask, redact_query};
+    use secrecy::SecretString;
+
+    fn redactor(redaction: Redaction) -> Redactor {
+        Redactor::new(redaction, SecretString::from("key".to_string()))
+    }
+
+    #[test]
+    fn masking_keeps_enough_to_tell_entries_apart() {
+        assert_eq!(mask(Pii::Email, "ursula@example.com"), "u***@example.com");
+        assert_eq!(mask(Pii::Name, "Ursula"), "U***");
+        assert_eq!(mask(Pii::Token, "abc123"), "***");
+        assert_eq!(mask(Pii::Ip, "203.0.113.42"), "203.0.113.0");
+        assert_eq!(
+            mask(Pii::Ip, "2001:db8:85a3::8a2e:370:7334"),
+            "2001:db8:85a3::"
+        );
+        assert_eq!(mask(Pii::Email, "not-an-email"), "***");
+    }
+
+    #[test]
+    fn hashes_are_stable_and_keyed() {
+        let hash = redactor(Redaction::Hash).redact(Pii::Email, "ursula@example.com");
+        assert!(!hash.contains("ursula"));
+        assert_eq!(
+            hash,
+            redactor(Redaction::Hash).redact(Pii::Email, "ursula@example.com")
+        );
+        let other_key = Redactor::new(Redaction::Hash, SecretString::from("other".to_string()));
+        assert_ne!(hash, other_key.redact(Pii::Email, "ursula@example.com"));
+    }
+
+    #[test]
+    fn nothing_is_hidden_when_redaction_is_off() {
+        assert_eq!(
+            redactor(Redaction::Off).redact(Pii::Email, "ursula@example.com"),
+            "ursula@example.com"
+        );
+    }
+
+    #[test]
+    fn query_values_are_hidden_but_not_their_names() {
+        assert_eq!(
+            redact_query("subscription_token=abc123&flag"),
+            "subscription_token=***&flag"
+        );
+    }
+}
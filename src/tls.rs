@@ -0,0 +1,284 @@
+//! Serving HTTPS directly, for deployments without a reverse proxy to
+//! terminate TLS.
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use axum::{
+    Router,
+    http::{HeaderMap, StatusCode, Uri, header, uri::Authority},
+    response::{IntoResponse, Redirect, Response},
+    serve::Listener,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::TlsAcceptor;
+use tokio_rustls::rustls::{
+    ServerConfig,
+    crypto::ring,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::{ClientHello, ResolvesServerCert},
+    sign::CertifiedKey,
+};
+use tokio_rustls::server::TlsStream;
+use tokio_util::sync::CancellationToken;
+
+use crate::configuration::TlsSettings;
+use crate::telemetry::Redacted;
+
+/// A client that has not finished its handshake by then is dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The certificate being served, which can be swapped while connections
+/// are accepted.
+#[derive(Debug)]
+pub struct CertificateStore {
+    certificate_path: PathBuf,
+    private_key_path: PathBuf,
+    reload_interval: Duration,
+    current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl CertificateStore {
+    pub fn load(settings: &TlsSettings) -> Result<Self, anyhow::Error> {
+        let certificate_path = PathBuf::from(&settings.certificate_path);
+        let private_key_path = PathBuf::from(&settings.private_key_path);
+        let certified_key = read_certified_key(&certificate_path, &private_key_path)?;
+        Ok(Self {
+            certificate_path,
+            private_key_path,
+            reload_interval: settings.reload_interval(),
+            current: RwLock::new(Arc::new(certified_key)),
+        })
+    }
+
+    /// Read the files again. The current certificate is kept when they
+    /// are not valid, e.g. when the key was not written yet.
+    pub fn reload(&self) -> Result<(), anyhow::Error> {
+        let certified_key = read_certified_key(&self.certificate_path, &self.private_key_path)?;
+        *self.current.write().unwrap() = Arc::new(certified_key);
+        Ok(())
+    }
+
+    pub fn acceptor(self: &Arc<Self>) -> TlsAcceptor {
+        let mut config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .expect("The ring provider supports the default protocol versions.")
+            .with_no_client_auth()
+            .with_cert_resolver(self.clone());
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        TlsAcceptor::from(Arc::new(config))
+    }
+
+    fn modified(&self) -> Option<(SystemTime, SystemTime)> {
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        Some((
+            modified(&self.certificate_path)?,
+            modified(&self.private_key_path)?,
+        ))
+    }
+}
+
+impl ResolvesServerCert for CertificateStore {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().clone())
+    }
+}
+
+fn read_certified_key(
+    certificate_path: &Path,
+    private_key_path: &Path,
+) -> Result<CertifiedKey, anyhow::Error> {
+    let chain = CertificateDer::pem_file_iter(certificate_path)
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read {}.", certificate_path.display()))?;
+    anyhow::ensure!(
+        !chain.is_empty(),
+        "No certificate in {}.",
+        certificate_path.display()
+    );
+    let private_key = PrivateKeyDer::from_pem_file(private_key_path)
+        .with_context(|| format!("Failed to read {}.", private_key_path.display()))?;
+    let signing_key =
+        ring::sign::any_supported_type(&private_key).context("Unsupported private key.")?;
+    let certified_key = CertifiedKey::new(chain, signing_key);
+    certified_key
+        .keys_match()
+        .context("The private key does not match the certificate.")?;
+    Ok(certified_key)
+}
+
+/// Reload the certificate when its files change, or on SIGHUP, until
+/// `shutdown` is cancelled.
+pub async fn watch_certificate(store: Arc<CertificateStore>, shutdown: CancellationToken) {
+    #[cfg(unix)]
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => Some(hangup),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to listen for SIGHUP");
+            None
+        }
+    };
+    let mut modified = store.modified();
+    loop {
+        #[cfg(unix)]
+        let sighup = async {
+            match hangup.as_mut() {
+                Some(hangup) => hangup.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        #[cfg(not(unix))]
+        let sighup = std::future::pending::<()>();
+
+        let trigger = tokio::select! {
+            _ = shutdown.cancelled() => return,
+            _ = sighup => "SIGHUP",
+            _ = tokio::time::sleep(store.reload_interval) => {
+                let now = store.modified();
+                if now == modified {
+                    continue;
+                }
+                modified = now;
+                "files changed"
+            }
+        };
+        match store.reload() {
+            Ok(()) => tracing::info!(trigger, "Reloaded the TLS certificate"),
+            Err(e) => tracing::error!(
+                error.cause_chain = ?e,
+                error.message = %e,
+                trigger,
+                "Failed to reload the TLS certificate, the previous one is still served",
+            ),
+        }
+    }
+}
+
+/// Accepts TCP connections and runs their TLS handshake off the accept
+/// loop, so that a slow client does not hold up the others.
+pub struct TlsListener {
+    connections: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, acceptor: TlsAcceptor) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let (sender, connections) = mpsc::channel(64);
+        tokio::spawn(accept_connections(listener, acceptor, sender));
+        Ok(Self {
+            connections,
+            local_addr,
+        })
+    }
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    sender: mpsc::Sender<(TlsStream<TcpStream>, SocketAddr)>,
+) {
+    loop {
+        // The server dropping the `TlsListener` closes the socket.
+        let accepted = tokio::select! {
+            _ = sender.closed() => return,
+            accepted = listener.accept() => accepted,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                // Most likely out of file descriptors, back off for a moment.
+                tracing::warn!(error = %e, "Failed to accept a connection");
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => {
+                    let _ = sender.send((stream, peer)).await;
+                }
+                Ok(Err(e)) => tracing::debug!(
+                    error = %e,
+                    peer = %Redacted::ip(peer.ip()),
+                    "TLS handshake failed"
+                ),
+                Err(_) => {
+                    tracing::debug!(peer = %Redacted::ip(peer.ip()), "TLS handshake timed out")
+                }
+            }
+        });
+    }
+}
+
+impl Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.connections.recv().await {
+            Some(connection) => connection,
+            // Only once the accept loop is gone, which it never is first.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+/// Send every plain HTTP request to the same URL over HTTPS, on
+/// `https_port`.
+pub fn redirect_to_https(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect(&headers, &uri, https_port)
+    })
+}
+
+fn redirect(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok());
+    match host {
+        Some(host) => Redirect::permanent(&https_url(host.host(), https_port, uri)).into_response(),
+        None => (StatusCode::BAD_REQUEST, "Missing Host header").into_response(),
+    }
+}
+
+fn https_url(host: &str, https_port: u16, uri: &Uri) -> String {
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    if https_port == 443 {
+        format!("https://{host}{path}")
+    } else {
+        format!("https://{host}:{https_port}{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::https_url;
+    use axum::http::Uri;
+
+    #[test]
+    fn the_path_and_query_are_kept() {
+        let uri: Uri = "/subscriptions/confirm?token=abc".parse().unwrap();
+        assert_eq!(
+            https_url("example.com", 8443, &uri),
+            "https://example.com:8443/subscriptions/confirm?token=abc"
+        );
+    }
+
+    #[test]
+    fn the_default_port_is_left_out() {
+        let uri: Uri = "/".parse().unwrap();
+        assert_eq!(https_url("example.com", 443, &uri), "https://example.com/");
+    }
+}
@@ -9,6 +9,15 @@ where
     StatusCode::INTERNAL_SERVER_ERROR.into_response()
 }
 
+/// Compare secrets without leaking, through timing, how much of them matched.
+pub fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (x, y)| difference | (x ^ y))
+            == 0
+}
+
 // Return a 400 with the user-representation of the validation error as body.
 // The error root cause is preserved for logging purposes.
 pub fn e400<T>(e: T) -> Response
@@ -0,0 +1,244 @@
+use entity::entities::{newsletter_issues, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+/// Publish an issue right away and return it.
+async fn publish_issue(app: &TestApp, title: &str) -> newsletter_issues::Model {
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": title,
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    NewsletterIssues::find()
+        .filter(newsletter_issues::Column::Title.eq(title))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .expect("The issue was not stored.")
+}
+
+async fn get(app: &TestApp, path: &str) -> reqwest::Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, path))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn hide(app: &TestApp, issue_id: uuid::Uuid) -> reqwest::Response {
+    app.api_client
+        .post(format!(
+            "{}/admin/newsletters/published/{}/hide",
+            &app.address, issue_id
+        ))
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn arrange() -> TestApp {
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app
+}
+
+#[tokio::test]
+async fn published_issues_are_listed_in_the_archive() {
+    // Arrange
+    let app = arrange().await;
+
+    // Act
+    let issue = publish_issue(&app, "Hello, World!").await;
+
+    // Assert
+    assert_eq!(issue.slug.as_deref(), Some("hello-world"));
+    let html_page = get(&app, "/archive").await.text().await.unwrap();
+    assert!(html_page.contains(r#"<a href="/archive/hello-world">Hello, World!</a>"#));
+    let response = get(&app, "/archive/hello-world").await;
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        response
+            .text()
+            .await
+            .unwrap()
+            .contains("<p>Newsletter body as HTML</p>")
+    );
+}
+
+#[tokio::test]
+async fn issues_sharing_a_title_get_distinct_slugs() {
+    // Arrange
+    let app = arrange().await;
+    let first = publish_issue(&app, "Weekly").await;
+    // The helper looks issues up by title.
+    NewsletterIssues::update_many()
+        .col_expr(
+            newsletter_issues::Column::Title,
+            sea_orm::sea_query::Expr::value("Weekly (old)"),
+        )
+        .filter(newsletter_issues::Column::Id.eq(first.id))
+        .exec(&app.db_connection)
+        .await
+        .unwrap();
+
+    // Act
+    let second = publish_issue(&app, "Weekly").await;
+
+    // Assert
+    assert_eq!(first.slug.as_deref(), Some("weekly"));
+    assert_ne!(second.slug, first.slug);
+    assert!(second.slug.unwrap().starts_with("weekly-"));
+}
+
+#[tokio::test]
+async fn hidden_issues_are_not_in_the_archive_or_the_feeds() {
+    // Arrange
+    let app = arrange().await;
+    let issue = publish_issue(&app, "Oops").await;
+
+    // Act
+    let response = hide(&app, issue.id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/published");
+    let html_page = get(&app, "/archive").await.text().await.unwrap();
+    assert!(!html_page.contains("Oops"));
+    assert_eq!(get(&app, "/archive/oops").await.status().as_u16(), 404);
+    assert!(
+        !get(&app, "/feed.rss")
+            .await
+            .text()
+            .await
+            .unwrap()
+            .contains("Oops")
+    );
+    assert!(
+        !get(&app, "/feed.atom")
+            .await
+            .text()
+            .await
+            .unwrap()
+            .contains("Oops")
+    );
+}
+
+#[tokio::test]
+async fn drafts_cannot_be_hidden() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_drafts(&serde_json::json!({
+        "title": "Work in progress",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    }))
+    .await;
+    let draft = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let response = hide(&app, draft.id).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/published");
+    let html_page = app.get_published_newsletters_html().await;
+    assert!(html_page.contains("The issue is not published."));
+    let draft = NewsletterIssues::find_by_id(draft.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!draft.hidden);
+}
+
+#[tokio::test]
+async fn hiding_an_unknown_issue_is_reported() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = hide(&app, uuid::Uuid::new_v4()).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/published");
+    let html_page = app.get_published_newsletters_html().await;
+    assert!(html_page.contains("The issue is not published."));
+    assert!(!html_page.contains("The issue has been hidden from the archive."));
+}
+
+#[tokio::test]
+async fn drafts_are_not_in_the_archive() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    app.post_drafts(&serde_json::json!({
+        "title": "Work in progress",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+    }))
+    .await;
+
+    // Assert
+    let html_page = get(&app, "/archive").await.text().await.unwrap();
+    assert!(!html_page.contains("Work in progress"));
+}
+
+#[tokio::test]
+async fn the_rss_feed_lists_published_issues() {
+    // Arrange
+    let app = arrange().await;
+    publish_issue(&app, "Fish & Chips").await;
+
+    // Act
+    let response = get(&app, "/feed.rss").await;
+
+    // Assert
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "application/rss+xml; charset=utf-8"
+    );
+    let xml = response.text().await.unwrap();
+    assert!(xml.contains("<title>Fish &amp; Chips</title>"));
+    assert!(xml.contains("/archive/fish-chips</link>"));
+    assert!(xml.contains("&lt;p&gt;Newsletter body as HTML&lt;/p&gt;"));
+}
+
+#[tokio::test]
+async fn the_atom_feed_lists_published_issues() {
+    // Arrange
+    let app = arrange().await;
+    let issue = publish_issue(&app, "Fish & Chips").await;
+
+    // Act
+    let response = get(&app, "/feed.atom").await;
+
+    // Assert
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "application/atom+xml; charset=utf-8"
+    );
+    let xml = response.text().await.unwrap();
+    assert!(xml.contains(&format!("<id>urn:uuid:{}</id>", issue.id)));
+    assert!(xml.contains("<title>Fish &amp; Chips</title>"));
+}
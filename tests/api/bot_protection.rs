@@ -0,0 +1,234 @@
+use entity::entities::prelude::*;
+use sea_orm::{EntityTrait, PaginatorTrait};
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, spawn_app_with};
+
+async fn spawn_protected_app(
+    configure: impl FnOnce(&mut zero2prod::configuration::Settings),
+) -> TestApp {
+    spawn_app_with(|config| {
+        config.bot_protection.enabled = true;
+        config.bot_protection.min_fill_seconds = 0;
+        configure(config);
+    })
+    .await
+}
+
+async fn mock_email_api(app: &TestApp) {
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+}
+
+fn form(email: &str, form_token: &str) -> String {
+    format!(
+        "name=le%20guin&email={}&website=&form_token={}",
+        urlencoding::encode(email),
+        form_token
+    )
+}
+
+async fn sent_emails(app: &TestApp) -> usize {
+    app.email_server.received_requests().await.unwrap().len()
+}
+
+#[tokio::test]
+async fn the_form_served_on_the_home_page_is_accepted() {
+    // Arrange
+    let app = spawn_protected_app(|_| {}).await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let response = app
+        .post_subscriptions(form("ursula_le_guin@gmail.com", &form_token))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(sent_emails(&app).await, 1);
+}
+
+#[tokio::test]
+async fn forms_without_a_valid_token_are_rejected() {
+    // Arrange
+    let app = spawn_protected_app(|_| {}).await;
+    mock_email_api(&app).await;
+
+    for body in [
+        "name=le%20guin&email=ursula_le_guin%40gmail.com".to_string(),
+        form("ursula_le_guin@gmail.com", "1700000000.deadbeef"),
+    ] {
+        // Act
+        let response = app.post_subscriptions(body).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400);
+    }
+    assert_eq!(sent_emails(&app).await, 0);
+}
+
+#[tokio::test]
+async fn forms_submitted_too_quickly_are_rejected() {
+    // Arrange
+    let app = spawn_protected_app(|config| config.bot_protection.min_fill_seconds = 60).await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let response = app
+        .post_subscriptions(form("ursula_le_guin@gmail.com", &form_token))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+    assert_eq!(sent_emails(&app).await, 0);
+}
+
+#[tokio::test]
+async fn a_filled_in_honeypot_is_silently_ignored() {
+    // Arrange
+    let app = spawn_protected_app(|_| {}).await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+    let body = form("ursula_le_guin@gmail.com", &form_token)
+        .replace("website=", "website=https%3A%2F%2Fspam.example");
+
+    // Act
+    let response = app.post_subscriptions(body).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(sent_emails(&app).await, 0);
+    let subscribers = Subscriptions::find()
+        .count(&app.db_connection)
+        .await
+        .unwrap();
+    assert_eq!(subscribers, 0);
+}
+
+#[tokio::test]
+async fn the_same_address_cannot_be_sent_confirmations_over_and_over() {
+    // Arrange
+    let app = spawn_protected_app(|config| config.bot_protection.per_email.max_requests = 1).await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let first = app
+        .post_subscriptions(form("ursula_le_guin@gmail.com", &form_token))
+        .await;
+    let second = app
+        .post_subscriptions(form("Ursula_Le_Guin@gmail.com", &form_token))
+        .await;
+
+    // Assert
+    assert_eq!(first.status().as_u16(), 200);
+    assert_eq!(second.status().as_u16(), 429);
+    assert_eq!(sent_emails(&app).await, 1);
+}
+
+#[tokio::test]
+async fn a_single_client_cannot_subscribe_many_addresses() {
+    // Arrange
+    let app = spawn_protected_app(|config| config.bot_protection.per_ip.max_requests = 2).await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let mut statuses = Vec::new();
+    for email in ["a@gmail.com", "b@gmail.com", "c@gmail.com"] {
+        let response = app.post_subscriptions(form(email, &form_token)).await;
+        statuses.push(response.status().as_u16());
+    }
+
+    // Assert
+    assert_eq!(statuses, [200, 200, 429]);
+    assert_eq!(sent_emails(&app).await, 2);
+}
+
+#[tokio::test]
+async fn the_client_address_is_taken_from_the_configured_proxy_header() {
+    // Arrange
+    let app = spawn_protected_app(|config| {
+        config.bot_protection.per_ip.max_requests = 1;
+        config.bot_protection.client_ip_header = Some("X-Forwarded-For".into());
+    })
+    .await;
+    mock_email_api(&app).await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let mut statuses = Vec::new();
+    for (email, client_ip) in [
+        ("a@gmail.com", "203.0.113.1"),
+        ("b@gmail.com", "203.0.113.2"),
+        ("c@gmail.com", "203.0.113.2"),
+    ] {
+        let response = app
+            .api_client
+            .post(format!("{}/subscriptions", &app.address))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .header("X-Forwarded-For", client_ip)
+            .body(form(email, &form_token))
+            .send()
+            .await
+            .unwrap();
+        statuses.push(response.status().as_u16());
+    }
+
+    // Assert
+    assert_eq!(statuses, [200, 200, 429]);
+}
+
+#[tokio::test]
+async fn the_challenge_is_verified_with_the_provider() {
+    // Arrange
+    let challenge_server = wiremock::MockServer::start().await;
+    let verify_url = format!("{}/siteverify", challenge_server.uri());
+    let app = spawn_protected_app(move |config| {
+        config.bot_protection.challenge = Some(zero2prod::configuration::ChallengeSettings {
+            verify_url,
+            secret_key: "challenge-secret".to_string().into(),
+        });
+    })
+    .await;
+    mock_email_api(&app).await;
+    Mock::given(path("/siteverify"))
+        .and(body_string_contains("secret=challenge-secret"))
+        .and(body_string_contains("response=human"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": true})),
+        )
+        .mount(&challenge_server)
+        .await;
+    Mock::given(path("/siteverify"))
+        .and(body_string_contains("response=robot"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({"success": false})),
+        )
+        .mount(&challenge_server)
+        .await;
+    let form_token = app.get_form_token().await;
+
+    // Act
+    let human = app
+        .post_subscriptions(form("a@gmail.com", &form_token) + "&challenge_response=human")
+        .await;
+    let robot = app
+        .post_subscriptions(form("b@gmail.com", &form_token) + "&challenge_response=robot")
+        .await;
+    let missing = app
+        .post_subscriptions(form("c@gmail.com", &form_token))
+        .await;
+
+    // Assert
+    assert_eq!(human.status().as_u16(), 200);
+    assert_eq!(robot.status().as_u16(), 400);
+    assert_eq!(missing.status().as_u16(), 400);
+    assert_eq!(sent_emails(&app).await, 1);
+}
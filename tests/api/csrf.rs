@@ -0,0 +1,109 @@
+use crate::helpers::{assert_is_redirect_to, spawn_app};
+
+#[tokio::test]
+async fn forms_carry_the_csrf_token() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.csrf_token().await;
+
+    // Act
+    let login_html = app.get_login_html().await;
+    app.test_user.login(&app).await;
+    let dashboard_html = app.get_admin_dashboard_html().await;
+
+    // Assert
+    assert!(login_html.contains(&format!(r#"name="csrf_token" value="{}""#, token)));
+    assert!(dashboard_html.contains(r#"<input type="hidden" name="csrf_token""#));
+}
+
+#[tokio::test]
+async fn a_login_without_csrf_token_is_rejected_with_a_403() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/login", &app.address))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert!(response.text().await.unwrap().contains("Request rejected"));
+}
+
+#[tokio::test]
+async fn the_token_can_be_sent_as_a_form_field() {
+    // Arrange
+    let app = spawn_app().await;
+    let token = app.csrf_token().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/login", &app.address))
+        .form(&serde_json::json!({
+            "username": &app.test_user.username,
+            "password": &app.test_user.password,
+            "csrf_token": token,
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/dashboard");
+}
+
+#[tokio::test]
+async fn admin_forms_without_a_valid_csrf_token_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    for token in [None, Some("not-the-session-token")] {
+        // Act
+        let mut request = app
+            .api_client
+            .post(format!("{}/admin/password", &app.address))
+            .form(&serde_json::json!({
+                "current_password": &app.test_user.password,
+                "new_password": "a-new-long-enough-password",
+                "new_password_check": "a-new-long-enough-password",
+            }));
+        if let Some(token) = token {
+            request = request.header("X-CSRF-Token", token);
+        }
+        let response = request.send().await.unwrap();
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 403);
+    }
+}
+
+#[tokio::test]
+async fn the_token_changes_when_logging_in() {
+    // Arrange
+    let app = spawn_app().await;
+    let anonymous_token = app.csrf_token().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/logout", &app.address))
+        .header("X-CSRF-Token", &anonymous_token)
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 403);
+    assert_ne!(app.csrf_token().await, anonymous_token);
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+use sea_orm::ConnectionTrait;
+use zero2prod::configuration::get_configuration;
+use zero2prod::startup::{Application, get_db_connection};
+
+use crate::helpers::spawn_app_with;
+
+#[tokio::test]
+async fn startup_gives_up_after_the_configured_attempts() {
+    // Arrange
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    // Nothing listens there.
+    configuration.database.port = 1;
+    configuration.database.connect_attempts = 3;
+    configuration.database.connect_backoff_milliseconds = 100;
+    configuration.database.acquire_timeout_milliseconds = 200;
+    configuration.application.port = 0;
+
+    // Act
+    let start = Instant::now();
+    let outcome = Application::build(configuration).await;
+
+    // Assert
+    let error = outcome.err().expect("Startup did not fail.");
+    assert!(error.to_string().contains("after 3 attempts"));
+    // 100ms, then 200ms.
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+#[tokio::test]
+async fn statements_running_past_the_timeout_are_cancelled() {
+    // Arrange
+    let app =
+        spawn_app_with(|config| config.database.statement_timeout_milliseconds = Some(100)).await;
+
+    // Act
+    let outcome = app
+        .db_connection
+        .execute_unprepared("SELECT pg_sleep(1)")
+        .await;
+
+    // Assert
+    let error = outcome.expect_err("The statement was not cancelled.");
+    assert!(error.to_string().contains("statement timeout"));
+}
+
+#[tokio::test]
+async fn the_pool_is_sized_from_the_settings() {
+    // Arrange
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.database.max_connections = 3;
+    configuration.database.min_connections = 1;
+
+    // Act
+    let db_connection = get_db_connection(&configuration.database);
+
+    // Assert
+    let options = db_connection.get_postgres_connection_pool().options();
+    assert_eq!(options.get_max_connections(), 3);
+    assert_eq!(options.get_min_connections(), 1);
+}
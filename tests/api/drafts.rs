@@ -0,0 +1,235 @@
+use entity::entities::{newsletter_issues, prelude::*};
+use sea_orm::EntityTrait;
+use wiremock::matchers::{any, body_partial_json, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+/// Save a draft and return it.
+async fn create_draft(app: &TestApp) -> newsletter_issues::Model {
+    let response = app
+        .post_drafts(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+        }))
+        .await;
+    let draft = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .expect("The draft was not stored.");
+    assert_is_redirect_to(
+        &response,
+        &format!("/admin/newsletters/drafts/{}", draft.id),
+    );
+    draft
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_see_the_drafts() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .get(format!("{}/admin/newsletters/drafts", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn drafts_are_stored_and_not_sent() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let draft = create_draft(&app).await;
+
+    // Assert
+    assert_eq!(draft.state, "draft");
+    let html_page = app.get_drafts_html().await;
+    assert!(html_page.contains("Newsletter title"));
+}
+
+#[tokio::test]
+async fn drafts_can_be_edited() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft = create_draft(&app).await;
+
+    // Act
+    let response = app
+        .post_draft(
+            draft.id,
+            &serde_json::json!({
+                "title": "A better title",
+                "text_content": "Newsletter body as plain text",
+                "html_content": "<p>Newsletter body as HTML</p>",
+            }),
+        )
+        .await;
+
+    // Assert
+    assert_is_redirect_to(
+        &response,
+        &format!("/admin/newsletters/drafts/{}", draft.id),
+    );
+    let html_page = app.get_draft_html(draft.id).await;
+    assert!(html_page.contains("The draft has been saved."));
+    assert!(html_page.contains("A better title"));
+}
+
+#[tokio::test]
+async fn markdown_drafts_are_edited_in_markdown() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    app.post_drafts(&serde_json::json!({
+        "title": "Newsletter title",
+        "markdown_content": "Hello *world*",
+    }))
+    .await;
+    let draft = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let html_page = app.get_draft_html(draft.id).await;
+    assert!(html_page.contains(r#"<textarea name="markdown_content">Hello *world*</textarea>"#));
+    assert!(html_page.contains(r#"<textarea name="html_content"></textarea>"#));
+    assert!(html_page.contains(r#"<textarea name="text_content"></textarea>"#));
+
+    // Act
+    app.post_draft(
+        draft.id,
+        &serde_json::json!({
+            "title": "Newsletter title",
+            "markdown_content": "Goodbye *world*",
+            "html_content": "",
+            "text_content": "",
+        }),
+    )
+    .await;
+
+    // Assert
+    let draft = NewsletterIssues::find_by_id(draft.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(draft.markdown_content.as_deref(), Some("Goodbye *world*"));
+    assert_eq!(draft.html_content, "<p>Goodbye <em>world</em></p>\n");
+    assert_eq!(draft.text_content, "Goodbye world");
+}
+
+#[tokio::test]
+async fn the_preview_shows_both_bodies() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft = create_draft(&app).await;
+
+    // Act
+    let html_page = app.get_draft_preview_html(draft.id).await;
+
+    // Assert
+    assert!(html_page.contains("Newsletter body as plain text"));
+    // The HTML body is escaped into the preview frame.
+    assert!(html_page.contains("&lt;p&gt;Newsletter body as HTML&lt;/p&gt;"));
+}
+
+#[tokio::test]
+async fn test_sends_only_go_to_the_logged_in_admin() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let draft = create_draft(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .and(body_partial_json(serde_json::json!({
+            "To": app.test_user.email,
+            "Subject": "[TEST] Newsletter title",
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_draft_test(draft.id).await;
+
+    // Assert
+    assert_is_redirect_to(
+        &response,
+        &format!("/admin/newsletters/drafts/{}", draft.id),
+    );
+    let html_page = app.get_draft_html(draft.id).await;
+    assert!(html_page.contains("A test email has been sent to"));
+    let draft = NewsletterIssues::find_by_id(draft.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(draft.state, "draft");
+}
+
+#[tokio::test]
+async fn publishing_a_draft_delivers_it() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let draft = create_draft(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_publish_draft(draft.id, &serde_json::json!({}))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/drafts");
+    let issue = NewsletterIssues::find_by_id(draft.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "published");
+
+    // A published issue can no longer be edited.
+    let response = app
+        .post_draft(
+            draft.id,
+            &serde_json::json!({
+                "title": "Too late",
+                "text_content": "Newsletter body as plain text",
+                "html_content": "<p>Newsletter body as HTML</p>",
+            }),
+        )
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters/drafts");
+}
@@ -1,4 +1,8 @@
-use crate::helpers::spawn_app;
+use std::time::Duration;
+use wiremock::matchers::method;
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{spawn_app, spawn_app_with};
 
 // `tokio::test` is the testing equivalent of `tokio::main`.
 // Is also spares you from having to specify the `#[test]` attribute.
@@ -25,3 +29,88 @@ async fn health_check_works() {
     assert!(response.status().is_success());
     assert_eq!(Some(0), response.content_length());
 }
+
+#[tokio::test]
+async fn the_liveness_probe_always_answers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/live", &app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "up");
+}
+
+#[tokio::test]
+async fn the_readiness_probe_reports_each_dependency() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "up");
+    for component in ["postgres", "redis"] {
+        assert_eq!(body["components"][component]["status"], "up");
+        assert!(body["components"][component]["latency_ms"].is_u64());
+    }
+    // Not checked unless asked for.
+    assert!(body["components"].get("email_provider").is_none());
+}
+
+#[tokio::test]
+async fn the_email_provider_can_be_part_of_readiness() {
+    // Arrange
+    let app = spawn_app_with(|config| config.health.check_email_provider = true).await;
+    Mock::given(method("HEAD"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["components"]["email_provider"]["status"], "up");
+}
+
+#[tokio::test]
+async fn a_dependency_that_does_not_answer_in_time_makes_the_instance_unready() {
+    // Arrange
+    let app = spawn_app_with(|config| {
+        config.health.check_email_provider = true;
+        config.health.timeout_milliseconds = 200;
+    })
+    .await;
+    Mock::given(method("HEAD"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 503);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "down");
+    assert_eq!(body["components"]["postgres"]["status"], "up");
+    assert_eq!(body["components"]["email_provider"]["status"], "down");
+    assert_eq!(body["components"]["email_provider"]["error"], "timeout");
+}
@@ -1,17 +1,27 @@
 use argon2::password_hash::SaltString;
 use argon2::{Algorithm, Argon2, Params, PasswordHasher};
 use entity::entities::users;
+use fake::Fake;
+use fake::faker::internet::en::SafeEmail;
 use migration::{Migrator, MigratorTrait};
 use sea_orm::sqlx::Executor;
 use sea_orm::sqlx::postgres::PgPoolOptions;
 use sea_orm::{ActiveModelTrait, DatabaseConnection, Set, SqlxPostgresConnector};
-use std::sync::LazyLock;
+use secrecy::{ExposeSecret, SecretString};
+use std::path::PathBuf;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::fmt::MakeWriter;
 use uuid::Uuid;
 use wiremock::MockServer;
-use zero2prod::configuration::{DatabaseSettings, get_configuration};
-use zero2prod::startup::Application;
+use zero2prod::configuration::{DatabaseSettings, Settings, TlsSettings, get_configuration};
+use zero2prod::email_client::EmailClient;
+use zero2prod::issue_links::IssueLinks;
 use zero2prod::startup::get_db_connection;
-use zero2prod::telemetry::{get_subscriber, init_subscriber};
+use zero2prod::startup::{Application, HmacSecret};
+use zero2prod::telemetry::{LogFormat, get_subscriber, init_subscriber};
 
 static TRACING: LazyLock<()> = LazyLock::new(|| {
     let subscriber_name = "test".to_string();
@@ -22,11 +32,25 @@ static TRACING: LazyLock<()> = LazyLock::new(|| {
     // `get_subscriber`, therefore they are not the same type. We could work around
     // it, but this is the most straight-forward way of moving forward.
     if std::env::var("TEST_LOG").is_ok() {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::stdout);
-        init_subscriber(subscriber);
+        let (subscriber, log_filter) = get_subscriber(
+            subscriber_name,
+            default_filter_level,
+            LogFormat::Compact,
+            false,
+            std::io::stdout,
+            None,
+        );
+        init_subscriber(subscriber, log_filter);
     } else {
-        let subscriber = get_subscriber(subscriber_name, default_filter_level, std::io::sink);
-        init_subscriber(subscriber);
+        let (subscriber, log_filter) = get_subscriber(
+            subscriber_name,
+            default_filter_level,
+            LogFormat::Bunyan,
+            false,
+            std::io::sink,
+            None,
+        );
+        init_subscriber(subscriber, log_filter);
     }
 });
 
@@ -38,6 +62,13 @@ pub struct TestApp {
     pub email_server: MockServer,
     pub test_user: TestUser,
     pub api_client: reqwest::Client,
+    pub email_client: EmailClient,
+    pub issue_links: IssueLinks,
+    pub webhook_secret: SecretString,
+    pub metrics_token: SecretString,
+    pub shutdown: CancellationToken,
+    pub server: JoinHandle<Result<(), std::io::Error>>,
+    pub redirect_port: Option<u16>,
 }
 
 /// Confirmation links embedded in the request to the email API
@@ -51,6 +82,7 @@ impl TestApp {
     pub async fn post_logout(&self) -> reqwest::Response {
         self.api_client
             .post(&format!("{}/admin/logout", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .send()
             .await
             .expect("Failed to execute request.")
@@ -74,6 +106,7 @@ impl TestApp {
     {
         self.api_client
             .post(&format!("{}/admin/password", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .form(body)
             .send()
             .await
@@ -112,6 +145,7 @@ impl TestApp {
     {
         self.api_client
             .post(&format!("{}/login", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .form(body)
             .send()
             .await
@@ -136,11 +170,241 @@ impl TestApp {
     {
         self.api_client
             .post(&format!("{}/admin/newsletters", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
             .form(body)
             .send()
             .await
             .expect("Failed to execute request.")
     }
+    pub async fn get_published_newsletters_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/newsletters/published", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn get_scheduled_newsletters_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/newsletters/scheduled", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_cancel_newsletter(&self, issue_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletters/scheduled/{}/cancel",
+                &self.address, issue_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_reschedule_newsletter(
+        &self,
+        issue_id: Uuid,
+        send_at: &str,
+    ) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletters/scheduled/{}/reschedule",
+                &self.address, issue_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(&[("send_at", send_at)])
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_drafts_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/newsletters/drafts", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_drafts<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/newsletters/drafts", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_draft_html(&self, draft_id: Uuid) -> String {
+        self.api_client
+            .get(format!(
+                "{}/admin/newsletters/drafts/{}",
+                &self.address, draft_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_draft<Body>(&self, draft_id: Uuid, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletters/drafts/{}",
+                &self.address, draft_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_draft_preview_html(&self, draft_id: Uuid) -> String {
+        self.api_client
+            .get(format!(
+                "{}/admin/newsletters/drafts/{}/preview",
+                &self.address, draft_id
+            ))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_draft_test(&self, draft_id: Uuid) -> reqwest::Response {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletters/drafts/{}/test",
+                &self.address, draft_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_publish_draft<Body>(&self, draft_id: Uuid, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!(
+                "{}/admin/newsletters/drafts/{}/publish",
+                &self.address, draft_id
+            ))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_log_level_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/log-level", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_log_level(&self, directives: &str) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/admin/log-level", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(&serde_json::json!({ "directives": directives }))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_lists_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/lists", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_lists<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/lists", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn get_suppressions_html(&self) -> String {
+        self.api_client
+            .get(format!("{}/admin/suppressions", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    pub async fn post_suppressions<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/suppressions", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
+    pub async fn post_import_suppressions<Body>(&self, body: &Body) -> reqwest::Response
+    where
+        Body: serde::Serialize,
+    {
+        self.api_client
+            .post(format!("{}/admin/suppressions/import", &self.address))
+            .header("X-CSRF-Token", self.csrf_token().await)
+            .form(body)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     pub async fn post_subscriptions(&self, body: String) -> reqwest::Response {
         self.api_client
             .post(&format!("{}/subscriptions", &self.address))
@@ -151,6 +415,56 @@ impl TestApp {
             .expect("Failed to execute request.")
     }
 
+    /// The CSRF token of the current session, as embedded in the forms.
+    pub async fn csrf_token(&self) -> String {
+        let html = self.get_login_html().await;
+        let (_, rest) = html
+            .split_once(r#"name="csrf_token" value=""#)
+            .expect("No CSRF token on the login page.");
+        rest.split('"').next().unwrap().to_string()
+    }
+
+    /// The signed token of the subscribe form on the home page.
+    pub async fn get_form_token(&self) -> String {
+        let html = self
+            .api_client
+            .get(format!("{}/", &self.address))
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap();
+        let (_, rest) = html
+            .split_once(r#"name="form_token" value=""#)
+            .expect("No form token on the home page.");
+        rest.split('"').next().unwrap().to_string()
+    }
+
+    /// The metrics page, as scraped by Prometheus.
+    pub async fn get_metrics(&self) -> String {
+        self.api_client
+            .get(format!("{}/metrics", &self.address))
+            .bearer_auth(self.metrics_token.expose_secret())
+            .send()
+            .await
+            .expect("Failed to execute request.")
+            .text()
+            .await
+            .unwrap()
+    }
+
+    /// Deliver a provider event, authenticated the way Postmark does it.
+    pub async fn post_email_event(&self, event: &serde_json::Value) -> reqwest::Response {
+        self.api_client
+            .post(format!("{}/webhooks/email-events", &self.address))
+            .basic_auth("postmark", Some(self.webhook_secret.expose_secret()))
+            .json(event)
+            .send()
+            .await
+            .expect("Failed to execute request.")
+    }
+
     /// Extract the confirmation links embedded in the request to the email API.
     pub fn get_confirmation_links(&self, email_request: &wiremock::Request) -> ConfirmationLinks {
         let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
@@ -181,6 +495,8 @@ pub struct TestUser {
     pub user_id: Uuid,
     pub username: String,
     pub password: String,
+    pub email: String,
+    pub is_owner: bool,
 }
 
 impl TestUser {
@@ -197,10 +513,13 @@ impl TestUser {
             user_id: Uuid::new_v4(),
             username: Uuid::new_v4().to_string(),
             password: Uuid::new_v4().to_string(),
+            email: SafeEmail().fake(),
+            // Like the seeded admin.
+            is_owner: true,
         }
     }
 
-    async fn store(&self, db_connection: &DatabaseConnection) {
+    pub async fn store(&self, db_connection: &DatabaseConnection) {
         let salt = SaltString::generate(&mut rand::thread_rng());
         // Match parameters of the default password
         let password_hash = Argon2::new(
@@ -216,6 +535,8 @@ impl TestUser {
             user_id: Set(self.user_id),
             username: Set(self.username.clone()),
             password_hash: Set(password_hash),
+            email: Set(Some(self.email.clone())),
+            is_owner: Set(self.is_owner),
         };
         user.insert(db_connection)
             .await
@@ -225,6 +546,12 @@ impl TestUser {
 
 // Launch our application in the background
 pub async fn spawn_app() -> TestApp {
+    spawn_app_with(|_| {}).await
+}
+
+/// Launch the application with adjustments to the test configuration.
+/// Bot protection is off unless `configure` turns it back on.
+pub async fn spawn_app_with(configure: impl FnOnce(&mut Settings)) -> TestApp {
     // The first time `initialize` is invoked the code in `TRACING` is executed.
     // All other invocations will instead skip execution.
     LazyLock::force(&TRACING);
@@ -240,6 +567,9 @@ pub async fn spawn_app() -> TestApp {
         config.application.port = 0;
         // Use the mock server as email API
         config.email_client.base_url = email_server.uri();
+        // Most tests post hand-written subscribe forms, many of them in a row.
+        config.bot_protection.enabled = false;
+        configure(&mut config);
         config
     };
 
@@ -250,11 +580,17 @@ pub async fn spawn_app() -> TestApp {
         .await
         .expect("Failed to build application.");
     let application_port = application.port();
+    let redirect_port = application.redirect_port();
 
     // Got the port before spawning the application
-    let address = format!("http://127.0.0.1:{}", application_port);
+    let scheme = match configuration.application.tls {
+        Some(_) => "https",
+        None => "http",
+    };
+    let address = format!("{}://127.0.0.1:{}", scheme, application_port);
+    let shutdown = application.shutdown_token();
 
-    let _ = tokio::spawn(application.run_until_stopped());
+    let server = tokio::spawn(application.run_until_stopped());
 
     let client = reqwest::Client::builder()
         .redirect(reqwest::redirect::Policy::none())
@@ -269,6 +605,17 @@ pub async fn spawn_app() -> TestApp {
         email_server,
         test_user: TestUser::generate(),
         api_client: client,
+        email_client: configuration.email_client.client(),
+        issue_links: IssueLinks::new(
+            configuration.application.public_url(),
+            HmacSecret(configuration.application.hmac_secret),
+            configuration.tracking.enabled,
+        ),
+        webhook_secret: configuration.webhooks.secret,
+        metrics_token: configuration.metrics.token,
+        shutdown,
+        server,
+        redirect_port,
     };
 
     test_app.test_user.store(&test_app.db_connection).await;
@@ -276,16 +623,62 @@ pub async fn spawn_app() -> TestApp {
     test_app
 }
 
+/// A self-signed certificate for `localhost` and `127.0.0.1`, written to
+/// PEM files for the application to serve.
+pub struct TestCertificate {
+    pub certificate_pem: String,
+    pub certificate_path: PathBuf,
+    pub private_key_path: PathBuf,
+}
+
+impl TestCertificate {
+    pub fn generate() -> Self {
+        let directory = std::env::temp_dir().join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&directory).unwrap();
+        Self::write(directory.join("cert.pem"), directory.join("key.pem"))
+    }
+
+    /// Replace the files with a new certificate, as a renewal would.
+    pub fn renew(&self) -> Self {
+        Self::write(self.certificate_path.clone(), self.private_key_path.clone())
+    }
+
+    fn write(certificate_path: PathBuf, private_key_path: PathBuf) -> Self {
+        let rcgen::CertifiedKey { cert, signing_key } =
+            rcgen::generate_simple_self_signed(vec!["localhost".into(), "127.0.0.1".into()])
+                .unwrap();
+        std::fs::write(&certificate_path, cert.pem()).unwrap();
+        std::fs::write(&private_key_path, signing_key.serialize_pem()).unwrap();
+        Self {
+            certificate_pem: cert.pem(),
+            certificate_path,
+            private_key_path,
+        }
+    }
+
+    pub fn settings(&self) -> TlsSettings {
+        TlsSettings {
+            certificate_path: self.certificate_path.display().to_string(),
+            private_key_path: self.private_key_path.display().to_string(),
+            reload_interval_seconds: 1,
+            redirect_http_port: None,
+        }
+    }
+
+    /// A client trusting this certificate only.
+    pub fn client(&self) -> reqwest::Client {
+        let certificate = reqwest::Certificate::from_pem(self.certificate_pem.as_bytes()).unwrap();
+        reqwest::Client::builder()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(certificate)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap()
+    }
+}
+
 async fn configure_database(config: &DatabaseSettings) -> DatabaseConnection {
-    let db_pool = PgPoolOptions::new()
-        .connect_with(config.without_db())
-        .await
-        .expect("Failed to connect to the Postgres.");
-    // Create database
-    db_pool
-        .execute(format!(r#"CREATE DATABASE "{}";"#, config.database_name).as_str())
-        .await
-        .expect("Failed to create database.");
+    create_database(config).await;
     let db_pool = PgPoolOptions::new()
         .connect_with(config.with_db())
         .await
@@ -303,3 +696,78 @@ pub fn assert_is_redirect_to(response: &reqwest::Response, location: &str) {
     assert_eq!(response.status().as_u16(), 303);
     assert_eq!(response.headers().get("Location").unwrap(), location);
 }
+
+/// Create an empty database, without running the migrations.
+pub async fn create_database(config: &DatabaseSettings) {
+    let db_pool = PgPoolOptions::new()
+        .connect_with(config.without_db())
+        .await
+        .expect("Failed to connect to the Postgres.");
+    db_pool
+        .execute(format!(r#"CREATE DATABASE "{}";"#, config.database_name).as_str())
+        .await
+        .expect("Failed to create database.");
+}
+
+/// The output of everything logged on this thread while the guard
+/// is alive. Tests using it should run on the current-thread runtime, so
+/// that the application logs on the same thread.
+#[derive(Clone, Default)]
+pub struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    pub fn start() -> (Self, DefaultGuard) {
+        Self::start_with(LogFormat::Bunyan)
+    }
+
+    pub fn start_with(format: LogFormat) -> (Self, DefaultGuard) {
+        let logs = Self::default();
+        let (subscriber, _) = get_subscriber(
+            "test".into(),
+            "info".into(),
+            format,
+            false,
+            logs.clone(),
+            None,
+        );
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
+    /// One JSON object per line, with the bunyan or JSON format.
+    pub fn lines(&self) -> Vec<serde_json::Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("Not a bunyan line."))
+            .collect()
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        self.occurrences(text) > 0
+    }
+
+    pub fn occurrences(&self, text: &str) -> usize {
+        String::from_utf8_lossy(&self.0.lock().unwrap())
+            .matches(text)
+            .count()
+    }
+}
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for CapturedLogs {
+    type Writer = Self;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
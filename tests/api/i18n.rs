@@ -0,0 +1,154 @@
+use entity::entities::prelude::*;
+use sea_orm::EntityTrait;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::spawn_app;
+
+#[tokio::test]
+async fn the_locale_chosen_on_the_form_is_stored_and_used_for_the_confirmation_email() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&locale=fr";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_subscriptions(body.into()).await;
+    assert_eq!(200, response.status().as_u16());
+
+    // Assert
+    let saved = Subscriptions::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.locale, "fr");
+
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let body: serde_json::Value = serde_json::from_slice(&email_request.body).unwrap();
+    assert_eq!(body["Subject"], "Bienvenue !");
+    assert!(
+        body["TextBody"]
+            .as_str()
+            .unwrap()
+            .contains("pour confirmer votre inscription")
+    );
+}
+
+#[tokio::test]
+async fn the_accept_language_header_is_used_when_the_form_has_no_locale() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/subscriptions", &app.address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("Accept-Language", "de-CH, de;q=0.9, en;q=0.5")
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(200, response.status().as_u16());
+
+    // Assert
+    let saved = Subscriptions::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.locale, "de");
+}
+
+#[tokio::test]
+async fn unsupported_languages_fall_back_to_english() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&locale=tlh";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions(body.into()).await;
+
+    // Assert
+    let saved = Subscriptions::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(saved.locale, "en");
+}
+
+#[tokio::test]
+async fn public_pages_follow_the_accept_language_header() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for (page, language, expected) in [
+        ("/", "fr-FR,fr;q=0.9", "Bienvenue dans notre newsletter"),
+        ("/", "de", "Willkommen bei unserem Newsletter"),
+        ("/login", "fr", "Se connecter"),
+        ("/login", "es", "Login"),
+    ] {
+        // Act
+        let html = app
+            .api_client
+            .get(format!("{}{}", &app.address, page))
+            .header("Accept-Language", language)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+
+        // Assert
+        assert!(
+            html.contains(expected),
+            "{page} in {language} did not contain {expected:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn the_confirmation_page_is_shown_in_the_subscribers_language() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com&locale=de";
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app.post_subscriptions(body.into()).await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_links = app.get_confirmation_links(email_request);
+
+    // Act
+    let html = reqwest::get(confirmation_links.html)
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(html.contains("Anmeldung bestätigt"));
+    assert!(html.contains(r#"lang="de""#));
+}
@@ -0,0 +1,152 @@
+use entity::entities::{list_memberships, prelude::*};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{ConfirmationLinks, TestApp, assert_is_redirect_to, spawn_app};
+
+/// Create a list through the admin UI.
+/// The test user is left logged in.
+async fn create_list(app: &TestApp, slug: &str) {
+    app.test_user.login(app).await;
+    let response = app
+        .post_lists(&serde_json::json!({"name": slug, "slug": slug}))
+        .await;
+    assert_is_redirect_to(&response, "/admin/lists");
+}
+
+/// Subscribe to a list and return the links from its confirmation email.
+async fn subscribe_to(app: &TestApp, list: &str) -> ConfirmationLinks {
+    let _mock_guard = Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+
+    let body = format!("name=le%20guin&email=ursula_le_guin%40gmail.com&list={list}");
+    app.post_subscriptions(body)
+        .await
+        .error_for_status()
+        .unwrap();
+
+    let email_request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    app.get_confirmation_links(&email_request)
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_lists() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_lists(&serde_json::json!({"name": "Weekly", "slug": "weekly"}))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn created_lists_are_shown_on_the_lists_page() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    create_list(&app, "rust-weekly").await;
+
+    // Assert
+    let html_page = app.get_lists_html().await;
+    assert!(html_page.contains("<p><i>The list rust-weekly has been created.</i></p>"));
+    assert!(html_page.contains("<td>rust-weekly</td>"));
+}
+
+#[tokio::test]
+async fn subscribing_to_an_unknown_list_returns_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com&list=nope".into())
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn each_list_is_confirmed_separately() {
+    // Arrange
+    let app = spawn_app().await;
+    create_list(&app, "weekly").await;
+    let newsletter_links = subscribe_to(&app, "newsletter").await;
+    let _weekly_links = subscribe_to(&app, "weekly").await;
+
+    // Act
+    reqwest::get(newsletter_links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    // Assert
+    let subscribers = Subscriptions::find().all(&app.db_connection).await.unwrap();
+    assert_eq!(subscribers.len(), 1);
+    let memberships = ListMemberships::find()
+        .filter(list_memberships::Column::SubscriberId.eq(subscribers[0].id))
+        .find_also_related(Lists)
+        .all(&app.db_connection)
+        .await
+        .unwrap();
+    assert_eq!(memberships.len(), 2);
+    for (membership, list) in memberships {
+        let expected = match list.unwrap().slug.as_str() {
+            "newsletter" => "confirmed",
+            _ => "pending_confirmation",
+        };
+        assert_eq!(membership.status, expected);
+    }
+}
+
+#[tokio::test]
+async fn newsletters_are_only_delivered_to_the_targeted_lists() {
+    // Arrange
+    let app = spawn_app().await;
+    create_list(&app, "weekly").await;
+    let links = subscribe_to(&app, "weekly").await;
+    reqwest::get(links.html)
+        .await
+        .unwrap()
+        .error_for_status()
+        .unwrap();
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        // The only confirmed subscriber is not on the default list
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+            "lists": "newsletter",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+}
@@ -0,0 +1,87 @@
+use zero2prod::telemetry::LogFilter;
+
+use crate::helpers::{TestUser, assert_is_redirect_to, spawn_app};
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_change_the_log_level() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/admin/log-level", &app.address))
+        .form(&serde_json::json!({ "directives": "trace" }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+}
+
+#[tokio::test]
+async fn only_owners_can_change_the_log_level() {
+    // Arrange
+    let app = spawn_app().await;
+    let editor = TestUser {
+        is_owner: false,
+        ..TestUser::generate()
+    };
+    editor.store(&app.db_connection).await;
+    editor.login(&app).await;
+
+    // Act
+    let dashboard = app.get_admin_dashboard_html().await;
+    let page = app
+        .api_client
+        .get(format!("{}/admin/log-level", &app.address))
+        .send()
+        .await
+        .unwrap();
+    let response = app.post_log_level("trace").await;
+
+    // Assert
+    assert!(!dashboard.contains("/admin/log-level"));
+    assert_eq!(page.status().as_u16(), 403);
+    assert_eq!(response.status().as_u16(), 403);
+}
+
+#[tokio::test]
+async fn the_log_level_can_be_changed_without_a_restart() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let log_filter = LogFilter::global().unwrap();
+    let initial = log_filter.current().unwrap();
+
+    // Act
+    let response = app.post_log_level("info,zero2prod::scheduler=debug").await;
+    assert_is_redirect_to(&response, "/admin/log-level");
+    let enabled = tracing::enabled!(target: "zero2prod::scheduler", tracing::Level::DEBUG);
+    let changed = log_filter.current().unwrap();
+    let html_page = app.get_log_level_html().await;
+    log_filter.set(&initial).unwrap();
+
+    // Assert
+    assert!(enabled);
+    assert!(html_page.contains("The log level has been changed."));
+    assert!(changed.contains("zero2prod::scheduler=debug"));
+}
+
+#[tokio::test]
+async fn invalid_directives_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.post_log_level("zero2prod=loud").await;
+    assert_is_redirect_to(&response, "/admin/log-level");
+
+    // Assert
+    let html_page = app.get_log_level_html().await;
+    assert!(html_page.contains("These are not valid filter directives."));
+    let current = LogFilter::global().unwrap().current().unwrap();
+    assert!(!current.contains("loud"));
+}
@@ -1,8 +1,28 @@
 mod admin_dashboard;
+mod archive;
+mod bot_protection;
 mod change_password;
+mod csrf;
+mod database;
+mod drafts;
 mod health_check;
 mod helpers;
+mod i18n;
+mod lists;
+mod log_level;
 mod login;
+mod metrics;
+mod migrations;
 mod newsletter;
+mod personalization;
+mod request_id;
+mod scheduled_newsletters;
+mod security_headers;
+mod shutdown;
 mod subscriptions;
 mod subscriptions_confirm;
+mod suppressions;
+mod telemetry;
+mod tls;
+mod tracking;
+mod webhooks;
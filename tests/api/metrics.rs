@@ -0,0 +1,157 @@
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::spawn_app;
+use crate::newsletter::create_confirmed_subscriber;
+
+/// The value of a series, `0` when it has not been recorded yet.
+fn value(metrics: &str, series: &str) -> f64 {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map(|v| v.parse().unwrap())
+        .unwrap_or(0.0)
+}
+
+#[tokio::test]
+async fn metrics_require_the_scraper_token() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for token in [None, Some("not-the-token")] {
+        // Act
+        let mut request = app.api_client.get(format!("{}/metrics", &app.address));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await.unwrap();
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 401);
+    }
+}
+
+#[tokio::test]
+async fn requests_are_counted_per_route() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    for _ in 0..2 {
+        reqwest::get(format!("{}/health_check", &app.address))
+            .await
+            .unwrap();
+    }
+    reqwest::get(format!("{}/issues/{}", &app.address, uuid::Uuid::new_v4()))
+        .await
+        .unwrap();
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(
+        value(
+            &metrics,
+            r#"zero2prod_http_requests_total{method="GET",route="/health_check",status="200"}"#
+        ),
+        2.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            r#"zero2prod_http_request_duration_seconds_count{method="GET",route="/health_check"}"#
+        ),
+        2.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            r#"zero2prod_http_requests_total{method="GET",route="/issues/{issue_id}",status="404"}"#
+        ),
+        1.0
+    );
+}
+
+#[tokio::test]
+async fn pool_gauges_are_exported() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert!(metrics.contains(r#"zero2prod_db_pool_connections{state="idle"}"#));
+    assert!(
+        value(
+            &metrics,
+            r#"zero2prod_redis_pool_connections{state="connected"}"#
+        ) > 0.0
+    );
+}
+
+#[tokio::test]
+async fn subscriptions_confirmations_and_emails_are_counted() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    create_confirmed_subscriber(&app).await;
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(value(&metrics, "zero2prod_subscriptions_total"), 1.0);
+    assert_eq!(value(&metrics, "zero2prod_confirmations_total"), 1.0);
+    assert_eq!(
+        value(
+            &metrics,
+            r#"zero2prod_emails_sent_total{provider="postmark"}"#
+        ),
+        1.0
+    );
+}
+
+#[tokio::test]
+async fn emails_the_provider_rejects_are_counted_as_failed() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions("name=le%20guin&email=ursula_le_guin%40gmail.com".into())
+        .await;
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(
+        value(
+            &metrics,
+            r#"zero2prod_emails_failed_total{provider="postmark"}"#
+        ),
+        1.0
+    );
+    assert_eq!(value(&metrics, "zero2prod_subscriptions_total"), 0.0);
+}
+
+#[tokio::test]
+async fn idempotent_replays_are_counted() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "markdown_content": "Newsletter body",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+
+    // Act
+    app.post_newsletters(&newsletter_request_body).await;
+    app.post_newsletters(&newsletter_request_body).await;
+    let metrics = app.get_metrics().await;
+
+    // Assert
+    assert_eq!(value(&metrics, "zero2prod_idempotent_replays_total"), 1.0);
+}
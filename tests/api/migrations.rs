@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use sea_orm::sqlx::{self, Connection, PgConnection};
+use sea_orm_migration::MigrationStatus;
+use uuid::Uuid;
+use zero2prod::configuration::{DatabaseSettings, get_configuration};
+use zero2prod::migrations::{
+    MIGRATION_LOCK_KEY, MigrateCommand, migrate_on_start, migration_status,
+};
+use zero2prod::startup::get_db_connection;
+
+use crate::helpers::create_database;
+
+/// Settings for a database of its own, created empty.
+async fn empty_database() -> DatabaseSettings {
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.database.database_name = Uuid::new_v4().to_string();
+    create_database(&configuration.database).await;
+    configuration.database
+}
+
+#[tokio::test]
+async fn instances_starting_together_apply_the_migrations_once() {
+    // Arrange
+    let settings = empty_database().await;
+    let db_connection = get_db_connection(&settings);
+
+    // Act
+    let outcomes = tokio::join!(
+        migrate_on_start(&settings),
+        migrate_on_start(&settings),
+        migrate_on_start(&settings),
+    );
+
+    // Assert
+    outcomes.0.unwrap();
+    outcomes.1.unwrap();
+    outcomes.2.unwrap();
+    let status = migration_status(&db_connection).await.unwrap();
+    assert!(!status.is_empty());
+    assert!(
+        status
+            .iter()
+            .all(|(_, status)| *status == MigrationStatus::Applied)
+    );
+}
+
+#[tokio::test]
+async fn waiting_for_the_lock_is_not_cut_short_by_the_statement_timeout() {
+    // Arrange
+    let mut settings = empty_database().await;
+    settings.statement_timeout_milliseconds = Some(200);
+    let db_connection = get_db_connection(&settings);
+    let mut other_instance = PgConnection::connect_with(&settings.with_db())
+        .await
+        .unwrap();
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut other_instance)
+        .await
+        .unwrap();
+
+    // Act
+    let migrate = tokio::spawn({
+        let settings = settings.clone();
+        async move { migrate_on_start(&settings).await }
+    });
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    other_instance.close().await.unwrap();
+    let outcome = migrate.await.unwrap();
+
+    // Assert
+    outcome.unwrap();
+    let status = migration_status(&db_connection).await.unwrap();
+    assert!(
+        status
+            .iter()
+            .all(|(_, status)| *status == MigrationStatus::Applied)
+    );
+}
+
+#[tokio::test]
+async fn migrations_can_be_rolled_back_and_applied_again() {
+    // Arrange
+    let settings = empty_database().await;
+    let db_connection = get_db_connection(&settings);
+    MigrateCommand::Up { steps: None }
+        .run(&settings, &db_connection)
+        .await
+        .unwrap();
+
+    // Act
+    MigrateCommand::Down { steps: 2 }
+        .run(&settings, &db_connection)
+        .await
+        .unwrap();
+    let rolled_back = migration_status(&db_connection).await.unwrap();
+    MigrateCommand::Up { steps: Some(1) }
+        .run(&settings, &db_connection)
+        .await
+        .unwrap();
+    let reapplied = migration_status(&db_connection).await.unwrap();
+
+    // Assert
+    let pending = |status: &[(String, MigrationStatus)]| {
+        status
+            .iter()
+            .filter(|(_, status)| *status == MigrationStatus::Pending)
+            .count()
+    };
+    assert_eq!(pending(&rolled_back), 2);
+    assert_eq!(pending(&reapplied), 1);
+    assert_eq!(
+        rolled_back.last().unwrap().1,
+        MigrationStatus::Pending,
+        "The latest migration was not the one rolled back."
+    );
+}
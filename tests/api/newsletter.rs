@@ -1,7 +1,12 @@
 use crate::helpers::{ConfirmationLinks, TestApp, assert_is_redirect_to, spawn_app};
+use entity::entities::prelude::*;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
 use std::time::Duration;
-use wiremock::matchers::{any, method, path};
+use tokio_util::sync::CancellationToken;
+use wiremock::matchers::{any, body_partial_json, method, path};
 use wiremock::{Mock, ResponseTemplate};
+use zero2prod::issue_delivery::enqueue_delivery;
+use zero2prod::scheduler::resume_deliveries;
 
 #[tokio::test]
 async fn newsletters_returns_400_for_invalid_data() {
@@ -61,7 +66,7 @@ async fn newsletters_are_delivered_to_confirmed_subscribers() {
     let response = app.post_newsletters(&newsletter_request_body).await;
 
     // Assert
-    assert_eq!(response.status().as_u16(), 200);
+    assert_is_redirect_to(&response, "/admin/newsletters");
 }
 
 #[tokio::test]
@@ -92,14 +97,21 @@ async fn newsletters_are_not_delivered_to_unconfirmed_subscribers() {
     let response = app.post_newsletters(&newsletter_request_body).await;
 
     // Assert
-    assert_eq!(response.status().as_u16(), 200);
+    assert_is_redirect_to(&response, "/admin/newsletters");
     // Mock verfies on Drop thet we haven't sent the newsletter email
 }
 
 /// Use the public API of the application under testto create
 /// an unconfirmed subscriber.
 pub async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
-    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+    create_unconfirmed_subscriber_with_email(app, "ursula_le_guin@gmail.com").await
+}
+
+pub async fn create_unconfirmed_subscriber_with_email(
+    app: &TestApp,
+    email: &str,
+) -> ConfirmationLinks {
+    let body = format!("name=le%20guin&email={}", urlencoding::encode(email));
     let _mock_guard = Mock::given(path("/email"))
         .and(method("POST"))
         .respond_with(ResponseTemplate::new(200))
@@ -108,7 +120,7 @@ pub async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
         .mount_as_scoped(&app.email_server)
         .await;
 
-    app.post_subscriptions(body.into())
+    app.post_subscriptions(body)
         .await
         .error_for_status()
         .unwrap();
@@ -125,10 +137,14 @@ pub async fn create_unconfirmed_subscriber(app: &TestApp) -> ConfirmationLinks {
     app.get_confirmation_links(&email_request)
 }
 
-async fn create_confirmed_subscriber(app: &TestApp) {
+pub async fn create_confirmed_subscriber(app: &TestApp) {
+    create_confirmed_subscriber_with_email(app, "ursula_le_guin@gmail.com").await
+}
+
+pub async fn create_confirmed_subscriber_with_email(app: &TestApp, email: &str) {
     // We can reuse the same helper and just add
     // an extra step to actually call the confirmation link!
-    let confirmation_link = create_unconfirmed_subscriber(app).await;
+    let confirmation_link = create_unconfirmed_subscriber_with_email(app, email).await;
     reqwest::get(confirmation_link.html)
         .await
         .unwrap()
@@ -240,3 +256,220 @@ async fn concurrent_form_submission_is_handled_gracefully() {
 
     // Mock verifies on Drop that we have sent the newsletter email **once**
 }
+
+#[tokio::test]
+async fn retrying_a_failed_submission_does_not_create_the_issue_again() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    let response = app.post_newsletters(&newsletter_request_body).await;
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let html_page = app.get_newsletter_html().await;
+    assert!(html_page.contains("could not be delivered to every subscriber"));
+
+    // Act
+    let response = app.post_newsletters(&newsletter_request_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let issues = NewsletterIssues::find()
+        .all(&app.db_connection)
+        .await
+        .unwrap();
+    assert_eq!(issues.len(), 1);
+    // Mock verifies on Drop that the subscriber was only tried once
+}
+
+#[tokio::test]
+async fn emails_that_could_not_be_sent_are_retried_without_resending_the_others() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    create_confirmed_subscriber_with_email(&app, "octavia_butler@gmail.com").await;
+    app.test_user.login(&app).await;
+
+    let failing_guard = Mock::given(path("/email"))
+        .and(body_partial_json(
+            serde_json::json!({ "To": "octavia_butler@gmail.com" }),
+        ))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    let delivered_guard = Mock::given(path("/email"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount_as_scoped(&app.email_server)
+        .await;
+    app.post_newsletters(&serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    }))
+    .await;
+    drop(failing_guard);
+    drop(delivered_guard);
+    let issue = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "failed");
+    let html_page = app.get_published_newsletters_html().await;
+    assert!(html_page.contains("<td>failed</td>"));
+    assert!(html_page.contains("<td>1</td>"));
+
+    Mock::given(path("/email"))
+        .and(body_partial_json(
+            serde_json::json!({ "To": "octavia_butler@gmail.com" }),
+        ))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let delivered = resume_deliveries(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(delivered, 1);
+    let issue = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "published");
+}
+
+#[tokio::test]
+async fn interrupted_deliveries_are_resumed() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    app.post_newsletters(&serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339(),
+    }))
+    .await;
+    // As left by an instance that stopped right after claiming the issue.
+    let issue = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut interrupted = issue.clone().into_active_model();
+    interrupted.state = Set("sending".into());
+    interrupted.update(&app.db_connection).await.unwrap();
+    enqueue_delivery(&app.db_connection, issue.id)
+        .await
+        .unwrap();
+    let html_page = app.get_published_newsletters_html().await;
+    assert!(html_page.contains("<td>sending</td>"));
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let delivered = resume_deliveries(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(delivered, 1);
+    let issue = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "published");
+}
+
+#[tokio::test]
+async fn markdown_issues_are_delivered_as_html_and_plain_text() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .and(body_partial_json(serde_json::json!({
+            "HtmlBody": "<p>Read <a href=\"https://example.com\" rel=\"noopener noreferrer\">this</a>.</p>\n",
+            "TextBody": "Read this (https://example.com).",
+        })))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "markdown_content": "Read [this](https://example.com).",
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+}
+
+#[tokio::test]
+async fn newsletters_without_a_body_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,254 @@
+use entity::entities::prelude::*;
+use sea_orm::EntityTrait;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+async fn publish(app: &TestApp, html_content: &str, text_content: &str) -> reqwest::Response {
+    app.post_newsletters(&serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": text_content,
+        "html_content": html_content,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    }))
+    .await
+}
+
+/// The body of the last email sent through the mock Postmark server.
+async fn last_email(app: &TestApp) -> serde_json::Value {
+    let request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    serde_json::from_slice(&request.body).unwrap()
+}
+
+/// The only link in a plain-text body, pointed at the test application.
+fn get_link(app: &TestApp, text: &str) -> reqwest::Url {
+    let links: Vec<_> = linkify::LinkFinder::new()
+        .links(text)
+        .filter(|l| *l.kind() == linkify::LinkKind::Url)
+        .collect();
+    assert_eq!(links.len(), 1);
+    let mut link = reqwest::Url::parse(links[0].as_str()).unwrap();
+    assert_eq!(link.host_str().unwrap(), "127.0.0.1");
+    link.set_port(Some(app.port)).unwrap();
+    link
+}
+
+#[tokio::test]
+async fn issues_are_personalised_for_each_subscriber() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = publish(
+        &app,
+        "<p>Dear {{name}}</p>",
+        "Dear {{name}}, this was sent to {{email}}.",
+    )
+    .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    let email = last_email(&app).await;
+    assert_eq!(email["HtmlBody"], "<p>Dear le guin</p>");
+    assert_eq!(
+        email["TextBody"],
+        "Dear le guin, this was sent to ursula_le_guin@gmail.com."
+    );
+}
+
+#[tokio::test]
+async fn unknown_template_variables_are_rejected_before_sending() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = publish(&app, "<p>Dear {{first_name}}</p>", "Dear reader").await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn the_unsubscribe_link_stops_further_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+    publish(&app, "<p>Hi</p>", "Unsubscribe: {{unsubscribe_url}}").await;
+    let unsubscribe_link = get_link(&app, last_email(&app).await["TextBody"].as_str().unwrap());
+
+    // Act - Part 1 - Follow the link
+    let response = reqwest::get(unsubscribe_link.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Act - Part 2 - Confirm
+    let form: Vec<(String, String)> = unsubscribe_link.query_pairs().into_owned().collect();
+    let response = reqwest::Client::new()
+        .post(format!("{}/subscriptions/unsubscribe", app.address))
+        .form(&form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Assert - the mock only allows the first issue to be sent
+    publish(&app, "<p>Hi again</p>", "Hi again").await;
+}
+
+#[tokio::test]
+async fn a_tampered_unsubscribe_link_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let link = app
+        .issue_links
+        .unsubscribe_url(uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    let mut link = reqwest::Url::parse(&link).unwrap();
+    link.set_port(Some(app.port)).unwrap();
+    let tampered = link.as_str().replace("tag=", "tag=00");
+    let response = reqwest::get(tampered).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 401);
+}
+
+#[tokio::test]
+async fn published_issues_have_a_web_view() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    publish(
+        &app,
+        "<p>Read online at {{web_view_url}}</p>",
+        "Read online at {{web_view_url}}",
+    )
+    .await;
+    let web_view_link = get_link(&app, last_email(&app).await["TextBody"].as_str().unwrap());
+
+    // Act
+    let response = reqwest::get(web_view_link).await.unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(response.text().await.unwrap().contains("Read online at"));
+}
+
+#[tokio::test]
+async fn the_web_view_is_shown_in_the_language_of_the_reader() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    publish(
+        &app,
+        "{{#if (eq locale \"fr\")}}<p>Bonjour</p>{{else}}<p>Hello</p>{{/if}}",
+        "Read online at {{web_view_url}}",
+    )
+    .await;
+    let web_view_link = get_link(&app, last_email(&app).await["TextBody"].as_str().unwrap());
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(web_view_link)
+        .header("Accept-Language", "fr-CA, en;q=0.5")
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    let html_page = response.text().await.unwrap();
+    assert!(html_page.contains("<p>Bonjour</p>"));
+}
+
+/// Save a draft with the given bodies and return its id.
+async fn create_draft(app: &TestApp, html_content: &str, text_content: &str) -> uuid::Uuid {
+    app.post_drafts(&serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": text_content,
+        "html_content": html_content,
+    }))
+    .await;
+    NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+}
+
+#[tokio::test]
+async fn the_preview_is_personalised_like_a_test_email() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Dear {{name}},</p>", "Dear {{name}},").await;
+
+    // Act
+    let html_page = app.get_draft_preview_html(draft_id).await;
+
+    // Assert
+    assert!(html_page.contains(&format!("Dear {},", app.test_user.username)));
+    assert!(!html_page.contains("{{name}}"));
+}
+
+#[tokio::test]
+async fn the_preview_shows_template_errors() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let draft_id = create_draft(&app, "<p>Dear {{nickname}},</p>", "Dear {{name}},").await;
+
+    // Act
+    let html_page = app.get_draft_preview_html(draft_id).await;
+
+    // Assert
+    assert!(html_page.contains("Failed to render the HTML body"));
+    assert!(!html_page.contains("<iframe"));
+}
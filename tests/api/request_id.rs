@@ -0,0 +1,107 @@
+use uuid::Uuid;
+
+use crate::helpers::{CapturedLogs, spawn_app};
+
+#[tokio::test]
+async fn a_request_id_is_generated_when_none_is_sent() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/health/live", &app.address))
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let request_id = response.headers()["X-Request-Id"].to_str().unwrap();
+    assert!(Uuid::parse_str(request_id).is_ok());
+}
+
+#[tokio::test]
+async fn the_request_id_sent_by_the_client_is_echoed() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", &app.address))
+        .header("X-Request-Id", "edge-1234")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.headers()["X-Request-Id"], "edge-1234");
+}
+
+#[tokio::test]
+async fn an_unsafe_request_id_is_replaced() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::Client::new()
+        .get(format!("{}/health/live", &app.address))
+        .header("X-Request-Id", "<script>alert(1)</script>")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let request_id = response.headers()["X-Request-Id"].to_str().unwrap();
+    assert!(Uuid::parse_str(request_id).is_ok());
+}
+
+#[tokio::test]
+async fn every_log_line_of_a_request_carries_its_id() {
+    // Arrange
+    let (logs, _guard) = CapturedLogs::start();
+    let app = spawn_app().await;
+
+    // Act
+    app.api_client
+        .post(format!("{}/subscriptions", &app.address))
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .header("X-Request-Id", "subscribe-1234")
+        .body("name=le%20guin&email=")
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    let lines: Vec<_> = logs
+        .lines()
+        .into_iter()
+        .filter(|line| line["request_id"] == "subscribe-1234")
+        .collect();
+    assert!(lines.len() > 1);
+    assert!(
+        lines
+            .iter()
+            .any(|line| line["msg"] == "[ADDING A NEW SUBSCRIBER - START]")
+    );
+}
+
+#[tokio::test]
+async fn requests_are_access_logged() {
+    // Arrange
+    let (logs, _guard) = CapturedLogs::start();
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app.get_admin_dashboard().await;
+
+    // Assert
+    let request_id = response.headers()["X-Request-Id"].to_str().unwrap();
+    let access_log = logs
+        .lines()
+        .into_iter()
+        .find(|line| line["target"] == "access_log" && line["request_id"] == request_id)
+        .expect("The request was not logged.");
+    assert_eq!(access_log["http.request.method"], "GET");
+    assert_eq!(access_log["http.route"], "/admin/dashboard");
+    assert_eq!(access_log["http.response.status_code"], 200);
+    assert!(access_log["latency_ms"].is_u64());
+    assert_eq!(access_log["user_id"], app.test_user.user_id.to_string());
+}
@@ -0,0 +1,336 @@
+use chrono::{Duration, FixedOffset, SubsecRound, Utc};
+use entity::entities::{newsletter_issues, prelude::*};
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::trace::TracerProvider;
+use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+use tracing_subscriber::Registry;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod::scheduler::promote_due_issues;
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+const TRACE_ID: &str = "0af7651916cd43dd8448eb211c80319c";
+
+/// Schedule an issue an hour from now and return it.
+async fn schedule_issue(app: &TestApp) -> newsletter_issues::Model {
+    let send_at = (Utc::now() + Duration::hours(1)).to_rfc3339();
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+            "send_at": send_at,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+
+    NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .expect("The issue was not stored.")
+}
+
+/// Pretend the send time of an issue has passed.
+async fn make_due(app: &TestApp, issue: newsletter_issues::Model) {
+    let mut issue = issue.into_active_model();
+    issue.send_at = Set(Some((Utc::now() - Duration::minutes(1)).into()));
+    issue.update(&app.db_connection).await.unwrap();
+}
+
+#[tokio::test]
+async fn scheduled_issues_are_stored_and_not_sent_right_away() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let issue = schedule_issue(&app).await;
+
+    // Assert
+    assert_eq!(issue.state, "scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("The newsletter issue has been scheduled for"));
+    assert!(html_page.contains(&issue.id.to_string()));
+}
+
+#[tokio::test]
+async fn due_issues_are_delivered_by_the_scheduler() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue = schedule_issue(&app).await;
+    make_due(&app, issue.clone()).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let delivered = promote_due_issues(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(delivered, 1);
+    let issue = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "published");
+    assert!(issue.published_at.is_some());
+}
+
+#[tokio::test]
+async fn due_issues_are_delivered_within_the_trace_they_were_scheduled_in() {
+    // Arrange
+    let tracer = TracerProvider::builder().build().tracer("test");
+    let subscriber = Registry::default().with(tracing_opentelemetry::layer().with_tracer(tracer));
+    let _guard = tracing::subscriber::set_default(subscriber);
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act - Part 1 - Schedule the issue from within a trace
+    let response = app
+        .api_client
+        .post(format!("{}/admin/newsletters", &app.address))
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .header(
+            "traceparent",
+            format!("00-{}-b7ad6b7169203331-01", TRACE_ID),
+        )
+        .form(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+            "send_at": (Utc::now() + Duration::hours(1)).to_rfc3339(),
+        }))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let issue = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(issue.trace_context.as_deref().unwrap().contains(TRACE_ID));
+
+    // Act - Part 2 - Deliver it from the scheduler's own trace
+    make_due(&app, issue).await;
+    promote_due_issues(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &CancellationToken::new(),
+    )
+    .instrument(tracing::info_span!("scheduler"))
+    .await
+    .unwrap();
+
+    // Assert
+    let email_requests = app.email_server.received_requests().await.unwrap();
+    let email_request = email_requests.last().unwrap();
+    let traceparent = email_request.headers["traceparent"].to_str().unwrap();
+    assert!(traceparent.contains(TRACE_ID));
+}
+
+#[tokio::test]
+async fn scheduled_issues_can_be_rescheduled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue = schedule_issue(&app).await;
+    let send_at = (Utc::now() + Duration::days(1))
+        .trunc_subsecs(0)
+        .with_timezone(&FixedOffset::east_opt(2 * 3600).unwrap());
+
+    // Act
+    let response = app
+        .post_reschedule_newsletter(issue.id, &send_at.to_rfc3339())
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("The issue has been rescheduled for"));
+    let issue = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "scheduled");
+    assert_eq!(issue.send_at, Some(send_at));
+}
+
+#[tokio::test]
+async fn rescheduling_to_a_time_in_the_past_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let issue = schedule_issue(&app).await;
+
+    // Act
+    let response = app
+        .post_reschedule_newsletter(issue.id, &(Utc::now() - Duration::hours(1)).to_rfc3339())
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("is in the past."));
+    let stored = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(stored.send_at, issue.send_at);
+}
+
+#[tokio::test]
+async fn cancelled_issues_are_not_delivered() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue = schedule_issue(&app).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app.post_cancel_newsletter(issue.id).await;
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    make_due(&app, issue).await;
+    let delivered = promote_due_issues(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &CancellationToken::new(),
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(delivered, 0);
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("<p><i>The issue has been cancelled.</i></p>"));
+}
+
+#[tokio::test]
+async fn due_issues_are_left_scheduled_once_shutdown_has_started() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    let issue = schedule_issue(&app).await;
+    make_due(&app, issue.clone()).await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+    let shutdown = CancellationToken::new();
+    shutdown.cancel();
+
+    // Act
+    let delivered = promote_due_issues(
+        &app.db_connection,
+        &app.email_client,
+        &app.issue_links,
+        &shutdown,
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert_eq!(delivered, 0);
+    let issue = NewsletterIssues::find_by_id(issue.id)
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(issue.state, "scheduled");
+}
+
+#[tokio::test]
+async fn a_send_time_in_the_past_is_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string(),
+            "send_at": (Utc::now() - Duration::hours(1)).to_rfc3339(),
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn resubmitting_a_scheduled_issue_says_it_was_scheduled() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let newsletter_request_body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": "<p>Newsletter body as HTML</p>",
+        "idempotency_key": uuid::Uuid::new_v4().to_string(),
+        "send_at": (Utc::now() + Duration::hours(1)).to_rfc3339(),
+    });
+    app.post_newsletters(&newsletter_request_body).await;
+    app.get_scheduled_newsletters_html().await;
+
+    // Act
+    let response = app.post_newsletters(&newsletter_request_body).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters/scheduled");
+    let html_page = app.get_scheduled_newsletters_html().await;
+    assert!(html_page.contains("The newsletter issue has been scheduled."));
+    assert!(!html_page.contains("published"));
+}
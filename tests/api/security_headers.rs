@@ -0,0 +1,155 @@
+use crate::helpers::{spawn_app, spawn_app_with};
+
+fn csp_nonce(policy: &str) -> String {
+    let (_, rest) = policy
+        .split_once("'nonce-")
+        .expect("No nonce in the policy.");
+    rest.split('\'').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn responses_carry_the_security_headers() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = reqwest::get(format!("{}/", &app.address)).await.unwrap();
+
+    // Assert
+    let headers = response.headers();
+    assert_eq!(headers["x-content-type-options"], "nosniff");
+    assert_eq!(headers["x-frame-options"], "DENY");
+    assert_eq!(
+        headers["referrer-policy"],
+        "strict-origin-when-cross-origin"
+    );
+    assert!(headers.contains_key("permissions-policy"));
+    assert!(!headers.contains_key("strict-transport-security"));
+    assert!(!headers.contains_key("content-security-policy-report-only"));
+    let policy = headers["content-security-policy"].to_str().unwrap();
+    assert!(policy.ends_with("report-uri /csp-reports"));
+}
+
+#[tokio::test]
+async fn the_csp_nonce_is_available_to_templates() {
+    // Arrange
+    let app = spawn_app().await;
+
+    for _ in 0..2 {
+        // Act
+        let response = reqwest::get(format!("{}/", &app.address)).await.unwrap();
+        let policy = response.headers()["content-security-policy"]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let html = response.text().await.unwrap();
+
+        // Assert
+        let nonce = csp_nonce(&policy);
+        assert!(html.contains(&format!(r#"<style nonce="{}">"#, nonce)));
+    }
+}
+
+#[tokio::test]
+async fn each_response_gets_its_own_nonce() {
+    // Arrange
+    let app = spawn_app().await;
+    let policy = || async {
+        let response = reqwest::get(format!("{}/login", &app.address))
+            .await
+            .unwrap();
+        response.headers()["content-security-policy"]
+            .to_str()
+            .unwrap()
+            .to_owned()
+    };
+
+    // Act
+    let first = policy().await;
+    let second = policy().await;
+
+    // Assert
+    assert_ne!(csp_nonce(&first), csp_nonce(&second));
+}
+
+#[tokio::test]
+async fn the_policy_can_be_tried_in_report_only_mode_and_hsts_enabled() {
+    // Arrange
+    let app = spawn_app_with(|config| {
+        config.application.security_headers.csp_report_only = true;
+        config.application.security_headers.hsts_max_age_seconds = Some(600);
+    })
+    .await;
+
+    // Act
+    let response = reqwest::get(format!("{}/login", &app.address))
+        .await
+        .unwrap();
+
+    // Assert
+    let headers = response.headers();
+    assert!(headers.contains_key("content-security-policy-report-only"));
+    assert!(!headers.contains_key("content-security-policy"));
+    assert_eq!(
+        headers["strict-transport-security"],
+        "max-age=600; includeSubDomains"
+    );
+}
+
+#[tokio::test]
+async fn violation_reports_are_accepted_in_both_formats() {
+    // Arrange
+    let app = spawn_app().await;
+    let legacy = serde_json::json!({
+        "csp-report": {
+            "document-uri": "http://127.0.0.1/",
+            "violated-directive": "script-src",
+            "blocked-uri": "inline"
+        }
+    });
+    let reporting_api = serde_json::json!([{
+        "type": "csp-violation",
+        "body": {
+            "documentURL": "http://127.0.0.1/",
+            "effectiveDirective": "style-src",
+            "blockedURL": "inline",
+            "disposition": "report"
+        }
+    }]);
+
+    for (content_type, report) in [
+        ("application/csp-report", legacy),
+        ("application/reports+json", reporting_api),
+    ] {
+        // Act
+        let response = app
+            .api_client
+            .post(format!("{}/csp-reports", &app.address))
+            .header("Content-Type", content_type)
+            .body(report.to_string())
+            .send()
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 204);
+    }
+}
+
+#[tokio::test]
+async fn malformed_violation_reports_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/csp-reports", &app.address))
+        .body("not json")
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
@@ -0,0 +1,92 @@
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{spawn_app, spawn_app_with};
+
+const SUBSCRIPTION: &str = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+#[tokio::test]
+async fn readiness_reports_down_once_shutdown_has_started() {
+    // Arrange
+    let app = spawn_app_with(|config| config.shutdown.drain_delay_seconds = 5).await;
+
+    // Act
+    app.shutdown.cancel();
+    let readiness = reqwest::get(format!("{}/health/ready", &app.address))
+        .await
+        .expect("Failed to execute request.");
+    let liveness = reqwest::get(format!("{}/health/live", &app.address))
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(readiness.status().as_u16(), 503);
+    let body: serde_json::Value = readiness.json().await.unwrap();
+    assert_eq!(body["status"], "down");
+    assert_eq!(body["shutting_down"], true);
+    assert_eq!(liveness.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn requests_in_flight_complete_before_the_server_stops() {
+    // Arrange
+    let app = spawn_app().await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+        .expect(1)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let in_flight = tokio::spawn({
+        let client = app.api_client.clone();
+        let url = format!("{}/subscriptions", &app.address);
+        async move {
+            client
+                .post(url)
+                .header("Content-Type", "application/x-www-form-urlencoded")
+                .body(SUBSCRIPTION)
+                .send()
+                .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    app.shutdown.cancel();
+    let response = in_flight.await.unwrap().expect("The request was dropped.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(app.server.await.unwrap().is_ok());
+    let after_shutdown = reqwest::get(format!("{}/health/live", &app.address)).await;
+    assert!(after_shutdown.is_err());
+}
+
+#[tokio::test]
+async fn the_server_stops_once_the_grace_period_is_over() {
+    // Arrange
+    let app = spawn_app_with(|config| config.shutdown.grace_period_seconds = 1).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    tokio::spawn(
+        reqwest::Client::new()
+            .post(format!("{}/subscriptions", &app.address))
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(SUBSCRIPTION)
+            .send(),
+    );
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    app.shutdown.cancel();
+    let outcome = tokio::time::timeout(Duration::from_secs(3), app.server)
+        .await
+        .expect("The grace period was not enforced.");
+
+    // Assert
+    assert!(outcome.unwrap().is_ok());
+}
@@ -4,7 +4,7 @@ use sea_orm::EntityTrait;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, ResponseTemplate};
 
-use crate::helpers::spawn_app;
+use crate::helpers::{CapturedLogs, spawn_app};
 
 #[tokio::test]
 async fn subscribe_returns_a_200_for_valid_form_data() {
@@ -100,6 +100,43 @@ async fn subscribe_returns_a_400_when_fields_are_present_but_invalid() {
     }
 }
 
+#[tokio::test]
+async fn subscribe_rejects_addresses_against_the_email_policy_with_a_reason() {
+    // Arrange
+    let app = spawn_app().await;
+    let test_cases = vec![
+        (
+            "name=Ursula&email=ursula%40mailinator.com",
+            "Addresses at mailinator.com are disposable.",
+        ),
+        (
+            "name=Ursula&email=noreply%40example.com",
+            "noreply@ addresses belong to a role rather than a person.",
+        ),
+    ];
+
+    for (body, reason) in test_cases {
+        // Act
+        let response = app.post_subscriptions(body.into()).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 400);
+        let message = response.text().await.unwrap();
+        assert!(
+            message.starts_with(reason),
+            "Unexpected rejection reason: {}",
+            message
+        );
+    }
+    assert!(
+        Subscriptions::find()
+            .one(&app.db_connection)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
 #[tokio::test]
 async fn subscribe_sends_a_configuration_email_for_valid_data() {
     // Arrange
@@ -163,3 +200,77 @@ async fn subscribe_fails_if_there_is_a_fatal_database_error() {
     // Assert
     assert_eq!(response.status().as_u16(), 500);
 }
+
+#[tokio::test]
+async fn subscriber_details_are_redacted_in_the_logs() {
+    // Arrange
+    let (logs, _guard) = CapturedLogs::start();
+    let app = spawn_app().await;
+    let body = "name=le%20guin&email=ursula_le_guin%40gmail.com";
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    app.post_subscriptions(body.into()).await;
+    let email_request = &app.email_server.received_requests().await.unwrap()[0];
+    let confirmation_link = app.get_confirmation_links(email_request).html;
+    let response = reqwest::get(confirmation_link.clone()).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+
+    // Assert
+    assert!(logs.contains("u***@gmail.com"));
+    assert!(!logs.contains("ursula_le_guin@gmail.com"));
+    assert!(!logs.contains("ursula_le_guin%40gmail.com"));
+    assert!(!logs.contains("le guin"));
+    let (_, token) = confirmation_link
+        .query_pairs()
+        .find(|(name, _)| name == "subscription_token")
+        .unwrap();
+    assert!(!logs.contains(&token));
+}
+
+#[tokio::test]
+async fn admin_and_unsubscribe_details_are_redacted_in_the_logs() {
+    // Arrange
+    let (logs, _guard) = CapturedLogs::start();
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let unsubscribe_link = app
+        .issue_links
+        .unsubscribe_url(uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    let unsubscribe_link = reqwest::Url::parse(&unsubscribe_link).unwrap();
+    let form: Vec<(String, String)> = unsubscribe_link.query_pairs().into_owned().collect();
+    let (_, tag) = form.iter().find(|(name, _)| name == "tag").unwrap();
+
+    // Act
+    app.post_suppressions(&serde_json::json!({
+        "entry": "suppressed_reader@example.com",
+        "reason": "Complained",
+    }))
+    .await;
+    app.api_client
+        .post(format!("{}/admin/email", &app.address))
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .form(&[("email", "new_admin_address@example.com")])
+        .send()
+        .await
+        .unwrap();
+    reqwest::Client::new()
+        .post(format!("{}/subscriptions/unsubscribe", app.address))
+        .form(&form)
+        .send()
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(logs.contains("s***@example.com"));
+    assert!(logs.contains("n***@example.com"));
+    assert!(!logs.contains("suppressed_reader"));
+    assert!(!logs.contains("new_admin_address"));
+    assert!(logs.contains("UNSUBSCRIBE A SUBSCRIBER"));
+    assert!(!logs.contains(tag));
+}
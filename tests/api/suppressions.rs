@@ -0,0 +1,332 @@
+use entity::entities::{prelude::*, subscriptions, suppressions};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use wiremock::matchers::{any, method, path};
+use wiremock::{Mock, ResponseTemplate};
+use zero2prod::domain::SubscriberEmail;
+use zero2prod::email_client::SendEmailError;
+use zero2prod::suppression::SuppressionList;
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+async fn suppress(app: &TestApp, entry: &str, reason: &str) {
+    let response = app
+        .post_suppressions(&serde_json::json!({
+            "entry": entry,
+            "reason": reason,
+        }))
+        .await;
+    assert_is_redirect_to(&response, "/admin/suppressions");
+}
+
+async fn count_entries(app: &TestApp) -> u64 {
+    Suppressions::find()
+        .count(&app.db_connection)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn you_must_be_logged_in_to_manage_the_suppression_list() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_suppressions(&serde_json::json!({
+            "entry": "jane@example.com",
+            "reason": "Asked by phone",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/login");
+    assert_eq!(count_entries(&app).await, 0);
+}
+
+#[tokio::test]
+async fn entries_record_their_reason_and_author() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+
+    // Act
+    suppress(&app, "Jane@Example.com", "Asked by phone").await;
+
+    // Assert
+    let entry = Suppressions::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(entry.entry, "jane@example.com");
+    assert_eq!(entry.kind, "email");
+    assert_eq!(entry.reason, "Asked by phone");
+    assert_eq!(entry.added_by, Some(app.test_user.user_id));
+    let html_page = app.get_suppressions_html().await;
+    assert!(html_page.contains("jane@example.com has been suppressed."));
+    assert!(html_page.contains(&format!("<td>{}</td>", app.test_user.username)));
+}
+
+#[tokio::test]
+async fn entries_must_be_valid_and_have_a_reason() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    let test_cases = [
+        (
+            serde_json::json!({ "entry": "not an address", "reason": "Spam" }),
+            "not an address is not a valid email address or domain.",
+        ),
+        (
+            serde_json::json!({ "entry": "jane@example.com", "reason": " " }),
+            "Every entry needs a reason.",
+        ),
+    ];
+
+    for (body, error_message) in test_cases {
+        // Act
+        let response = app.post_suppressions(&body).await;
+
+        // Assert
+        assert_is_redirect_to(&response, "/admin/suppressions");
+        assert!(app.get_suppressions_html().await.contains(error_message));
+    }
+    assert_eq!(count_entries(&app).await, 0);
+}
+
+#[tokio::test]
+async fn suppressed_domains_cannot_subscribe() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "gmail.com", "Legal request").await;
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_subscriptions("name=le%20guin&email=Ursula_le_guin%40GMAIL.com".into())
+        .await;
+
+    // Assert
+    // The suppression list is not disclosed.
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        Subscriptions::find()
+            .filter(subscriptions::Column::Name.eq("le guin"))
+            .count(&app.db_connection)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn subscribers_at_a_suppressed_domain_do_not_receive_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    suppress(&app, "@gmail.com", "Legal request").await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    // Mock verifies on Drop that we haven't sent the newsletter email
+}
+
+#[tokio::test]
+async fn the_email_client_refuses_suppressed_recipients() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "jane@example.com", "Asked by phone").await;
+    let email_client = app
+        .email_client
+        .clone()
+        .with_suppression_list(SuppressionList::new(app.db_connection.clone()));
+
+    Mock::given(any())
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let outcome = email_client
+        .send_email(
+            &SubscriberEmail::parse("jane@example.com".into()).unwrap(),
+            "Subject",
+            "<p>Body</p>",
+            "Body",
+        )
+        .await;
+
+    // Assert
+    assert!(matches!(outcome, Err(SendEmailError::Suppressed)));
+}
+
+#[tokio::test]
+async fn entries_can_be_removed() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "jane@example.com", "Asked by phone").await;
+    let entry = Suppressions::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!(
+            "{}/admin/suppressions/{}/delete",
+            &app.address, entry.id
+        ))
+        .header("X-CSRF-Token", app.csrf_token().await)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    assert_eq!(count_entries(&app).await, 0);
+}
+
+#[tokio::test]
+async fn entries_can_be_imported() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "jane@example.com", "Asked by phone").await;
+
+    // Act
+    let response = app
+        .post_import_suppressions(&serde_json::json!({
+            "entries": "jane@example.com\nbob@example.com,\"Support ticket 12, closed\"\n\nexample.org\nnot valid",
+            "reason": "Migrated from the old system",
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/suppressions");
+    let html_page = app.get_suppressions_html().await;
+    assert!(html_page.contains("2 entries imported, 1 were already on the list."));
+    assert!(html_page.contains("1 lines were skipped."));
+    let bob = Suppressions::find()
+        .filter(suppressions::Column::Entry.eq("bob@example.com"))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(bob.reason, "Support ticket 12, closed");
+    let domain = Suppressions::find()
+        .filter(suppressions::Column::Entry.eq("example.org"))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(domain.kind, "domain");
+    assert_eq!(domain.reason, "Migrated from the old system");
+}
+
+#[tokio::test]
+async fn exports_can_be_imported_back() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "jane@example.com", "Asked by phone, twice").await;
+    suppress(&app, "example.org", "Legal request").await;
+
+    // Act - Part 1 - Export
+    let response = app
+        .api_client
+        .get(format!("{}/admin/suppressions/export", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(
+        response.headers().get("Content-Type").unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let csv = response.text().await.unwrap();
+    assert!(csv.starts_with("entry,kind,reason,added_by,created_at\n"));
+    assert!(csv.contains("jane@example.com,email,\"Asked by phone, twice\","));
+
+    // Act - Part 2 - Import into a fresh list
+    Suppressions::delete_many()
+        .exec(&app.db_connection)
+        .await
+        .unwrap();
+    app.post_import_suppressions(&serde_json::json!({ "entries": csv }))
+        .await;
+
+    // Assert
+    let jane = Suppressions::find()
+        .filter(suppressions::Column::Entry.eq("jane@example.com"))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(jane.reason, "Asked by phone, twice");
+    assert_eq!(count_entries(&app).await, 2);
+}
+
+#[tokio::test]
+async fn exports_with_multi_line_reasons_can_be_imported_back() {
+    // Arrange
+    let app = spawn_app().await;
+    app.test_user.login(&app).await;
+    suppress(&app, "jane@example.com", "Called twice.\nThen wrote.").await;
+    suppress(&app, "example.org", "Legal request").await;
+    let csv = app
+        .api_client
+        .get(format!("{}/admin/suppressions/export", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+        .text()
+        .await
+        .unwrap();
+    Suppressions::delete_many()
+        .exec(&app.db_connection)
+        .await
+        .unwrap();
+
+    // Act - Pasted into the form, which sends line breaks as `\r\n`
+    app.post_import_suppressions(&serde_json::json!({ "entries": csv.replace('\n', "\r\n") }))
+        .await;
+
+    // Assert
+    let jane = Suppressions::find()
+        .filter(suppressions::Column::Entry.eq("jane@example.com"))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(jane.reason, "Called twice.\nThen wrote.");
+    assert_eq!(count_entries(&app).await, 2);
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use tracing_subscriber::Registry;
+use tracing_subscriber::layer::SubscriberExt;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use zero2prod::configuration::{TelemetrySettings, get_configuration};
+use zero2prod::telemetry::{LogFormat, OtlpExporter, Redaction};
+
+use crate::helpers::CapturedLogs;
+
+/// Stands in for an OpenTelemetry collector, accepting every export.
+async fn collector() -> MockServer {
+    let collector = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&collector)
+        .await;
+    collector
+}
+
+fn settings(collector: &MockServer, sampling_ratio: f64) -> TelemetrySettings {
+    TelemetrySettings {
+        service_name: "zero2prod-under-test".into(),
+        otlp_endpoint: Some(collector.uri()),
+        sampling_ratio,
+        resource_attributes: BTreeMap::from([("deployment.environment".into(), "test".into())]),
+        redaction: Redaction::Mask,
+    }
+}
+
+/// Record a span with an event in it, then flush everything.
+async fn record_and_export(otlp: OtlpExporter) {
+    let subscriber = Registry::default().with(otlp.layer());
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("exported-span", subscriber_id = 42);
+        span.in_scope(|| tracing::info!(confirmed = true, "exported-event"));
+    });
+    tokio::task::spawn_blocking(move || otlp.shutdown())
+        .await
+        .unwrap();
+}
+
+/// The bodies of the exports received on `signal_path`. Protobuf keeps
+/// strings as they are, so they can be looked for in the raw bytes.
+async fn exports(collector: &MockServer, signal_path: &str) -> Vec<Vec<u8>> {
+    collector
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path() == signal_path)
+        .map(|request| request.body)
+        .collect()
+}
+
+fn contains(export: &[u8], text: &str) -> bool {
+    export
+        .windows(text.len())
+        .any(|window| window == text.as_bytes())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn spans_are_exported_with_the_service_resource() {
+    // Arrange
+    let collector = collector().await;
+    let otlp = OtlpExporter::new(&settings(&collector, 1.0))
+        .unwrap()
+        .expect("The exporter is configured.");
+
+    // Act
+    record_and_export(otlp).await;
+
+    // Assert
+    let exports = exports(&collector, "/v1/traces").await;
+    assert_eq!(exports.len(), 1);
+    let export = &exports[0];
+    assert!(contains(export, "exported-span"));
+    assert!(contains(export, "subscriber_id"));
+    assert!(contains(export, "zero2prod-under-test"));
+    assert!(contains(export, "deployment.environment"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn events_are_exported_as_logs() {
+    // Arrange
+    let collector = collector().await;
+    let otlp = OtlpExporter::new(&settings(&collector, 1.0))
+        .unwrap()
+        .expect("The exporter is configured.");
+
+    // Act
+    record_and_export(otlp).await;
+
+    // Assert
+    let exports = exports(&collector, "/v1/logs").await;
+    assert_eq!(exports.len(), 1);
+    assert!(contains(&exports[0], "exported-event"));
+    assert!(contains(&exports[0], "confirmed"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn no_trace_is_exported_when_sampling_is_off() {
+    // Arrange
+    let collector = collector().await;
+    let otlp = OtlpExporter::new(&settings(&collector, 0.0))
+        .unwrap()
+        .expect("The exporter is configured.");
+
+    // Act
+    record_and_export(otlp).await;
+
+    // Assert
+    assert!(exports(&collector, "/v1/traces").await.is_empty());
+}
+
+#[tokio::test]
+async fn nothing_is_exported_without_an_endpoint() {
+    let mut configuration = get_configuration().expect("Failed to read configuration.");
+    configuration.telemetry.otlp_endpoint = None;
+
+    assert!(
+        OtlpExporter::new(&configuration.telemetry)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[test]
+fn resource_attribute_names_are_read_as_they_are_written() {
+    let configuration = get_configuration().expect("Failed to read configuration.");
+
+    assert_eq!(
+        configuration
+            .telemetry
+            .resource_attributes
+            .get("deployment.environment")
+            .map(String::as_str),
+        Some("local")
+    );
+}
+
+#[test]
+fn each_event_is_written_once() {
+    for format in [
+        LogFormat::Pretty,
+        LogFormat::Compact,
+        LogFormat::Bunyan,
+        LogFormat::Json,
+    ] {
+        let (logs, _guard) = CapturedLogs::start_with(format);
+        tracing::info!("written-once");
+        assert_eq!(logs.occurrences("written-once"), 1, "{:?}", format);
+    }
+}
+
+#[test]
+fn the_pretty_format_is_not_coloured_outside_a_terminal() {
+    let (logs, _guard) = CapturedLogs::start_with(LogFormat::Pretty);
+    tracing::info!(coloured = false, "plain");
+    assert!(logs.contains("plain"));
+    assert!(!logs.contains("\x1b["));
+}
+
+#[test]
+fn the_json_format_has_one_object_per_event() {
+    let (logs, _guard) = CapturedLogs::start_with(LogFormat::Json);
+    tracing::info_span!("outer", subscriber_id = 42).in_scope(|| {
+        tracing::info!(confirmed = true, "json-event");
+    });
+    let lines = logs.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["message"], "json-event");
+    assert_eq!(lines[0]["confirmed"], true);
+    assert_eq!(lines[0]["spans"][0]["subscriber_id"], 42);
+}
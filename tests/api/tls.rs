@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use zero2prod::configuration::TlsSettings;
+
+use crate::helpers::{TestCertificate, spawn_app_with};
+
+#[tokio::test]
+async fn the_api_is_served_over_https() {
+    // Arrange
+    let certificate = TestCertificate::generate();
+    let app = spawn_app_with(|config| config.application.tls = Some(certificate.settings())).await;
+
+    // Act
+    let response = certificate
+        .client()
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert!(app.address.starts_with("https://"));
+    assert_eq!(response.status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn plain_http_is_not_answered_on_the_https_port() {
+    // Arrange
+    let certificate = TestCertificate::generate();
+    let app = spawn_app_with(|config| config.application.tls = Some(certificate.settings())).await;
+
+    // Act
+    let response = reqwest::get(format!("http://127.0.0.1:{}/health/live", app.port)).await;
+
+    // Assert
+    assert!(response.is_err());
+}
+
+#[tokio::test]
+async fn the_certificate_is_reloaded_when_its_files_change() {
+    // Arrange
+    let certificate = TestCertificate::generate();
+    let app = spawn_app_with(|config| config.application.tls = Some(certificate.settings())).await;
+
+    // Act
+    let renewed = certificate.renew();
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    // Assert
+    let url = format!("{}/health/live", &app.address);
+    let response = renewed.client().get(&url).send().await;
+    assert_eq!(response.unwrap().status().as_u16(), 200);
+    assert!(certificate.client().get(&url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn an_invalid_certificate_is_not_loaded() {
+    // Arrange
+    let certificate = TestCertificate::generate();
+    let app = spawn_app_with(|config| config.application.tls = Some(certificate.settings())).await;
+
+    // Act
+    std::fs::write(&certificate.certificate_path, "not a certificate").unwrap();
+    tokio::time::sleep(Duration::from_millis(2500)).await;
+
+    // Assert
+    let response = certificate
+        .client()
+        .get(format!("{}/health/live", &app.address))
+        .send()
+        .await;
+    assert_eq!(response.unwrap().status().as_u16(), 200);
+}
+
+#[tokio::test]
+async fn plain_http_is_redirected_to_https() {
+    // Arrange
+    let certificate = TestCertificate::generate();
+    let app = spawn_app_with(|config| {
+        config.application.tls = Some(TlsSettings {
+            redirect_http_port: Some(0),
+            ..certificate.settings()
+        })
+    })
+    .await;
+    let redirect_port = app.redirect_port.expect("No redirect listener.");
+
+    // Act
+    let response = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(format!(
+            "http://127.0.0.1:{}/subscriptions/confirm?subscription_token=abc",
+            redirect_port
+        ))
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 308);
+    assert_eq!(
+        response.headers().get("Location").unwrap(),
+        &format!(
+            "https://127.0.0.1:{}/subscriptions/confirm?subscription_token=abc",
+            app.port
+        )
+    );
+}
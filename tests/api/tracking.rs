@@ -0,0 +1,180 @@
+use entity::entities::{prelude::*, tracking_events};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+async fn arrange() -> TestApp {
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&app.email_server)
+        .await;
+    app
+}
+
+async fn publish(app: &TestApp, track: bool) -> reqwest::Response {
+    let mut body = serde_json::json!({
+        "title": "Newsletter title",
+        "text_content": "Newsletter body as plain text",
+        "html_content": r#"<p>Read <a href="https://example.com/post?a=1&amp;b=2">this</a></p>"#,
+        "idempotency_key": uuid::Uuid::new_v4().to_string()
+    });
+    if track {
+        body["track"] = "true".into();
+    }
+    app.post_newsletters(&body).await
+}
+
+/// The HTML body of the last email sent through the mock Postmark server.
+async fn last_html_body(app: &TestApp) -> String {
+    let request = app
+        .email_server
+        .received_requests()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    body["HtmlBody"].as_str().unwrap().to_owned()
+}
+
+/// The path of the first tracking link of the given kind in an HTML body.
+fn tracking_path(html: &str, kind: &str) -> String {
+    let prefix = format!("/t/{}/", kind);
+    let start = html.find(&prefix).expect("No tracking link found.");
+    let end = start + html[start..].find('"').unwrap();
+    html[start..end].to_owned()
+}
+
+async fn get(app: &TestApp, path: &str) -> reqwest::Response {
+    app.api_client
+        .get(format!("{}{}", &app.address, path))
+        .send()
+        .await
+        .expect("Failed to execute request.")
+}
+
+async fn count_events(app: &TestApp, kind: &str) -> u64 {
+    TrackingEvents::find()
+        .filter(tracking_events::Column::Kind.eq(kind))
+        .count(&app.db_connection)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn tracked_issues_have_their_links_rewritten_and_a_pixel_added() {
+    // Arrange
+    let app = arrange().await;
+
+    // Act
+    publish(&app, true).await;
+
+    // Assert
+    let html = last_html_body(&app).await;
+    assert!(!html.contains("https://example.com/post"));
+    assert!(html.contains("/t/c/"));
+    assert!(html.contains(r#"width="1" height="1""#));
+    assert!(html.contains("/t/o/"));
+}
+
+#[tokio::test]
+async fn untracked_issues_are_sent_as_written() {
+    // Arrange
+    let app = arrange().await;
+
+    // Act
+    publish(&app, false).await;
+
+    // Assert
+    let html = last_html_body(&app).await;
+    assert!(html.contains(r#"href="https://example.com/post?a=1&amp;b=2""#));
+    assert!(!html.contains("/t/c/"));
+    assert!(!html.contains("/t/o/"));
+}
+
+#[tokio::test]
+async fn following_a_tracked_link_records_a_click_and_redirects() {
+    // Arrange
+    let app = arrange().await;
+    publish(&app, true).await;
+    let click_path = tracking_path(&last_html_body(&app).await, "c");
+
+    // Act
+    let response = get(&app, &click_path).await;
+
+    // Assert
+    assert_is_redirect_to(&response, "https://example.com/post?a=1&b=2");
+    assert_eq!(count_events(&app, "click").await, 1);
+}
+
+#[tokio::test]
+async fn loading_the_pixel_records_an_open() {
+    // Arrange
+    let app = arrange().await;
+    publish(&app, true).await;
+    let open_path = tracking_path(&last_html_body(&app).await, "o");
+
+    // Act
+    let response = get(&app, &open_path).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers().get("Content-Type").unwrap(), "image/gif");
+    assert_eq!(count_events(&app, "open").await, 1);
+}
+
+#[tokio::test]
+async fn tampered_tracking_links_are_rejected() {
+    // Arrange
+    let app = arrange().await;
+    publish(&app, true).await;
+    let click_path = tracking_path(&last_html_body(&app).await, "c");
+    let (payload, tag) = click_path.split_once('.').unwrap();
+    let tampered = format!("{}A.{}", payload, tag);
+
+    // Act
+    let response = get(&app, &tampered).await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 404);
+    assert_eq!(count_events(&app, "click").await, 0);
+}
+
+#[tokio::test]
+async fn the_report_counts_opens_and_clicks() {
+    // Arrange
+    let app = arrange().await;
+    publish(&app, true).await;
+    let html = last_html_body(&app).await;
+    get(&app, &tracking_path(&html, "o")).await;
+    get(&app, &tracking_path(&html, "o")).await;
+    get(&app, &tracking_path(&html, "c")).await;
+    let issue = NewsletterIssues::find()
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let report = get(
+        &app,
+        &format!("/admin/newsletters/published/{}/report", issue.id),
+    )
+    .await
+    .text()
+    .await
+    .unwrap();
+
+    // Assert
+    let report: String = report.split_whitespace().collect();
+    assert!(report.contains("<td>Opens</td><td>2</td><td>1</td>"));
+    assert!(report.contains("<td>Clicks</td><td>1</td><td>1</td>"));
+    assert!(report.contains("<td>https://example.com/post?a&#x3D;1&amp;b&#x3D;2</td><td>1</td>"));
+}
@@ -0,0 +1,228 @@
+use entity::entities::{prelude::*, suppressions};
+use hmac::{Hmac, Mac};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use secrecy::ExposeSecret;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+use crate::helpers::{TestApp, assert_is_redirect_to, spawn_app};
+use crate::newsletter::create_confirmed_subscriber;
+
+fn hard_bounce(email: &str) -> serde_json::Value {
+    serde_json::json!({
+        "RecordType": "Bounce",
+        "Type": "HardBounce",
+        "TypeCode": 1,
+        "Email": email,
+        "BouncedAt": "2025-05-01T09:00:00Z"
+    })
+}
+
+async fn suppression_reason(app: &TestApp, email: &str) -> Option<String> {
+    Suppressions::find()
+        .filter(suppressions::Column::Entry.eq(email))
+        .one(&app.db_connection)
+        .await
+        .unwrap()
+        .map(|s| s.reason)
+}
+
+#[tokio::test]
+async fn a_hard_bounce_suppresses_the_address() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_email_event(&hard_bounce("Bounced@Example.com"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        suppression_reason(&app, "bounced@example.com").await,
+        Some("hard_bounce".into())
+    );
+}
+
+#[tokio::test]
+async fn a_spam_complaint_suppresses_the_address() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_email_event(&serde_json::json!({
+            "RecordType": "SpamComplaint",
+            "Type": "SpamComplaint",
+            "Email": "annoyed@example.com"
+        }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        suppression_reason(&app, "annoyed@example.com").await,
+        Some("spam_complaint".into())
+    );
+}
+
+#[tokio::test]
+async fn soft_bounces_and_other_events_are_ignored() {
+    // Arrange
+    let app = spawn_app().await;
+    let events = [
+        serde_json::json!({
+            "RecordType": "Bounce",
+            "Type": "SoftBounce",
+            "Email": "full-mailbox@example.com"
+        }),
+        serde_json::json!({
+            "RecordType": "Delivery",
+            "Recipient": "happy@example.com"
+        }),
+    ];
+
+    for event in events {
+        // Act
+        let response = app.post_email_event(&event).await;
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 200);
+    }
+    assert_eq!(
+        Suppressions::find()
+            .count(&app.db_connection)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
+#[tokio::test]
+async fn repeated_events_for_an_address_are_accepted() {
+    // Arrange
+    let app = spawn_app().await;
+    app.post_email_event(&hard_bounce("bounced@example.com"))
+        .await;
+
+    // Act
+    let response = app
+        .post_email_event(&hard_bounce("bounced@example.com"))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(
+        Suppressions::find()
+            .count(&app.db_connection)
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn events_without_valid_credentials_are_rejected() {
+    // Arrange
+    let app = spawn_app().await;
+    let url = format!("{}/webhooks/email-events", &app.address);
+    let requests = [
+        app.api_client.post(&url),
+        app.api_client
+            .post(&url)
+            .basic_auth("postmark", Some("wrong-secret")),
+        app.api_client
+            .post(&url)
+            .header("X-Webhook-Signature", "00ff"),
+    ];
+
+    for request in requests {
+        // Act
+        let response = request
+            .json(&hard_bounce("bounced@example.com"))
+            .send()
+            .await
+            .expect("Failed to execute request.");
+
+        // Assert
+        assert_eq!(response.status().as_u16(), 401);
+    }
+    assert_eq!(suppression_reason(&app, "bounced@example.com").await, None);
+}
+
+#[tokio::test]
+async fn signed_events_are_accepted() {
+    // Arrange
+    let app = spawn_app().await;
+    let body = serde_json::to_vec(&hard_bounce("bounced@example.com")).unwrap();
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_from_slice(app.webhook_secret.expose_secret().as_bytes())
+            .unwrap();
+    mac.update(&body);
+    let signature = hex::encode(mac.finalize().into_bytes());
+
+    // Act
+    let response = app
+        .api_client
+        .post(format!("{}/webhooks/email-events", &app.address))
+        .header("Content-Type", "application/json")
+        .header("X-Webhook-Signature", signature)
+        .body(body)
+        .send()
+        .await
+        .expect("Failed to execute request.");
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 200);
+    assert!(
+        suppression_reason(&app, "bounced@example.com")
+            .await
+            .is_some()
+    );
+}
+
+#[tokio::test]
+async fn malformed_events_are_rejected_with_a_400() {
+    // Arrange
+    let app = spawn_app().await;
+
+    // Act
+    let response = app
+        .post_email_event(&serde_json::json!({ "RecordType": "Bounce" }))
+        .await;
+
+    // Assert
+    assert_eq!(response.status().as_u16(), 400);
+}
+
+#[tokio::test]
+async fn suppressed_subscribers_do_not_receive_issues() {
+    // Arrange
+    let app = spawn_app().await;
+    create_confirmed_subscriber(&app).await;
+    app.test_user.login(&app).await;
+    app.post_email_event(&hard_bounce("ursula_le_guin@gmail.com"))
+        .await;
+
+    Mock::given(path("/email"))
+        .and(method("POST"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(&app.email_server)
+        .await;
+
+    // Act
+    let response = app
+        .post_newsletters(&serde_json::json!({
+            "title": "Newsletter title",
+            "text_content": "Newsletter body as plain text",
+            "html_content": "<p>Newsletter body as HTML</p>",
+            "idempotency_key": uuid::Uuid::new_v4().to_string()
+        }))
+        .await;
+
+    // Assert
+    assert_is_redirect_to(&response, "/admin/newsletters");
+    // Mock verifies on Drop that we haven't sent the newsletter email
+}